@@ -0,0 +1,234 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::byte_rope::RopeDelta;
+use crate::window_edit::WindowEdit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// File offsets of the annotated bytes
+    pub range: Range<usize>,
+    pub text: String,
+    /// A color name or `#rrggbb`, as in themes
+    pub color: Option<String>,
+}
+
+/// Comments on byte ranges. They're stored next to the file, in
+/// `<file>.tofu-notes`, so they can be kept together with it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    annotations: Vec<Annotation>,
+}
+
+impl Annotations {
+    pub fn sidecar_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".tofu-notes");
+        PathBuf::from(name)
+    }
+
+    pub fn add(&mut self, annotation: Annotation) {
+        self.annotations.push(annotation);
+        self.annotations
+            .sort_by_key(|annotation| (annotation.range.start, annotation.range.end));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Annotation> {
+        self.annotations.iter()
+    }
+
+    fn innermost_index(&self, offset: usize) -> Option<usize> {
+        self.annotations
+            .iter()
+            .enumerate()
+            .filter(|(_, annotation)| annotation.range.contains(&offset))
+            .min_by_key(|(_, annotation)| annotation.range.len())
+            .map(|(i, _)| i)
+    }
+
+    /// The innermost annotation around file offset `offset`.
+    pub fn at(&self, offset: usize) -> Option<&Annotation> {
+        self.innermost_index(offset).map(|i| &self.annotations[i])
+    }
+
+    pub fn remove_at(&mut self, offset: usize) -> Option<Annotation> {
+        self.innermost_index(offset)
+            .map(|i| self.annotations.remove(i))
+    }
+
+    /// Moves the ranges along with an edit of the loaded window, the way
+    /// selections move: both ends follow the bytes they're on. Annotations
+    /// whose bytes were all deleted go away.
+    pub fn apply_delta(&mut self, delta: &RopeDelta, window_start: usize, window_len: usize) {
+        let mut edit = WindowEdit::new(delta, window_start, window_len);
+        for annotation in self.annotations.iter_mut() {
+            annotation.range = edit.transform_range(annotation.range.clone());
+        }
+        self.annotations.retain(|annotation| !annotation.range.is_empty());
+    }
+
+    /// A small subset of TOML: one `[[annotation]]` table per annotation with
+    /// `start`, `end`, `text` and optionally `color`. Incomplete entries are skipped.
+    pub fn parse(text: &str) -> Annotations {
+        fn finish(fields: &mut Vec<(String, String)>, annotations: &mut Annotations) {
+            let get = |key: &str| {
+                fields
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.clone())
+            };
+            let offset = |key: &str| {
+                get(key).and_then(|v| match v.strip_prefix("0x") {
+                    Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                    None => v.parse().ok(),
+                })
+            };
+            if let (Some(start), Some(end), Some(text)) = (offset("start"), offset("end"), get("text")) {
+                if start < end {
+                    annotations.add(Annotation {
+                        range: start..end,
+                        text,
+                        color: get("color"),
+                    });
+                }
+            }
+            fields.clear();
+        }
+
+        let mut annotations = Annotations::default();
+        let mut fields = vec![];
+        for line in text.lines().map(str::trim) {
+            if line == "[[annotation]]" {
+                finish(&mut fields, &mut annotations);
+            } else if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(quoted) => unescape(quoted),
+                    None => value.to_string(),
+                };
+                fields.push((key.trim().to_string(), value));
+            }
+        }
+        finish(&mut fields, &mut annotations);
+        annotations
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = String::from("# tofu annotations\n");
+        for annotation in &self.annotations {
+            text += "\n[[annotation]]\n";
+            text += &format!("start = 0x{:x}\n", annotation.range.start);
+            text += &format!("end = 0x{:x}\n", annotation.range.end);
+            if let Some(color) = &annotation.color {
+                text += &format!("color = \"{}\"\n", escape(color));
+            }
+            text += &format!("text = \"{}\"\n", escape(&annotation.text));
+        }
+        text
+    }
+
+    pub fn load(path: &Path) -> Annotations {
+        fs::read_to_string(Annotations::sidecar_path(path))
+            .map(|text| Annotations::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Writes the sidecar file, unless there's nothing to write and no file yet.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let sidecar = Annotations::sidecar_path(path);
+        if self.is_empty() && !sidecar.exists() {
+            return Ok(());
+        }
+        fs::write(sidecar, self.serialize())
+    }
+
+    /// Single-line listing for the info line.
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "no annotations".into();
+        }
+        self.iter()
+            .map(|annotation| {
+                format!(
+                    "{:x}..{:x} {}",
+                    annotation.range.start, annotation.range.end, annotation.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('\\' | '"'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xi_rope::{DeltaBuilder, Interval};
+
+    use crate::byte_rope::Rope;
+
+    #[test]
+    fn test_annotations() {
+        let mut annotations = Annotations::default();
+        annotations.add(Annotation {
+            range: 0x100..0x110,
+            text: "header \"v2\"".into(),
+            color: Some("yellow".into()),
+        });
+        annotations.add(Annotation {
+            range: 0x104..0x108,
+            text: "checksum".into(),
+            color: None,
+        });
+        assert_eq!(Annotations::parse(&annotations.serialize()), annotations);
+        assert_eq!(annotations.at(0x105).unwrap().text, "checksum");
+        assert_eq!(annotations.at(0x10f).unwrap().color.as_deref(), Some("yellow"));
+        assert!(annotations.at(0x110).is_none());
+
+        // Window 0x100..0x120, insert 2 bytes at window offset 2
+        let mut builder = DeltaBuilder::new(0x20);
+        builder.replace(Interval::new(2, 2), Rope::from(vec![0u8; 2]).into_node());
+        annotations.apply_delta(&builder.build(), 0x100, 0x20);
+        let ranges: Vec<_> = annotations.iter().map(|a| a.range.clone()).collect();
+        assert_eq!(ranges, [0x100..0x112, 0x106..0x10a]);
+
+        assert_eq!(annotations.remove_at(0x107).unwrap().text, "checksum");
+        assert_eq!(annotations.at(0x107).unwrap().text, "header \"v2\"");
+
+        // Deleting the end of one annotation and all of another
+        annotations.add(Annotation {
+            range: 0x114..0x118,
+            text: "trailer".into(),
+            color: None,
+        });
+        let mut builder = DeltaBuilder::new(0x22);
+        builder.delete(Interval::new(0x10, 0x19));
+        annotations.apply_delta(&builder.build(), 0x100, 0x22);
+        assert_eq!(annotations.iter().count(), 1);
+        assert_eq!(annotations.at(0x100).unwrap().range, 0x100..0x110);
+    }
+}
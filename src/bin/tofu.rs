@@ -1,8 +1,11 @@
 #![deny(clippy::all)]
 use std::fs::File;
-use std::io::Read;
+use std::io::{IsTerminal, Read};
 use std::io::{stdout, BufWriter};
+use tofu::hex_view::dump::{dump_file, parse_offset};
+use tofu::hex_view::theme::Theme;
 use tofu::hex_view::view::HexView;
+use tofu::hex_view::OutputColorizer;
 use tofu::{CurrentBuffer, BuffrCollection};
 use std::fs::OpenOptions;
 use std::time::SystemTime;
@@ -32,7 +35,6 @@ fn debug_log(message: &str) {
             if log_path.exists() {
                 // If the path exists, attempt to open the file for writing
                 if let Ok(mut file) = OpenOptions::new()
-                    .write(true)
                     .append(true)
                     .open(&log_path) {
 
@@ -53,10 +55,64 @@ fn main() {
 
     let stdout = stdout();
     let mut stdout = BufWriter::with_capacity(STDOUT_BUF, stdout.lock());
-    let filename = std::env::args().nth(1);
+    let mut filename = None;
+    let mut theme_name = None;
+    let mut diff_target = None;
+    let mut dump = false;
+    let mut dump_offset = None;
+    let mut dump_length = None;
+    let mut color = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--theme" => theme_name = args.next(),
+            "--dump" => dump = true,
+            "--offset" => dump_offset = args.next(),
+            "--length" => dump_length = args.next(),
+            "--color" => color = args.next(),
+            "-d" | "--diff" => {
+                filename = args.next();
+                diff_target = args.next();
+            }
+            _ => filename = Some(arg),
+        }
+    }
+
+    let theme = Theme::for_terminal(theme_name.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    if dump {
+        let fail = |message: String| -> ! {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        };
+        let filename = filename.unwrap_or_else(|| fail("usage: tofu --dump [--offset X] [--length N] FILE".into()));
+        let offset = dump_offset.map_or(Ok(0), |offset| parse_offset(&offset)).unwrap_or_else(|e| fail(e));
+        let length = dump_length.map(|length| parse_offset(&length).unwrap_or_else(|e| fail(e)));
+        let colored = match color.as_deref() {
+            None | Some("auto") => std::io::stdout().is_terminal(),
+            Some("always") => true,
+            Some("never") => false,
+            Some(other) => fail(format!("--color takes auto, always or never, not {}", other)),
+        };
+        let colorizer = if colored {
+            OutputColorizer::with_theme(theme)
+        } else {
+            OutputColorizer::plain()
+        };
+        if let Err(e) = dump_file(&mut stdout, filename.as_ref(), offset, length, &colorizer) {
+            // A closed pipe (e.g. `| head`) is not an error worth reporting
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                fail(format!("{}: {}", filename, e));
+            }
+        }
+        return;
+    }
     
     // Load only a window_chunk
-    let buffr_collection = filename
+    let mut buffr_collection = filename
         .as_ref()
         .map(|filename| {
             debug_log(&format!("Attempting to load file: {:?}", filename));
@@ -82,7 +138,13 @@ fn main() {
                 Some(filename),
             ))
         })
-        .unwrap_or_else(BuffrCollection::new);
+        .unwrap_or_default();
+    if let Some(diff_target) = diff_target {
+        if let Err(e) = buffr_collection.current_mut().compare_with(diff_target.as_ref()) {
+            eprintln!("can't compare with {}: {}", diff_target, e);
+            std::process::exit(1);
+        }
+    }
 
     /*
     Original, loads whole file
@@ -98,7 +160,8 @@ fn main() {
     //     .unwrap_or_else(BuffrCollection::new);
         
         
-    let view = HexView::with_buffr_collection(buffr_collection);
+    let mut view = HexView::with_buffr_collection(buffr_collection);
+    view.set_theme(theme);
 
     view.run_event_loop(&mut stdout).unwrap();
 }
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::convert::From;
 use std::fmt;
+use std::io::{self, Read};
 use xi_rope::delta::*;
 use xi_rope::interval::*;
 use xi_rope::multiset::Subset;
@@ -105,7 +106,7 @@ impl Rope {
         self.0.len() == 0
     }
 
-    pub fn iter_chunks<T: IntervalBounds>(&self, range: T) -> ChunkIter {
+    pub fn iter_chunks<T: IntervalBounds>(&self, range: T) -> ChunkIter<'_> {
         let Interval { start, end } = range.into_interval(self.0.len());
         ChunkIter {
             cursor: Cursor::new(&self.0, start),
@@ -113,7 +114,7 @@ impl Rope {
         }
     }
 
-    pub fn slice_to_cow<T: IntervalBounds>(&self, range: T) -> Cow<[u8]> {
+    pub fn slice_to_cow<T: IntervalBounds>(&self, range: T) -> Cow<'_, [u8]> {
         let mut iter = self.iter_chunks(range);
         let first = iter.next();
         let second = iter.next();
@@ -132,6 +133,24 @@ impl Rope {
         }
     }
 
+    /// The bytes in `range`, sharing storage with `self`.
+    pub fn slice<T: IntervalBounds>(&self, range: T) -> Rope {
+        Rope(self.0.subseq(range))
+    }
+
+    /// Everything `reader` yields, read straight into leaves.
+    pub fn read_from(mut reader: impl Read) -> io::Result<Rope> {
+        let mut builder = TreeBuilder::new();
+        loop {
+            let mut leaf = Vec::with_capacity(MAX_LEAF);
+            (&mut reader).take(MAX_LEAF as u64).read_to_end(&mut leaf)?;
+            if leaf.is_empty() {
+                return Ok(Rope(builder.build()));
+            }
+            builder.push_leaf(Bytes(leaf));
+        }
+    }
+
     pub fn apply_delta(&self, delta: &RopeDelta) -> Rope {
         Rope(delta.apply(&self.0))
     }
@@ -172,7 +191,7 @@ impl From<Rope> for Vec<u8> {
     }
 }
 
-impl<'a> From<&'a Rope> for Vec<u8> {
+impl From<&Rope> for Vec<u8> {
     fn from(rope: &Rope) -> Self {
         rope.iter_chunks(..).fold(vec![], |mut acc, x| {
             acc.extend_from_slice(x);
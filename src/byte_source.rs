@@ -0,0 +1,91 @@
+use std::cmp;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::current_buffer::BufferSnapshot;
+use crate::CurrentBuffer;
+
+/// Random-access bytes addressed by file offset; reads past the end return
+/// fewer bytes. Lets parsers work on files larger than the loaded window.
+pub trait ByteSource {
+    fn len(&self) -> u64;
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+
+    /// Like `read_at`, but fails unless all `len` bytes are there. Lengths
+    /// running past the end fail before anything is read.
+    fn read_exact_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let past_end = io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("{} bytes at {:x} run past the end of the data", len, offset),
+        );
+        if offset.checked_add(len as u64).is_none_or(|end| end > self.len()) {
+            return Err(past_end);
+        }
+        let bytes = self.read_at(offset, len)?;
+        if bytes.len() < len {
+            return Err(past_end);
+        }
+        Ok(bytes)
+    }
+}
+
+pub struct FileSource {
+    file: File,
+    len: u64,
+}
+
+impl FileSource {
+    pub fn open(path: &Path) -> io::Result<FileSource> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(FileSource { file, len })
+    }
+}
+
+impl ByteSource for FileSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        file.take(len as u64).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// The buffer as it would be written: loaded window from memory, the rest from disk.
+impl ByteSource for CurrentBuffer {
+    fn len(&self) -> u64 {
+        self.logical_len()
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.read_range(offset as usize, len)
+    }
+}
+
+impl ByteSource for BufferSnapshot {
+    fn len(&self) -> u64 {
+        self.logical_len()
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.read_range(offset as usize, len)
+    }
+}
+
+impl ByteSource for Vec<u8> {
+    fn len(&self) -> u64 {
+        self.as_slice().len() as u64
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let start = cmp::min(offset as usize, self.as_slice().len());
+        let end = cmp::min(start.saturating_add(len), self.as_slice().len());
+        Ok(self[start..end].to_vec())
+    }
+}
@@ -0,0 +1,305 @@
+use crate::digest::{Md5, Sha1, Sha256};
+
+/// CRC-32 as used by zlib, PNG and GPT (reflected polynomial 0xedb88320).
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32(0xffff_ffff)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = CRC32_TABLE[((self.0 ^ byte as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+/// Parameters of a CRC, as in the usual catalogues. Reflected CRCs process
+/// bits least significant first.
+#[derive(Debug)]
+struct CrcParams {
+    name: &'static str,
+    width: u32,
+    poly: u32,
+    init: u32,
+    reflected: bool,
+    xor_out: u32,
+}
+
+const CRCS: &[CrcParams] = &[
+    CrcParams { name: "crc8", width: 8, poly: 0x07, init: 0, reflected: false, xor_out: 0 },
+    CrcParams { name: "crc8-maxim", width: 8, poly: 0x31, init: 0, reflected: true, xor_out: 0 },
+    CrcParams { name: "crc16", width: 16, poly: 0x8005, init: 0, reflected: true, xor_out: 0 },
+    CrcParams { name: "crc16-modbus", width: 16, poly: 0x8005, init: 0xffff, reflected: true, xor_out: 0 },
+    CrcParams { name: "crc16-ccitt", width: 16, poly: 0x1021, init: 0xffff, reflected: false, xor_out: 0 },
+    CrcParams { name: "crc16-xmodem", width: 16, poly: 0x1021, init: 0, reflected: false, xor_out: 0 },
+    CrcParams { name: "crc16-kermit", width: 16, poly: 0x1021, init: 0, reflected: true, xor_out: 0 },
+    CrcParams { name: "crc32", width: 32, poly: 0x04c1_1db7, init: !0, reflected: true, xor_out: !0 },
+    CrcParams { name: "crc32c", width: 32, poly: 0x1edc_6f41, init: !0, reflected: true, xor_out: !0 },
+    CrcParams { name: "crc32-bzip2", width: 32, poly: 0x04c1_1db7, init: !0, reflected: false, xor_out: !0 },
+    CrcParams { name: "crc32-mpeg2", width: 32, poly: 0x04c1_1db7, init: !0, reflected: false, xor_out: 0 },
+];
+
+/// Any CRC of `CRCS`, table driven.
+#[derive(Debug, Clone)]
+pub struct Crc {
+    params: &'static CrcParams,
+    table: Vec<u32>,
+    value: u32,
+}
+
+impl Crc {
+    fn new(params: &'static CrcParams) -> Crc {
+        let mask = u32::MAX >> (32 - params.width);
+        let table = (0..256u32)
+            .map(|i| {
+                if params.reflected {
+                    let poly = params.poly.reverse_bits() >> (32 - params.width);
+                    (0..8).fold(i, |crc, _| if crc & 1 == 1 { (crc >> 1) ^ poly } else { crc >> 1 })
+                } else {
+                    let top = 1 << (params.width - 1);
+                    (0..8).fold(i << (params.width - 8), |crc, _| {
+                        if crc & top != 0 {
+                            ((crc << 1) ^ params.poly) & mask
+                        } else {
+                            (crc << 1) & mask
+                        }
+                    })
+                }
+            })
+            .collect();
+        Crc {
+            params,
+            table,
+            value: params.init & mask,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        let shift = self.params.width - 8;
+        let mask = u32::MAX >> (32 - self.params.width);
+        for &byte in bytes {
+            self.value = if self.params.reflected {
+                self.table[((self.value ^ byte as u32) & 0xff) as usize] ^ (self.value >> 8)
+            } else {
+                let index = ((self.value >> shift) ^ byte as u32) & 0xff;
+                (self.table[index as usize] ^ (self.value << 8)) & mask
+            };
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.value ^ self.params.xor_out
+    }
+}
+
+pub const HASH_NAMES: &str = "crc8 crc8-maxim crc16 crc16-modbus crc16-ccitt crc16-xmodem \
+     crc16-kermit crc32 crc32c crc32-bzip2 crc32-mpeg2 adler32 fletcher16 fletcher32 \
+     md5 sha1 sha256 sum8 sum32 xor8";
+
+/// Computes one of `HASH_NAMES` over bytes given in any number of pieces.
+#[derive(Debug, Clone)]
+pub enum Hasher {
+    Crc(Crc),
+    Adler32 { a: u32, b: u32 },
+    Fletcher16 { a: u16, b: u16 },
+    /// Over little endian 16-bit words, the last one padded with a zero
+    Fletcher32 { a: u32, b: u32, odd_byte: Option<u8> },
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sum8(u8),
+    Sum32(u32),
+    Xor8(u8),
+}
+
+impl Hasher {
+    pub fn new(name: &str) -> Option<Hasher> {
+        if let Some(params) = CRCS.iter().find(|params| params.name == name) {
+            return Some(Hasher::Crc(Crc::new(params)));
+        }
+        Some(match name {
+            "adler32" => Hasher::Adler32 { a: 1, b: 0 },
+            "fletcher16" => Hasher::Fletcher16 { a: 0, b: 0 },
+            "fletcher32" => Hasher::Fletcher32 { a: 0, b: 0, odd_byte: None },
+            "md5" => Hasher::Md5(Md5::new()),
+            "sha1" => Hasher::Sha1(Sha1::new()),
+            "sha256" => Hasher::Sha256(Sha256::new()),
+            "sum8" => Hasher::Sum8(0),
+            "sum32" => Hasher::Sum32(0),
+            "xor8" => Hasher::Xor8(0),
+            _ => return None,
+        })
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Crc(crc) => crc.update(bytes),
+            Hasher::Adler32 { a, b } => {
+                // Sums stay below 2^32 for this many bytes before reducing
+                for chunk in bytes.chunks(5552) {
+                    for &byte in chunk {
+                        *a += byte as u32;
+                        *b += *a;
+                    }
+                    *a %= 65521;
+                    *b %= 65521;
+                }
+            }
+            Hasher::Fletcher16 { a, b } => {
+                for &byte in bytes {
+                    *a = (*a + byte as u16) % 255;
+                    *b = (*b + *a) % 255;
+                }
+            }
+            Hasher::Fletcher32 { a, b, odd_byte } => {
+                let mut add = |word: u16| {
+                    *a = (*a + word as u32) % 65535;
+                    *b = (*b + *a) % 65535;
+                };
+                let mut bytes = bytes;
+                if let (Some(low), Some((&high, rest))) = (*odd_byte, bytes.split_first()) {
+                    add(u16::from_le_bytes([low, high]));
+                    *odd_byte = None;
+                    bytes = rest;
+                }
+                let mut words = bytes.chunks_exact(2);
+                for word in &mut words {
+                    add(u16::from_le_bytes([word[0], word[1]]));
+                }
+                if let [last] = words.remainder() {
+                    *odd_byte = Some(*last);
+                }
+            }
+            Hasher::Md5(md5) => md5.update(bytes),
+            Hasher::Sha1(sha1) => sha1.update(bytes),
+            Hasher::Sha256(sha256) => sha256.update(bytes),
+            Hasher::Sum8(sum) => {
+                *sum = bytes.iter().fold(*sum, |sum, &byte| sum.wrapping_add(byte));
+            }
+            Hasher::Sum32(sum) => {
+                *sum = bytes.iter().fold(*sum, |sum, &byte| sum.wrapping_add(byte as u32));
+            }
+            Hasher::Xor8(xor) => *xor = bytes.iter().fold(*xor, |xor, byte| xor ^ byte),
+        }
+    }
+
+    /// The checksum as bytes, numbers big endian and as wide as their type.
+    pub fn finish_bytes(mut self) -> Vec<u8> {
+        if let Hasher::Fletcher32 { odd_byte: Some(_), .. } = self {
+            self.update(&[0]);
+        }
+        match self {
+            Hasher::Crc(crc) => {
+                crc.finish().to_be_bytes()[4 - crc.params.width as usize / 8..].to_vec()
+            }
+            Hasher::Adler32 { a, b } | Hasher::Fletcher32 { a, b, .. } => {
+                (b << 16 | a).to_be_bytes().to_vec()
+            }
+            Hasher::Fletcher16 { a, b } => vec![b as u8, a as u8],
+            Hasher::Md5(md5) => md5.finish(),
+            Hasher::Sha1(sha1) => sha1.finish(),
+            Hasher::Sha256(sha256) => sha256.finish(),
+            Hasher::Sum8(sum) | Hasher::Xor8(sum) => vec![sum],
+            Hasher::Sum32(sum) => sum.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// The checksum in hex, as `finish_bytes`.
+    pub fn finish(self) -> String {
+        self.finish_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_hashers() {
+        let hash = |name: &str, bytes: &[u8]| {
+            let mut hasher = Hasher::new(name).unwrap();
+            let (first, second) = bytes.split_at(bytes.len() / 2 + 1);
+            hasher.update(first);
+            hasher.update(second);
+            hasher.finish()
+        };
+        let checks = [
+            ("crc8", "f4"),
+            ("crc8-maxim", "a1"),
+            ("crc16", "bb3d"),
+            ("crc16-modbus", "4b37"),
+            ("crc16-ccitt", "29b1"),
+            ("crc16-xmodem", "31c3"),
+            ("crc16-kermit", "2189"),
+            ("crc32", "cbf43926"),
+            ("crc32c", "e3069283"),
+            ("crc32-bzip2", "fc891918"),
+            ("crc32-mpeg2", "0376e6e7"),
+            ("adler32", "091e01de"),
+            ("sum8", "dd"),
+            ("xor8", "31"),
+        ];
+        for (name, check) in checks {
+            assert_eq!(hash(name, b"123456789"), check, "{}", name);
+        }
+        assert_eq!(hash("fletcher16", b"abcde"), "c8f0");
+        assert_eq!(hash("fletcher32", b"abcde"), "f04fc729");
+        assert_eq!(hash("md5", b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert!(Hasher::new("crc64").is_none());
+    }
+}
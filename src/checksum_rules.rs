@@ -0,0 +1,197 @@
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::byte_source::ByteSource;
+use crate::checksum::Hasher;
+use crate::outline::parse_int;
+use crate::state;
+
+/// Bytes read at a time to compute a checksum.
+const READ_CHUNK_LEN: usize = 1 << 20;
+
+/// A checksum of a range of the file, stored in the file at `store_at`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumRule {
+    /// One of `checksum::HASH_NAMES`
+    pub algorithm: String,
+    /// Whether the value is stored least significant byte first
+    pub little_endian: bool,
+    /// File offsets, end exclusive
+    pub range: Range<usize>,
+    pub store_at: usize,
+}
+
+impl ChecksumRule {
+    /// `<algorithm>[le|be] over <start>..<end> store at <offset>`, big endian
+    /// without a suffix.
+    pub fn parse(text: &str) -> Result<ChecksumRule, String> {
+        let usage = || {
+            format!(
+                "expected <algorithm>[le|be] over <start>..<end> store at <offset>, not {}",
+                text
+            )
+        };
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let (name, range, store_at) = match words[..] {
+            [name, "over", range, "store", "at", store_at] => (name, range, store_at),
+            _ => return Err(usage()),
+        };
+        let (algorithm, little_endian) = match (name.strip_suffix("le"), name.strip_suffix("be")) {
+            _ if Hasher::new(name).is_some() => (name, false),
+            (Some(base), _) if Hasher::new(base).is_some() => (base, true),
+            (_, Some(base)) if Hasher::new(base).is_some() => (base, false),
+            _ => return Err(format!("unknown checksum {}", name)),
+        };
+        let offset =
+            |text: &str| usize::try_from(parse_int(text)?).map_err(|_| format!("not an offset: {}", text));
+        let (start, end) = range.split_once("..").ok_or_else(usage)?;
+        let range = offset(start)?..offset(end)?;
+        if range.is_empty() {
+            return Err(format!("empty range {:x}..{:x}", range.start, range.end));
+        }
+        let rule = ChecksumRule {
+            algorithm: algorithm.to_string(),
+            little_endian,
+            range,
+            store_at: offset(store_at)?,
+        };
+        if rule.store_at.checked_add(rule.value_len()).is_none() {
+            return Err(format!("the value doesn't fit at {:x}", rule.store_at));
+        }
+        Ok(rule)
+    }
+
+    /// The length of the stored value.
+    fn value_len(&self) -> usize {
+        Hasher::new(&self.algorithm).map_or(0, |hasher| hasher.finish_bytes().len())
+    }
+
+    /// The bytes that should be at `store_at`.
+    pub fn compute(&self, source: &dyn ByteSource) -> io::Result<Vec<u8>> {
+        let mut hasher = Hasher::new(&self.algorithm)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown checksum"))?;
+        let mut offset = self.range.start;
+        while offset < self.range.end {
+            let len = cmp::min(READ_CHUNK_LEN, self.range.end - offset);
+            let bytes = source.read_exact_at(offset as u64, len)?;
+            hasher.update(&bytes);
+            offset += len;
+        }
+        let mut value = hasher.finish_bytes();
+        if self.little_endian {
+            value.reverse();
+        }
+        Ok(value)
+    }
+
+    /// Whether changing the bytes in `range` may change the checksum or the
+    /// stored value.
+    pub fn touches(&self, range: Range<usize>) -> bool {
+        let overlaps = |other: &Range<usize>| range.start < other.end && other.start < range.end;
+        overlaps(&self.range)
+            || overlaps(&(self.store_at..self.store_at.saturating_add(self.value_len())))
+    }
+
+    /// Whether the stored value is the computed one.
+    pub fn check(&self, source: &dyn ByteSource) -> io::Result<bool> {
+        let value = self.compute(source)?;
+        Ok(source.read_exact_at(self.store_at as u64, value.len())? == value)
+    }
+}
+
+impl fmt::Display for ChecksumRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} over 0x{:x}..0x{:x} store at 0x{:x}",
+            self.algorithm,
+            if self.little_endian { "le" } else { "be" },
+            self.range.start,
+            self.range.end,
+            self.store_at
+        )
+    }
+}
+
+/// The checksum rules of a file, kept in the state directory like marks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChecksumRules {
+    rules: Vec<ChecksumRule>,
+}
+
+impl ChecksumRules {
+    pub fn add(&mut self, rule: ChecksumRule) {
+        self.rules.retain(|other| other.store_at != rule.store_at);
+        self.rules.push(rule);
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChecksumRule> {
+        self.rules.iter()
+    }
+
+    /// One rule per line; lines that don't parse are skipped.
+    pub fn parse(text: &str) -> ChecksumRules {
+        ChecksumRules {
+            rules: text
+                .lines()
+                .filter_map(|line| ChecksumRule::parse(line).ok())
+                .collect(),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        self.iter().map(|rule| format!("{}\n", rule)).collect()
+    }
+
+    pub fn load(path: &Path) -> ChecksumRules {
+        state::read_state(path, "checksums")
+            .map(|text| ChecksumRules::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        state::write_state(path, "checksums", &self.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_rules() {
+        let rule = ChecksumRule::parse("crc32le over 0..9 store at 0xc").unwrap();
+        assert_eq!((rule.algorithm.as_str(), rule.little_endian), ("crc32", true));
+        assert!(!ChecksumRule::parse("crc32 over 0..9 store at 12").unwrap().little_endian);
+        assert!(ChecksumRule::parse("crc64 over 0..9 store at 12").is_err());
+        assert!(ChecksumRule::parse("crc32 over 9..9 store at 12").is_err());
+        assert!(ChecksumRule::parse(&format!("crc32 over 0..9 store at {}", usize::MAX - 1)).is_err());
+        let last = ChecksumRule { store_at: usize::MAX - 1, ..rule.clone() };
+        assert!(last.touches(usize::MAX - 1..usize::MAX));
+
+        let mut data = b"123456789\0\0\0\x26\x39\xf4\xcb".to_vec();
+        assert_eq!(rule.compute(&data).unwrap(), [0x26, 0x39, 0xf4, 0xcb]);
+        assert!(rule.check(&data).unwrap());
+        data[0] = b'0';
+        assert!(!rule.check(&data).unwrap());
+
+        assert!(rule.touches(8..9) && rule.touches(0xf..0x20));
+        assert!(!rule.touches(9..0xc) && !rule.touches(0x10..0x20));
+
+        let mut rules = ChecksumRules::default();
+        rules.add(rule);
+        rules.add(ChecksumRule::parse("sum8 over 0..9 store at 9").unwrap());
+        assert_eq!(ChecksumRules::parse(&rules.serialize()), rules);
+    }
+}
@@ -0,0 +1,245 @@
+//! The system clipboard, written with the OSC 52 terminal escape or with a
+//! command such as `xclip` or `wl-copy`.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::encoding::parse_hex;
+use crate::export::base64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardFormat {
+    /// Space separated hex bytes, one line per selection
+    Hex,
+    /// The bytes themselves, selections back to back
+    Raw,
+    /// One line per selection
+    Base64,
+}
+
+pub const FORMAT_NAMES: &str = "hex raw base64";
+
+/// Most bytes copied with OSC 52, before base64; terminals drop longer escapes.
+pub const MAX_OSC52_LEN: usize = 74_000;
+
+impl ClipboardFormat {
+    pub fn parse(name: &str) -> Option<ClipboardFormat> {
+        Some(match name {
+            "hex" => ClipboardFormat::Hex,
+            "raw" => ClipboardFormat::Raw,
+            "base64" => ClipboardFormat::Base64,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardFormat::Hex => "hex",
+            ClipboardFormat::Raw => "raw",
+            ClipboardFormat::Base64 => "base64",
+        }
+    }
+
+    pub fn encode(self, selections: &[Vec<u8>]) -> Vec<u8> {
+        let lines = |line: fn(&[u8]) -> String| {
+            selections
+                .iter()
+                .map(|bytes| line(bytes))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .into_bytes()
+        };
+        match self {
+            ClipboardFormat::Hex => lines(|bytes| {
+                bytes
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }),
+            ClipboardFormat::Raw => selections.concat(),
+            ClipboardFormat::Base64 => lines(base64),
+        }
+    }
+
+    /// Selections from clipboard contents, one per non-empty line for hex
+    /// and base64. Hex lines are read with `parse_hex`.
+    pub fn decode(self, contents: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        if self == ClipboardFormat::Raw {
+            return Ok(vec![contents.to_vec()]);
+        }
+        let text = std::str::from_utf8(contents).map_err(|_| "the clipboard is not text".to_string())?;
+        let selections = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match self {
+                ClipboardFormat::Base64 => base64_decode(line.trim()),
+                _ => parse_hex(line),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if selections.is_empty() {
+            return Err("the clipboard is empty".into());
+        }
+        Ok(selections)
+    }
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut bytes = Vec::new();
+    let (mut bits, mut bit_count) = (0u32, 0);
+    for c in text.trim_end_matches('=').bytes() {
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| format!("not base64: {}", text))?;
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Where yanks to the clipboard register go and how pastes read it.
+///
+/// `TOFU_CLIPBOARD_COPY` is a shell command that gets the contents on its
+/// input, else the view writes an OSC 52 escape to the terminal.
+/// `TOFU_CLIPBOARD_PASTE` is a shell command printing the clipboard; without
+/// it, pastes get what tofu last copied.
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    pub format: ClipboardFormat,
+    /// Whether yanks into the default register are copied too
+    pub sync: bool,
+    copy_command: Option<String>,
+    paste_command: Option<String>,
+    /// OSC 52 escape waiting to be written to the terminal
+    escape: Option<String>,
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Clipboard::new()
+    }
+}
+
+impl Clipboard {
+    pub fn new() -> Clipboard {
+        let command = |name| env::var(name).ok().filter(|x| !x.trim().is_empty());
+        Clipboard {
+            format: ClipboardFormat::Hex,
+            sync: false,
+            copy_command: command("TOFU_CLIPBOARD_COPY"),
+            paste_command: command("TOFU_CLIPBOARD_PASTE"),
+            escape: None,
+        }
+    }
+
+    pub fn copy(&mut self, selections: &[Vec<u8>]) -> Result<(), String> {
+        let contents = self.format.encode(selections);
+        match &self.copy_command {
+            Some(command) => run(command, Some(&contents)).map(|_| ()),
+            None if contents.len() > MAX_OSC52_LEN => Err(format!(
+                "{} bytes is too much for the terminal clipboard (at most {}), \
+                 set TOFU_CLIPBOARD_COPY to copy more",
+                contents.len(),
+                MAX_OSC52_LEN
+            )),
+            None => {
+                self.escape = Some(osc52(&contents));
+                Ok(())
+            }
+        }
+    }
+
+    /// The OSC 52 escape of the last copy, for the view to write.
+    pub fn take_escape(&mut self) -> Option<String> {
+        self.escape.take()
+    }
+
+    /// The clipboard's selections, or `None` without a paste command.
+    pub fn paste(&self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        match &self.paste_command {
+            Some(command) => self.format.decode(&run(command, None)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "clipboard: {}, copy with {}, paste {}, {}",
+            self.format.name(),
+            self.copy_command.as_deref().unwrap_or("OSC 52"),
+            match &self.paste_command {
+                Some(command) => format!("with {}", command),
+                None => "from the last copy".into(),
+            },
+            if self.sync { "yanks copied" } else { "only \"+ yanks copied" }
+        )
+    }
+}
+
+/// The escape setting the terminal's clipboard, passed through tmux if needed.
+fn osc52(contents: &[u8]) -> String {
+    let escape = format!("\x1b]52;c;{}\x07", base64(contents));
+    if env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", escape.replace('\x1b', "\x1b\x1b"))
+    } else {
+        escape
+    }
+}
+
+/// Runs `command` with the shell, feeding it `input`, and returns what it
+/// printed. Output is only read when there is no input, as copy commands may
+/// stay around to serve the clipboard.
+fn run(command: &str, input: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let error = |e: io::Error| format!("{}: {}", command, e);
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(if input.is_some() { Stdio::null() } else { Stdio::piped() })
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(error)?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input).map_err(error)?;
+    }
+    let output = child.wait_with_output().map_err(error)?;
+    if !output.status.success() {
+        return Err(format!("{} failed ({})", command, output.status));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_formats() {
+        let deadbeef = vec![0xde, 0xad, 0xbe, 0xef];
+        let selections = vec![deadbeef, vec![], b"foobar".to_vec()];
+        for format in [ClipboardFormat::Hex, ClipboardFormat::Base64] {
+            let decoded = format.decode(&format.encode(&selections)).unwrap();
+            assert_eq!(decoded, [&selections[0][..], b"foobar"]);
+        }
+        assert_eq!(ClipboardFormat::Hex.encode(&selections[..1]), b"de ad be ef");
+
+        let mut clipboard = Clipboard {
+            format: ClipboardFormat::Raw,
+            sync: false,
+            copy_command: None,
+            paste_command: None,
+            escape: None,
+        };
+        clipboard.copy(&[b"hi".to_vec()]).unwrap();
+        assert!(clipboard.take_escape().unwrap().contains("52;c;aGk="));
+        assert!(clipboard.copy(&[vec![0; MAX_OSC52_LEN + 1]]).is_err());
+        assert_eq!(clipboard.take_escape(), None);
+    }
+}
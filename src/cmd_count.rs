@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use super::keymap::KeyMap;
+use super::keymap::{Binding, Describe, KeyMap};
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
@@ -49,6 +49,22 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::AppendDigit(d) if *d < 10 => "add a digit to the count",
+            Action::AppendDigit(_) => "add a hex digit to the count (after x)",
+            Action::CancelEntry => "clear the count",
+            Action::SwitchHexEntry => "switch between decimal and hex count",
+            Action::RemoveLast => "remove the last digit",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Transition {
     NotHandled,
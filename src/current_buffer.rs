@@ -1,11 +1,23 @@
 use xi_rope::Interval;
 
-use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use super::byte_rope::*;
 use super::history::History;
+use crate::annotations::{Annotation, Annotations};
+use crate::checksum_rules::{ChecksumRule, ChecksumRules};
+use crate::clipboard::Clipboard;
+use crate::diff::{DiffTarget, SummaryJob};
+use crate::executable::{Executable, Section};
+use crate::macros::Macros;
+use crate::marks::Marks;
+use crate::operations;
+use crate::outline::Outline;
+use crate::registers::Registers;
+use crate::hex_view::minimap::{Minimap, MinimapKind};
 use crate::modes::mode::DirtyBytes;
+use crate::modes::register::CLIPBOARD_REGISTER;
 use crate::selection::{SelRegion, Selection};
 
 use std::fs::File;
@@ -26,6 +38,9 @@ use std::time::{
 
 const DEBUG_FLAG: bool = false;
 
+/// Largest window `select_file_range` loads to fit a selection
+const MAX_SELECTION_WINDOW: usize = 16 << 20;
+
 fn debug_log(message: &str) {
     /*
     use std::fs::OpenOptions;
@@ -44,7 +59,6 @@ fn debug_log(message: &str) {
             if log_path.exists() {
                 // If the path exists, attempt to open the file for writing
                 if let Ok(mut file) = OpenOptions::new()
-                    .write(true)
                     .append(true)
                     .open(&log_path) {
 
@@ -72,23 +86,70 @@ pub struct CurrentBuffer {
     pub path: Option<PathBuf>,
     pub data: Rope,
     pub selection: Selection,
-    pub registers: HashMap<char, Vec<Vec<u8>>>,
     pub dirty: bool,
     pub history: History,
+    /// Whether an edit was applied since `BuffrCollection::take_changed`;
+    /// undos, redos and checksum fix-ups don't count
+    pub changed: bool,
     pub data_start_offset: usize,
+    /// Number of file bytes, starting at `data_start_offset`, that `data` stands for
+    pub window_file_len: usize,
+    pub marks: Marks,
+    pub annotations: Annotations,
+    pub checksum_rules: ChecksumRules,
+    /// Rules whose stored checksum doesn't match, or can't be read; `None`
+    /// after edits to the bytes a rule covers, until they're checked again
+    pub bad_checksums: Option<usize>,
+    /// Field tree shown next to the hex view, if any
+    pub outline: Option<Outline>,
+    /// File this buffer is compared against, if any
+    pub diff_target: Option<DiffTarget>,
+    /// Sections of the file if it's an executable, for the status line
+    pub sections: Vec<Section>,
 }
 
 // in current_buffers.rs
 impl CurrentBuffer {
     pub fn from_data_and_path(data: Vec<u8>, path: Option<impl Into<PathBuf>>) -> CurrentBuffer {
-        CurrentBuffer {
+        let path: Option<PathBuf> = path.map(Into::into);
+        let mut buffer = CurrentBuffer {
+            marks: path.as_deref().map(Marks::load).unwrap_or_default(),
+            annotations: path.as_deref().map(Annotations::load).unwrap_or_default(),
+            checksum_rules: path.as_deref().map(ChecksumRules::load).unwrap_or_default(),
+            bad_checksums: Some(0),
+            window_file_len: data.len(),
             data: data.into(),
             selection: Selection::new(),
-            registers: HashMap::new(),
             dirty: false,
-            path: path.map(Into::into),
+            path,
             history: History::new(),
+            changed: false,
             data_start_offset: 0,  // Initialize at 0
+            outline: None,
+            diff_target: None,
+            sections: vec![],
+        };
+        buffer.restore_history();
+        buffer.refresh_sections();
+        buffer.refresh_checksums();
+        buffer
+    }
+
+    /// Restores the undo history kept when the file was last written, if the
+    /// file is unchanged since and the window it was made in starts where
+    /// this one does.
+    fn restore_history(&mut self) {
+        let saved = match self.path.as_deref().and_then(History::load) {
+            Some(saved) => saved,
+            None => return,
+        };
+        let loaded = saved.window_start == self.data_start_offset
+            && saved.window_len == self.data.len();
+        if !loaded && self.load_window_at(saved.window_start, saved.window_len).is_err() {
+            return;
+        }
+        if self.data.len() == saved.window_len {
+            self.history = saved.history;
         }
     }
     
@@ -108,9 +169,11 @@ impl CurrentBuffer {
             debug_log(&format!("File size: {}, Current buffer size: {}", 
                 file_size, self.data.len()));
             
-            // Seek to the end of current data
-            file.seek(SeekFrom::Start(self.data.len() as u64))?;
-            debug_log(&format!("Seeking to position: {}", self.data.len()));
+            // Seek to the end of the file bytes the window stands for, which
+            // edits may have made longer or shorter than `data`
+            let end_of_data = self.data_start_offset + self.window_file_len;
+            file.seek(SeekFrom::Start(end_of_data as u64))?;
+            debug_log(&format!("Seeking to position: {}", end_of_data));
             
             // Read next chunk
             let mut next_chunk = vec![0; chunk_size];
@@ -134,6 +197,7 @@ impl CurrentBuffer {
                 // Apply the delta to append the new chunk
                 let old_size = self.data.len();
                 self.data = self.data.apply_delta(&delta);
+                self.window_file_len += bytes_read;
                 debug_log(&format!("Buffer size changed: {} -> {}", 
                     old_size, self.data.len()));
                 
@@ -148,6 +212,357 @@ impl CurrentBuffer {
         }
     }
 
+    /// Length of the file backing this buffer, as currently on disk.
+    pub fn file_len(&self) -> Option<u64> {
+        self.path
+            .as_ref()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+    }
+
+    /// Length of the buffer as it would be written: the file, with the loaded
+    /// window replaced by the (possibly edited) data.
+    pub fn logical_len(&self) -> u64 {
+        let window_end = (self.data_start_offset + self.window_file_len) as u64;
+        let suffix_len = self.file_len().unwrap_or(0).saturating_sub(window_end);
+        (self.data_start_offset + self.data.len()) as u64 + suffix_len
+    }
+
+    /// Reads `len` bytes at `offset` of the buffer as it would be written, taking
+    /// the loaded window from memory and everything else from disk.
+    pub fn read_range(&self, offset: usize, len: usize) -> Result<Vec<u8>, std::io::Error> {
+        read_around_window(
+            self.path.as_deref(),
+            &self.data,
+            self.data_start_offset,
+            self.window_file_len,
+            offset..offset.saturating_add(len),
+        )
+    }
+
+    /// A copy of the buffer as it would be written that can be read on another
+    /// thread. Only the loaded window is copied, and cloning the rope is cheap.
+    pub fn snapshot(&self) -> BufferSnapshot {
+        BufferSnapshot {
+            path: self.path.clone(),
+            data: self.data.clone(),
+            data_start_offset: self.data_start_offset,
+            window_file_len: self.window_file_len,
+            len: self.logical_len(),
+        }
+    }
+
+    /// Replaces the loaded window with `len` bytes of the file starting at `start`.
+    /// Only valid for clean buffers: edits and their history are dropped.
+    fn load_window_at(&mut self, start: usize, len: usize) -> Result<(), std::io::Error> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "buffer has no path")
+        })?;
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start as u64))?;
+        let mut window = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut window)?;
+
+        debug_log(&format!("Loaded window {}..{}", start, start + window.len()));
+        self.window_file_len = window.len();
+        self.data = window.into();
+        self.data_start_offset = start;
+        self.selection = Selection::new();
+        self.history = History::new();
+        Ok(())
+    }
+
+    /// Starts comparing the buffer with the file at `path`.
+    pub fn compare_with(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        self.diff_target = Some(DiffTarget::open(path)?);
+        Ok(())
+    }
+
+    /// Whether `path` names the buffer's own file, however it's spelled.
+    pub fn is_own_file(&self, path: &Path) -> bool {
+        match (&self.path, path.canonicalize()) {
+            (Some(own), Ok(path)) => own.canonicalize().is_ok_and(|own| own == path),
+            _ => false,
+        }
+    }
+
+    /// Writes the buffer to `path`, after fixing its checksums. When only a
+    /// window of the file is loaded, the bytes outside the window are copied
+    /// from the buffer's own file, so the whole file is written whatever the
+    /// target. Checksums that couldn't be fixed don't stop the write; why they
+    /// couldn't is returned.
+    pub fn write_to(&mut self, path: &Path) -> Result<Option<String>, std::io::Error> {
+        let unfixed = self.fix_checksums().err();
+        let is_own_file = self.is_own_file(path);
+        let file_len = self.file_len().unwrap_or(0) as usize;
+        let window_end = self.data_start_offset + self.window_file_len;
+        let windowed = self.path.is_some() && (self.data_start_offset > 0 || window_end < file_len);
+
+        if windowed && is_own_file {
+            // Written next to the file, so that renaming it over the file
+            // can't fail halfway or cross file systems
+            let mut temp_name = std::ffi::OsString::from(".");
+            temp_name.push(path.file_name().unwrap_or_default());
+            temp_name.push(format!(".tofu-{}", std::process::id()));
+            let temp_path = path.with_file_name(temp_name);
+            let written = self
+                .write_around_window(&temp_path)
+                .and_then(|temp| {
+                    temp.set_permissions(std::fs::metadata(path)?.permissions())?;
+                    temp.sync_all()
+                })
+                .and_then(|()| std::fs::rename(&temp_path, path));
+            if written.is_err() {
+                let _ = std::fs::remove_file(&temp_path);
+            }
+            written?;
+        } else if windowed {
+            self.write_around_window(path)?.sync_all()?;
+        } else {
+            std::fs::write(path, self.data.slice_to_cow(..))?;
+        }
+
+        if let (true, Some(own)) = (is_own_file, self.path.clone()) {
+            self.window_file_len = self.data.len();
+            self.refresh_sections();
+            // Marks and annotations are a convenience; failing to store them
+            // shouldn't fail the write
+            let _ = self.marks.save(&own);
+            let _ = self.annotations.save(&own);
+            let _ = self.history.save(&own, self.data_start_offset, self.data.len());
+        }
+        Ok(unfixed)
+    }
+
+    /// Writes the buffer's file to `target`, with the loaded window replaced by
+    /// the data. The bytes around the window are copied without reading all of
+    /// them into memory.
+    fn write_around_window(&self, target: &Path) -> Result<File, std::io::Error> {
+        let path = self.path.as_deref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "buffer has no path")
+        })?;
+        let mut original = File::open(path)?;
+        let mut out = std::io::BufWriter::new(File::create(target)?);
+        std::io::copy(&mut (&mut original).take(self.data_start_offset as u64), &mut out)?;
+        for chunk in self.data.iter_chunks(..) {
+            out.write_all(chunk)?;
+        }
+        original.seek(SeekFrom::Start((self.data_start_offset + self.window_file_len) as u64))?;
+        std::io::copy(&mut original, &mut out)?;
+
+        out.into_inner().map_err(|e| e.into_error())
+    }
+
+    /// Moves all selections to a file offset. If the offset is outside the
+    /// loaded window, a new window of `window_len` bytes is loaded around it,
+    /// aligned to `align` so that rows keep their file addresses.
+    pub fn jump_to_file_offset(
+        &mut self,
+        offset: usize,
+        window_len: usize,
+        align: usize,
+    ) -> Result<DirtyBytes, String> {
+        let loaded_end = self.data_start_offset + self.data.len();
+        if offset < self.data_start_offset || offset >= loaded_end {
+            if self.dirty {
+                return Err("unsaved changes: write them before leaving the loaded window".into());
+            }
+            let start = offset.saturating_sub(window_len / 3);
+            let start = start - start % std::cmp::max(1, align);
+            self.load_window_at(start, window_len)
+                .map_err(|e| format!("can't load window: {}", e))?;
+            if self.data.is_empty() {
+                return Ok(DirtyBytes::ChangeLength);
+            }
+        }
+
+        let rope_offset = std::cmp::min(offset - self.data_start_offset, self.data.len() - 1);
+        self.map_selections(|region| vec![region.jump_to(rope_offset)]);
+        Ok(DirtyBytes::ChangeLength)
+    }
+
+    /// Selects the bytes at file offsets `range` as a single selection, loading
+    /// a window around them first if needed. Ranges longer than the window are
+    /// cut at the window's end.
+    pub fn select_file_range(
+        &mut self,
+        range: Range<u64>,
+        window_len: usize,
+        align: usize,
+    ) -> Result<DirtyBytes, String> {
+        let start = range.start as usize;
+        let end = std::cmp::max(range.end as usize, start + 1);
+        let loaded_end = self.data_start_offset + self.data.len();
+        if start < self.data_start_offset || end > loaded_end {
+            if self.dirty {
+                return Err("unsaved changes: write them before leaving the loaded window".into());
+            }
+            let window_start = start.saturating_sub(window_len / 3);
+            let window_start = window_start - window_start % std::cmp::max(1, align);
+            let len = std::cmp::min(
+                std::cmp::max(window_len, end - window_start + window_len / 3),
+                MAX_SELECTION_WINDOW,
+            );
+            self.load_window_at(window_start, len)
+                .map_err(|e| format!("can't load window: {}", e))?;
+        }
+        let loaded_end = self.data_start_offset + self.data.len();
+        if start < self.data_start_offset || start >= loaded_end {
+            return Err("range is outside the file".into());
+        }
+
+        let tail = start - self.data_start_offset;
+        let caret = std::cmp::min(end, loaded_end) - 1 - self.data_start_offset;
+        self.selection = Selection::new();
+        self.map_selections(|_| vec![SelRegion::new(caret, tail)]);
+        Ok(DirtyBytes::ChangeLength)
+    }
+
+    /// Overwrites the loaded bytes at file offset `offset` with `bytes`,
+    /// keeping the buffer's length.
+    pub fn overwrite_at_file_offset(&mut self, offset: u64, bytes: &[u8]) -> Result<DirtyBytes, String> {
+        let start = (offset as usize)
+            .checked_sub(self.data_start_offset)
+            .filter(|start| start + bytes.len() <= self.data.len())
+            .ok_or("bytes to overwrite aren't loaded")?;
+        let delta = operations::overwrite_range(&self.data, start, bytes);
+        Ok(self.apply_delta(delta))
+    }
+
+    /// Re-reads the section table, e.g. after the headers were edited.
+    pub fn refresh_sections(&mut self) {
+        self.sections = Executable::parse(self)
+            .map(|executable| executable.sections)
+            .unwrap_or_default();
+    }
+
+    pub fn add_checksum_rule(&mut self, rule: ChecksumRule) {
+        self.checksum_rules.add(rule);
+        self.store_checksum_rules();
+    }
+
+    pub fn clear_checksum_rules(&mut self) {
+        self.checksum_rules.clear();
+        self.store_checksum_rules();
+    }
+
+    /// Rules don't move with edits, so unlike marks they're stored right away.
+    fn store_checksum_rules(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = self.checksum_rules.save(path);
+        }
+        self.refresh_checksums();
+    }
+
+    pub fn refresh_checksums(&mut self) {
+        self.bad_checksums = Some(
+            self.checksum_rules
+                .iter()
+                .filter(|rule| !rule.check(self).unwrap_or(false))
+                .count(),
+        );
+    }
+
+    /// Marks the checksums as unchecked if `delta` may change bytes a rule
+    /// covers or stores to. Checking them right away would hash every rule's
+    /// range on every edit.
+    fn note_checksum_edit(&mut self, delta: &RopeDelta) {
+        if self.bad_checksums.is_none() {
+            return;
+        }
+        let (changed, new_len) = delta.summary();
+        let start = self.data_start_offset + changed.start;
+        // Changing the length moves every byte after the change
+        let end = if new_len == changed.size() {
+            self.data_start_offset + changed.end
+        } else {
+            usize::MAX
+        };
+        if self.checksum_rules.iter().any(|rule| rule.touches(start..end)) {
+            self.bad_checksums = None;
+        }
+    }
+
+    /// Writes every wrong checksum, in the order of the rules so that one may
+    /// cover the field of another, as undoable edits, then checks them all.
+    /// Returns how many changed.
+    pub fn fix_checksums(&mut self) -> Result<usize, String> {
+        // Fix-ups follow from other changes, so `.` shouldn't repeat them
+        let changed = self.changed;
+        let fixed = self.fix_checksum_fields();
+        self.changed = changed;
+        self.refresh_checksums();
+        fixed
+    }
+
+    fn fix_checksum_fields(&mut self) -> Result<usize, String> {
+        let mut fixed = 0;
+        let rules: Vec<ChecksumRule> = self.checksum_rules.iter().cloned().collect();
+        for rule in rules {
+            let value = rule.compute(self).map_err(|e| format!("{}: {}", rule, e))?;
+            if self.read_range(rule.store_at, value.len()).ok().as_ref() == Some(&value) {
+                continue;
+            }
+            let start = rule
+                .store_at
+                .checked_sub(self.data_start_offset)
+                .filter(|start| start + value.len() <= self.data.len())
+                .ok_or_else(|| format!("{}: the field isn't loaded", rule))?;
+            let delta = operations::overwrite_range(&self.data, start, &value);
+            self.apply_delta_in_place(delta);
+            fixed += 1;
+        }
+        Ok(fixed)
+    }
+
+    /// Sets mark `name` at the main cursor. Marks are stored right away unless
+    /// there are unsaved changes, in which case they're stored on write.
+    pub fn set_mark(&mut self, name: char, label: Option<String>) {
+        let offset = self.data_start_offset + self.selection.main_cursor_offset();
+        self.marks.set(name, offset, label);
+        self.store_marks_if_clean();
+    }
+
+    pub fn remove_mark(&mut self, name: char) -> bool {
+        let removed = self.marks.remove(name).is_some();
+        self.store_marks_if_clean();
+        removed
+    }
+
+    /// Annotates the bytes of every selection. Like marks, annotations are
+    /// stored right away unless there are unsaved changes.
+    pub fn annotate(&mut self, text: &str, color: Option<String>) {
+        for region in self.selection.iter() {
+            self.annotations.add(Annotation {
+                range: self.data_start_offset + region.min()
+                    ..self.data_start_offset + region.max() + 1,
+                text: text.to_string(),
+                color: color.clone(),
+            });
+        }
+        self.store_annotations_if_clean();
+    }
+
+    /// Removes the innermost annotation around the main cursor.
+    pub fn remove_annotation_at_cursor(&mut self) -> Option<Annotation> {
+        let offset = self.data_start_offset + self.selection.main_cursor_offset();
+        let removed = self.annotations.remove_at(offset);
+        self.store_annotations_if_clean();
+        removed
+    }
+
+    fn store_annotations_if_clean(&self) {
+        if let (Some(path), false) = (&self.path, self.dirty) {
+            let _ = self.annotations.save(path);
+        }
+    }
+
+    fn store_marks_if_clean(&self) {
+        if let (Some(path), false) = (&self.path, self.dirty) {
+            let _ = self.marks.save(path);
+        }
+    }
+
     pub fn name(&self) -> String {
         if let Some(path) = &self.path {
             format!("{}", path.display())
@@ -166,7 +581,7 @@ impl CurrentBuffer {
             }
             new
         });
-        invalidated_ranges.sort_by(|a, b| a.start.cmp(&b.start));
+        invalidated_ranges.sort_by_key(|a| a.start);
 
         let mut disjoint_invalidated_ranges = Vec::new();
         for r in invalidated_ranges {
@@ -186,6 +601,8 @@ impl CurrentBuffer {
 
     fn apply_delta_to_current_buffer(&mut self, delta: RopeDelta, is_final: bool) {
         let next_data = self.data.apply_delta(&delta);
+        self.move_file_offsets(&delta);
+        self.note_checksum_edit(&delta);
         if is_final {
             self.history
                 .perform_final(&self.data, delta, self.selection.clone());
@@ -195,6 +612,7 @@ impl CurrentBuffer {
         }
         self.data = next_data;
         self.dirty = true;
+        self.changed = true;
     }
 
     pub fn apply_delta(&mut self, delta: RopeDelta) -> DirtyBytes {
@@ -205,6 +623,15 @@ impl CurrentBuffer {
         DirtyBytes::ChangeLength
     }
 
+    /// For deltas that keep the length of every selection, which then stay
+    /// selected so they can be changed again.
+    pub fn apply_delta_in_place(&mut self, delta: RopeDelta) -> DirtyBytes {
+        let selection = self.selection.clone();
+        let dirty = self.apply_delta(delta);
+        self.selection = selection;
+        dirty
+    }
+
     pub fn apply_delta_offset_carets(
         &mut self,
         delta: RopeDelta,
@@ -241,34 +668,64 @@ impl CurrentBuffer {
         DirtyBytes::ChangeLength
     }
 
+    /// Moves marks and annotations along with an edit about to be applied.
+    fn move_file_offsets(&mut self, delta: &RopeDelta) {
+        self.marks
+            .apply_delta(delta, self.data_start_offset, self.data.len());
+        self.annotations
+            .apply_delta(delta, self.data_start_offset, self.data.len());
+    }
+
     pub fn commit_delta(&mut self) {
         self.history.commit_partial();
     }
 
+    /// Changes until the matching `end_undo_group` are undone together.
+    pub fn begin_undo_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    pub fn end_undo_group(&mut self) {
+        self.history.end_group();
+    }
+
     pub fn perform_undo(&mut self) -> Option<DirtyBytes> {
-        if let Some((undo_delta, old_selection)) =
-            self.history.undo(&self.data, self.selection.clone())
-        {
-            self.selection = old_selection;
-            self.data = self.data.apply_delta(&undo_delta);
-            self.dirty = true;
-            Some(DirtyBytes::ChangeLength)
-        } else {
-            None
-        }
+        let (undo_delta, old_selection) = self.history.undo(&self.data, self.selection.clone())?;
+        Some(self.apply_history_delta(undo_delta, old_selection))
     }
 
     pub fn perform_redo(&mut self) -> Option<DirtyBytes> {
-        if let Some((redo_delta, old_selection)) =
-            self.history.redo(&self.data, self.selection.clone())
+        let (redo_delta, old_selection) = self.history.redo()?;
+        Some(self.apply_history_delta(redo_delta, old_selection))
+    }
+
+    /// Undoes and redoes changes until the buffer is in state `target` of the
+    /// undo tree.
+    pub fn goto_history_state(&mut self, target: usize) -> Option<DirtyBytes> {
+        let mut dirty = None;
+        while let Some((delta, selection)) =
+            self.history.step_towards(target, &self.data, self.selection.clone())
         {
-            self.selection = old_selection;
-            self.data = self.data.apply_delta(&redo_delta);
-            self.dirty = true;
-            Some(DirtyBytes::ChangeLength)
-        } else {
-            None
+            dirty = Some(self.apply_history_delta(delta, selection));
         }
+        dirty
+    }
+
+    /// Moves `steps` states back or forward in the order changes were made,
+    /// across branches of the undo tree.
+    pub fn step_history(&mut self, steps: isize) -> Option<DirtyBytes> {
+        let last = self.history.len() as isize - 1;
+        let target = (self.history.current() as isize + steps).clamp(0, last);
+        self.goto_history_state(target as usize)
+    }
+
+    fn apply_history_delta(&mut self, delta: RopeDelta, selection: Selection) -> DirtyBytes {
+        self.selection = selection;
+        self.move_file_offsets(&delta);
+        self.note_checksum_edit(&delta);
+        self.data = self.data.apply_delta(&delta);
+        self.dirty = true;
+        DirtyBytes::ChangeLength
     }
 
     fn switch_main_sel(&mut self, f: impl FnOnce(&mut Selection)) -> DirtyBytes {
@@ -286,6 +743,30 @@ impl CurrentBuffer {
         dirty
     }
 
+    fn change_selections(&mut self, f: impl FnOnce(&mut Selection)) -> DirtyBytes {
+        let mut dirty: Vec<Interval> = self.selection.iter().copied().map(Into::into).collect();
+        f(&mut self.selection);
+        dirty.extend(self.selection.iter().copied().map(Interval::from));
+        DirtyBytes::ChangeInPlace(dirty)
+    }
+
+    /// Leaves a single cursor at `offset`.
+    pub fn select_only(&mut self, offset: usize) -> DirtyBytes {
+        self.change_selections(|sel| {
+            sel.retain(sel.main_selection);
+            sel.replace_main(SelRegion::new(offset, offset));
+        })
+    }
+    pub fn add_cursor(&mut self, offset: usize) -> DirtyBytes {
+        self.change_selections(|sel| sel.add(SelRegion::new(offset, offset)))
+    }
+    pub fn extend_main_to(&mut self, offset: usize) -> DirtyBytes {
+        self.change_selections(|sel| {
+            let main = sel.main();
+            sel.replace_main(main.extend_to(offset));
+        })
+    }
+
     pub fn remove_selection(&mut self, index: usize) -> DirtyBytes {
         self.modify_sels_in_place(|sel| sel.remove(index % sel.len()))
     }
@@ -299,19 +780,44 @@ impl CurrentBuffer {
         self.switch_main_sel(|sel| sel.select_prev(count))
     }
 
-    pub fn yank_selections(&mut self, reg: char) {
+    /// Each selection's file offset and bytes.
+    pub fn selected_bytes(&self) -> Vec<(usize, Vec<u8>)> {
         if self.data.is_empty() {
-            self.registers
-                .insert(reg, vec![vec![]; self.selection.len()]);
-            return;
+            return vec![];
         }
-
-        let selections = self
-            .selection
+        self.selection
             .iter()
-            .map(|region| self.data.slice_to_cow(region.min()..=region.max()).to_vec())
-            .collect();
-        self.registers.insert(reg, selections);
+            .map(|region| {
+                (
+                    self.data_start_offset + region.min(),
+                    self.data.slice_to_cow(region.min()..=region.max()).to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    /// The bytes of every selection, empty ones for an empty buffer.
+    pub fn selection_contents(&self) -> Vec<Vec<u8>> {
+        if self.data.is_empty() {
+            vec![vec![]; self.selection.len()]
+        } else {
+            self.selection
+                .iter()
+                .map(|region| self.data.slice_to_cow(region.min()..=region.max()).to_vec())
+                .collect()
+        }
+    }
+
+    /// Like `selection_contents`, but sharing storage with the buffer.
+    pub fn selection_ropes(&self) -> Vec<Rope> {
+        if self.data.is_empty() {
+            vec![Rope::default(); self.selection.len()]
+        } else {
+            self.selection
+                .iter()
+                .map(|region| self.data.slice(region.min()..=region.max()))
+                .collect()
+        }
     }
 
     pub fn overflow_sel_style(&self) -> Option<OverflowSelectionStyle> {
@@ -335,9 +841,83 @@ impl CurrentBuffer {
     }
 }
 
+/// Reads `range` of a file whose bytes `window_start..window_start +
+/// window_file_len` are replaced by `window`.
+fn read_around_window(
+    path: Option<&Path>,
+    window: &Rope,
+    window_start: usize,
+    window_file_len: usize,
+    range: Range<usize>,
+) -> Result<Vec<u8>, std::io::Error> {
+    let window_end = window_start + window.len();
+    let mut out = Vec::new();
+
+    if range.start < window_start {
+        let end = std::cmp::min(range.end, window_start);
+        out.extend(read_from_disk(path, range.start, end - range.start)?);
+    }
+    let (start_in_window, end_in_window) =
+        (std::cmp::max(range.start, window_start), std::cmp::min(range.end, window_end));
+    if start_in_window < end_in_window {
+        out.extend_from_slice(
+            &window.slice_to_cow(start_in_window - window_start..end_in_window - window_start),
+        );
+    }
+    if range.end > window_end {
+        let start_after = std::cmp::max(range.start, window_end);
+        let disk_offset = start_after - window_end + window_start + window_file_len;
+        out.extend(read_from_disk(path, disk_offset, range.end - start_after)?);
+    }
+    Ok(out)
+}
+
+fn read_from_disk(path: Option<&Path>, offset: usize, len: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut bytes = Vec::new();
+    if let Some(path) = path {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.take(len as u64).read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// See `CurrentBuffer::snapshot`.
+pub struct BufferSnapshot {
+    path: Option<PathBuf>,
+    data: Rope,
+    data_start_offset: usize,
+    window_file_len: usize,
+    len: u64,
+}
+
+impl BufferSnapshot {
+    pub fn logical_len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn read_range(&self, offset: usize, len: usize) -> Result<Vec<u8>, std::io::Error> {
+        read_around_window(
+            self.path.as_deref(),
+            &self.data,
+            self.data_start_offset,
+            self.window_file_len,
+            offset..offset.saturating_add(len),
+        )
+    }
+}
+
 pub struct BuffrCollection {
     list: Vec<CurrentBuffer>,
     cur_buf_index: usize,
+    pub registers: Registers,
+    pub clipboard: Clipboard,
+    pub macros: Macros,
+    minimap: Option<MinimapKind>,
+    /// The minimap the view shows, whose cells the minimap mode jumps between
+    pub shown_minimap: Option<Minimap>,
+    /// Running `:diffsummary`, whose outcome the view shows when it's done
+    pub diff_summary: Option<SummaryJob>,
 }
 
 impl Default for BuffrCollection {
@@ -361,9 +941,65 @@ impl BuffrCollection {
         BuffrCollection {
             cur_buf_index: 0,
             list: vec![buf],
+            registers: Registers::new(),
+            clipboard: Clipboard::new(),
+            macros: Macros::new(),
+            minimap: None,
+            shown_minimap: None,
+            diff_summary: None,
         }
     }
 
+    /// Which overview the view should show next to the hex view, if any.
+    pub fn minimap(&self) -> Option<MinimapKind> {
+        self.minimap
+    }
+
+    pub fn set_minimap(&mut self, kind: Option<MinimapKind>) {
+        self.minimap = kind;
+    }
+
+    /// Drops the shown minimap after the file was written, so the view
+    /// computes it again from what's on disk now.
+    pub fn restart_minimap(&mut self) {
+        self.shown_minimap = None;
+    }
+
+    /// Copies every selection of the current buffer into register `reg`, or
+    /// appends them to the lowercase register when `reg` is uppercase. The
+    /// clipboard register, and `"` when the clipboard is synced, also copy to
+    /// the system clipboard.
+    pub fn yank_selections(&mut self, reg: char) -> Result<(), String> {
+        let selections = self.current().selection_ropes();
+        let copied = if reg == CLIPBOARD_REGISTER || (reg == '"' && self.clipboard.sync) {
+            self.clipboard.copy(&selections.iter().map(Vec::from).collect::<Vec<_>>())
+        } else {
+            Ok(())
+        };
+        self.registers.set(reg, selections);
+        copied
+    }
+
+    /// Contents of register `reg`, reading the system clipboard for the
+    /// clipboard register when it can.
+    pub fn register_contents(&self, reg: char) -> Result<Option<Vec<Rope>>, String> {
+        if reg == CLIPBOARD_REGISTER {
+            if let Some(contents) = self.clipboard.paste()? {
+                return Ok(Some(contents.into_iter().map(Rope::from).collect()));
+            }
+        }
+        Ok(self.registers.get(reg))
+    }
+
+    /// Whether any buffer was edited since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        let changed = self.list.iter().any(|buf| buf.changed);
+        for buf in self.list.iter_mut() {
+            buf.changed = false;
+        }
+        changed
+    }
+
     pub fn current(&self) -> &CurrentBuffer {
         &self.list[self.cur_buf_index]
     }
@@ -397,6 +1033,16 @@ impl BuffrCollection {
         Ok(())
     }
 
+    /// Makes the next (or previous) buffer current.
+    pub fn cycle_current_buffer(&mut self, forward: bool) {
+        let len = self.list.len();
+        self.cur_buf_index = if forward {
+            (self.cur_buf_index + 1) % len
+        } else {
+            (self.cur_buf_index + len - 1) % len
+        };
+    }
+
     pub fn delete_current(&mut self) {
         self.list.remove(self.cur_buf_index);
         self.cur_buf_index = self.cur_buf_index.saturating_sub(1);
@@ -405,3 +1051,63 @@ impl BuffrCollection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state;
+
+    #[test]
+    fn test_write_windowed() {
+        state::use_test_state_dir();
+        let dir = env::temp_dir().join(format!("tofu-test-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.bin");
+        let bytes: Vec<u8> = (0..64).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut buffer = CurrentBuffer::from_data_and_path(bytes[..16].to_vec(), Some(&path));
+        buffer.jump_to_file_offset(40, 12, 8).unwrap();
+        assert_eq!(buffer.data_start_offset, 32);
+        buffer.overwrite_at_file_offset(41, &[0xff]).unwrap();
+        let delta = operations::insert(&buffer.data, &buffer.selection, vec![0xaa]);
+        buffer.apply_delta(delta);
+        let mut expected = bytes.clone();
+        expected[41] = 0xff;
+        expected.insert(40, 0xaa);
+
+        // Another file gets the whole buffer, not just the window
+        buffer.write_to(&dir.join("b.bin")).unwrap();
+        assert_eq!(std::fs::read(dir.join("b.bin")).unwrap(), expected);
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+        // The own file spelled differently is still the own file
+        buffer.write_to(&dir.join(".").join("a.bin")).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+        assert_eq!(buffer.window_file_len, 13);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_history_in_window() {
+        state::use_test_state_dir();
+        let dir = env::temp_dir().join(format!("tofu-test-restore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.bin");
+        let bytes: Vec<u8> = (0..64).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut buffer = CurrentBuffer::from_data_and_path(bytes[..16].to_vec(), Some(&path));
+        buffer.jump_to_file_offset(40, 12, 8).unwrap();
+        buffer.overwrite_at_file_offset(41, &[0xff]).unwrap();
+        buffer.write_to(&path).unwrap();
+
+        // Opening starts at the file's start, then goes to the saved window
+        let mut reopened = CurrentBuffer::from_data_and_path(bytes[..16].to_vec(), Some(&path));
+        assert_eq!((reopened.data_start_offset, reopened.data.len()), (32, 12));
+        assert_eq!(reopened.data.slice_to_cow(9..10)[0], 0xff);
+        assert!(reopened.perform_undo().is_some());
+        assert_eq!(reopened.data.slice_to_cow(9..10)[0], 41);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
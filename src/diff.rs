@@ -0,0 +1,280 @@
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::byte_source::{ByteSource, FileSource};
+
+const SCAN_CHUNK: usize = 1 << 20;
+const SUMMARY_RANGES: usize = 6;
+
+/// Whether the two sources differ at each offset of `range`. Past the end of
+/// the shorter source, every byte counts as different.
+fn difference_mask(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    range: Range<u64>,
+) -> io::Result<Vec<bool>> {
+    let len = (range.end - range.start) as usize;
+    let bytes_a = a.read_at(range.start, len)?;
+    let bytes_b = b.read_at(range.start, len)?;
+    Ok((0..len)
+        .map(|i| bytes_a.get(i) != bytes_b.get(i))
+        .collect())
+}
+
+fn compared_len(a: &dyn ByteSource, b: &dyn ByteSource) -> u64 {
+    cmp::max(a.len(), b.len())
+}
+
+/// First offset at or after `from` where "differs" equals `differs`.
+fn find_forward(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    from: u64,
+    differs: bool,
+) -> io::Result<Option<u64>> {
+    let end = compared_len(a, b);
+    let mut start = from;
+    while start < end {
+        let chunk_end = cmp::min(end, start + SCAN_CHUNK as u64);
+        let mask = difference_mask(a, b, start..chunk_end)?;
+        if let Some(i) = mask.iter().position(|&d| d == differs) {
+            return Ok(Some(start + i as u64));
+        }
+        start = chunk_end;
+    }
+    Ok(None)
+}
+
+/// Last offset at or before `from` where "differs" equals `differs`.
+fn find_backward(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    from: u64,
+    differs: bool,
+) -> io::Result<Option<u64>> {
+    let mut end = cmp::min(from + 1, compared_len(a, b));
+    while end > 0 {
+        let chunk_start = end.saturating_sub(SCAN_CHUNK as u64);
+        let mask = difference_mask(a, b, chunk_start..end)?;
+        if let Some(i) = mask.iter().rposition(|&d| d == differs) {
+            return Ok(Some(chunk_start + i as u64));
+        }
+        end = chunk_start;
+    }
+    Ok(None)
+}
+
+/// Start of the first difference run after the one containing `from`.
+pub fn next_difference(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    from: u64,
+) -> io::Result<Option<u64>> {
+    match find_forward(a, b, from, false)? {
+        Some(same) => find_forward(a, b, same, true),
+        None => Ok(None),
+    }
+}
+
+/// Start of the last difference run before the one containing `from`.
+pub fn prev_difference(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    from: u64,
+) -> io::Result<Option<u64>> {
+    let same = match find_backward(a, b, from, false)? {
+        Some(same) => same,
+        None => return Ok(None),
+    };
+    let run_end = match find_backward(a, b, same, true)? {
+        Some(run_end) => run_end,
+        None => return Ok(None),
+    };
+    Ok(Some(
+        find_backward(a, b, run_end, false)?.map_or(0, |before| before + 1),
+    ))
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    pub run_count: usize,
+    pub byte_count: u64,
+    /// The first few runs
+    pub runs: Vec<Range<u64>>,
+}
+
+impl DiffSummary {
+    fn add_run(&mut self, run: Range<u64>) {
+        self.run_count += 1;
+        self.byte_count += run.end - run.start;
+        if self.runs.len() < SUMMARY_RANGES {
+            self.runs.push(run);
+        }
+    }
+}
+
+/// Reads both sources once, a chunk at a time, keeping track of the run of
+/// differences across chunk boundaries. Fails with `Interrupted` once
+/// `cancelled` is set.
+pub fn summarize(
+    a: &dyn ByteSource,
+    b: &dyn ByteSource,
+    cancelled: &AtomicBool,
+) -> io::Result<DiffSummary> {
+    let mut summary = DiffSummary::default();
+    let end = compared_len(a, b);
+    let mut run_start = None;
+    let mut start = 0;
+    while start < end {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        let chunk_end = cmp::min(end, start + SCAN_CHUNK as u64);
+        let mask = difference_mask(a, b, start..chunk_end)?;
+        for (offset, differs) in (start..chunk_end).zip(mask) {
+            match (differs, run_start) {
+                (true, None) => run_start = Some(offset),
+                (false, Some(run)) => {
+                    summary.add_run(run..offset);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        start = chunk_end;
+    }
+    if let Some(run) = run_start {
+        summary.add_run(run..end);
+    }
+    Ok(summary)
+}
+
+/// The file a buffer is compared against, kept open while comparing.
+pub struct DiffTarget {
+    pub path: PathBuf,
+    pub source: FileSource,
+}
+
+impl DiffTarget {
+    pub fn open(path: &Path) -> io::Result<DiffTarget> {
+        Ok(DiffTarget {
+            path: path.to_owned(),
+            source: FileSource::open(path)?,
+        })
+    }
+}
+
+/// A `summarize` running on a background thread, so comparing large files
+/// doesn't block the editor. Dropping it stops the thread.
+pub struct SummaryJob {
+    result: Arc<Mutex<Option<io::Result<DiffSummary>>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SummaryJob {
+    pub fn start(
+        a: impl ByteSource + Send + 'static,
+        b: impl ByteSource + Send + 'static,
+    ) -> SummaryJob {
+        let job = SummaryJob {
+            result: Arc::new(Mutex::new(None)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+
+        let result = Arc::clone(&job.result);
+        let cancelled = Arc::clone(&job.cancelled);
+        thread::spawn(move || {
+            let summary = summarize(&a, &b, &cancelled);
+            *result.lock().unwrap() = Some(summary);
+        });
+
+        job
+    }
+
+    /// The outcome, once the thread is done.
+    pub fn take_result(&self) -> Option<io::Result<DiffSummary>> {
+        self.result.lock().unwrap().take()
+    }
+}
+
+impl Drop for SummaryJob {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.run_count == 0 {
+            return write!(f, "no differences");
+        }
+        write!(
+            f,
+            "{} bytes differ in {} ranges:",
+            self.byte_count, self.run_count
+        )?;
+        for run in &self.runs {
+            write!(f, " {:x}..{:x}", run.start, run.end)?;
+        }
+        if self.run_count > self.runs.len() {
+            write!(f, " ...")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difference_runs() {
+        let a = vec![0u8, 1, 2, 3, 4, 5, 6, 7];
+        let b = vec![0u8, 9, 9, 3, 4, 9, 6, 7, 8, 8];
+
+        assert_eq!(next_difference(&a, &b, 0).unwrap(), Some(1));
+        assert_eq!(next_difference(&a, &b, 1).unwrap(), Some(5));
+        assert_eq!(next_difference(&a, &b, 5).unwrap(), Some(8));
+        assert_eq!(next_difference(&a, &b, 8).unwrap(), None);
+        assert_eq!(prev_difference(&a, &b, 8).unwrap(), Some(5));
+        assert_eq!(prev_difference(&a, &b, 6).unwrap(), Some(5));
+        assert_eq!(prev_difference(&a, &b, 5).unwrap(), Some(1));
+        assert_eq!(prev_difference(&a, &b, 2).unwrap(), None);
+
+        let summary = summarize(&a, &b, &AtomicBool::new(false)).unwrap();
+        assert_eq!(summary.run_count, 3);
+        assert_eq!(summary.byte_count, 5);
+        assert_eq!(summary.runs, vec![1..3, 5..6, 8..10]);
+
+        let job = SummaryJob::start(a, b);
+        let summary = loop {
+            match job.take_result() {
+                Some(summary) => break summary.unwrap(),
+                None => thread::yield_now(),
+            }
+        };
+        assert_eq!(summary.run_count, 3);
+    }
+
+    #[test]
+    fn test_summary_across_chunks() {
+        let a = vec![0u8; SCAN_CHUNK + 4];
+        let mut b = a.clone();
+        b[SCAN_CHUNK - 1] = 1;
+        b[SCAN_CHUNK] = 1;
+        b[SCAN_CHUNK + 2] = 1;
+        let summary = summarize(&a, &b, &AtomicBool::new(false)).unwrap();
+        let chunk = SCAN_CHUNK as u64;
+        assert_eq!(summary.runs, vec![chunk - 1..chunk + 1, chunk + 2..chunk + 3]);
+        assert_eq!(summary.byte_count, 3);
+
+        let cancelled = summarize(&a, &b, &AtomicBool::new(true));
+        assert_eq!(cancelled.unwrap_err().kind(), io::ErrorKind::Interrupted);
+    }
+}
@@ -0,0 +1,306 @@
+use lazy_static::lazy_static;
+
+const BLOCK_LEN: usize = 64;
+
+/// Buffers input into the 64-byte blocks MD5 and SHA work on, and pads the
+/// last one with the message length.
+#[derive(Debug, Clone)]
+struct Blocks {
+    buffer: [u8; BLOCK_LEN],
+    filled: usize,
+    len: u64,
+}
+
+impl Blocks {
+    fn new() -> Blocks {
+        Blocks {
+            buffer: [0; BLOCK_LEN],
+            filled: 0,
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8], mut compress: impl FnMut(&[u8; BLOCK_LEN])) {
+        self.len = self.len.wrapping_add(bytes.len() as u64);
+        while !bytes.is_empty() {
+            let taken = std::cmp::min(BLOCK_LEN - self.filled, bytes.len());
+            self.buffer[self.filled..self.filled + taken].copy_from_slice(&bytes[..taken]);
+            self.filled += taken;
+            bytes = &bytes[taken..];
+            if self.filled == BLOCK_LEN {
+                compress(&self.buffer);
+                self.filled = 0;
+            }
+        }
+    }
+
+    fn finish(&mut self, big_endian: bool, mut compress: impl FnMut(&[u8; BLOCK_LEN])) {
+        let bits = self.len.wrapping_mul(8);
+        let len_bytes = if big_endian {
+            bits.to_be_bytes()
+        } else {
+            bits.to_le_bytes()
+        };
+        let padding_len = (BLOCK_LEN * 2 - 8 - 1 - self.filled) % BLOCK_LEN;
+        let mut padding = vec![0x80];
+        padding.resize(1 + padding_len, 0);
+        padding.extend(len_bytes);
+        let len = self.len;
+        self.update(&padding, &mut compress);
+        self.len = len;
+    }
+}
+
+fn words<const N: usize>(block: &[u8; BLOCK_LEN], big_endian: bool) -> [u32; N] {
+    let mut words = [0; N];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        let bytes = bytes.try_into().unwrap();
+        *word = if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+    }
+    words
+}
+
+/// The 32 bits after the point of `root(n)`, for SHA-256's constants.
+fn fraction_bits(n: u128, root: u32) -> u32 {
+    let scaled = n << (32 * root);
+    let mut x = (scaled as f64).powf(1.0 / root as f64) as u128;
+    while x.pow(root) > scaled {
+        x -= 1;
+    }
+    while (x + 1).pow(root) <= scaled {
+        x += 1;
+    }
+    x as u32
+}
+
+lazy_static! {
+    static ref MD5_K: [u32; 64] = {
+        let mut k = [0; 64];
+        for (i, k) in k.iter_mut().enumerate() {
+            *k = ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32;
+        }
+        k
+    };
+    static ref PRIMES: Vec<u128> = (2..)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .take(64)
+        .collect();
+    static ref SHA256_K: Vec<u32> = PRIMES.iter().map(|&p| fraction_bits(p, 3)).collect();
+    static ref SHA256_INIT: Vec<u32> = PRIMES[..8].iter().map(|&p| fraction_bits(p, 2)).collect();
+}
+
+const MD5_SHIFTS: [[u32; 4]; 4] = [[7, 12, 17, 22], [5, 9, 14, 20], [4, 11, 16, 23], [6, 10, 15, 21]];
+
+#[derive(Debug, Clone)]
+pub struct Md5 {
+    state: [u32; 4],
+    blocks: Blocks,
+}
+
+impl Md5 {
+    pub fn new() -> Md5 {
+        Md5 {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476],
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn compress(state: &mut [u32; 4], block: &[u8; BLOCK_LEN]) {
+        let m: [u32; 16] = words(block, false);
+        let [mut a, mut b, mut c, mut d] = *state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_SHIFTS[i / 16][i % 4]));
+        }
+        for (word, new) in state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(new);
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(bytes, |block| Md5::compress(state, block));
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(false, |block| Md5::compress(state, block));
+        self.state.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    blocks: Blocks,
+}
+
+impl Sha1 {
+    pub fn new() -> Sha1 {
+        Sha1 {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0],
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn compress(state: &mut [u32; 5], block: &[u8; BLOCK_LEN]) {
+        let mut w = [0u32; 80];
+        w[..16].copy_from_slice(&words::<16>(block, true));
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = *state;
+        for (i, &w) in w.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a82_7999),
+                1 => (b ^ c ^ d, 0x6ed9_eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (word, new) in state.iter_mut().zip([a, b, c, d, e]) {
+            *word = word.wrapping_add(new);
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(bytes, |block| Sha1::compress(state, block));
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(true, |block| Sha1::compress(state, block));
+        self.state.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    blocks: Blocks,
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        let mut state = [0; 8];
+        state.copy_from_slice(&SHA256_INIT);
+        Sha256 {
+            state,
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn compress(state: &mut [u32; 8], block: &[u8; BLOCK_LEN]) {
+        let mut w = [0u32; 64];
+        w[..16].copy_from_slice(&words::<16>(block, true));
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for (&w, &k) in w.iter().zip(SHA256_K.iter()) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (word, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(new);
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(bytes, |block| Sha256::compress(state, block));
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(true, |block| Sha256::compress(state, block));
+        self.state.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: Vec<u8>) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_digests() {
+        let md5 = |bytes: &[u8]| {
+            let mut md5 = Md5::new();
+            md5.update(bytes);
+            hex(md5.finish())
+        };
+        assert_eq!(md5(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            md5(b"The quick brown fox jumps over the lazy dog"),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+
+        let mut sha1 = Sha1::new();
+        sha1.update(b"abcdbcdecdefdefgefghfghighijhi");
+        sha1.update(b"jkijkljklmklmnlmnomnopnopq");
+        assert_eq!(hex(sha1.finish()), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+
+        let mut sha256 = Sha256::new();
+        sha256.update(b"abc");
+        assert_eq!(
+            hex(sha256.finish()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let mut sha256 = Sha256::new();
+        sha256.update(&[b'a'; 1000]);
+        assert_eq!(
+            hex(sha256.finish()),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+}
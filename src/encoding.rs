@@ -0,0 +1,158 @@
+use crate::outline::{parse_int, IntFormat};
+
+pub const TYPE_NAMES: &str =
+    "u8..u64, i8..i64, f32, f64 (le/be suffix), ascii, utf8, utf16le, utf16be, hex";
+
+/// Encodes `value` as type `ty` for `:put`: integers and floats take an
+/// optional `le`/`be` suffix (little endian by default), strings are quoted
+/// and `hex` takes pairs of digits, spaces allowed.
+pub fn encode_value(ty: &str, value: &str) -> Result<Vec<u8>, String> {
+    if let Some(format) = IntFormat::parse(ty) {
+        return format.encode(parse_int(value)?);
+    }
+    let value = value.trim();
+    let (base, big_endian) = match (ty.strip_suffix("be"), ty.strip_suffix("le")) {
+        (Some(base), _) => (base, true),
+        (_, Some(base)) => (base, false),
+        _ => (ty, false),
+    };
+    let mut bytes = match base {
+        "f32" => (parse_float(value)? as f32).to_be_bytes().to_vec(),
+        "f64" => parse_float(value)?.to_be_bytes().to_vec(),
+        "utf16" => unquote(value)?
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        "ascii" if ty == base => {
+            let text = unquote(value)?;
+            if !text.is_ascii() {
+                return Err(format!("not ASCII: {}", text));
+            }
+            text.into_bytes()
+        }
+        "utf8" if ty == base => unquote(value)?.into_bytes(),
+        "hex" if ty == base => parse_hex(value)?,
+        _ => return Err(format!("unknown type {}, types: {}", ty, TYPE_NAMES)),
+    };
+    // Floats were built big endian and UTF-16 little endian
+    match base {
+        "f32" | "f64" if !big_endian => bytes.reverse(),
+        "utf16" if big_endian => bytes.chunks_mut(2).for_each(|pair| pair.swap(0, 1)),
+        _ => {}
+    }
+    Ok(bytes)
+}
+
+fn parse_float(text: &str) -> Result<f64, String> {
+    text.parse::<f64>().map_err(|_| format!("not a number: {}", text))
+}
+
+/// The text between double quotes, with `\\`, `\"`, `\n`, `\r`, `\t`, `\0` and
+/// `\xNN` escapes, which give a code point rather than a raw byte.
+fn unquote(text: &str) -> Result<String, String> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string: {}", text))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let code = u8::from_str_radix(&digits, 16)
+                    .map_err(|_| format!("bad escape \\x{}", digits))?;
+                out.push(code as char);
+            }
+            Some(c @ ('\\' | '"')) => out.push(c),
+            Some(c) => return Err(format!("bad escape \\{}", c)),
+            None => return Err("string ends with \\".into()),
+        }
+    }
+    Ok(out)
+}
+
+/// Bytes from hex as it's usually written: `de ad be ef`, `deadbeef`,
+/// `0xde,0xad`, `{0xde, 0xad}`, `\xde\xad` or any of them quoted.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let tokens = text
+        .split(|c: char| c.is_whitespace() || ",;:".contains(c))
+        .map(|token| token.trim_matches(|c| "[]{}()\"'".contains(c)));
+    for token in tokens {
+        for (i, piece) in token.split("\\x").enumerate() {
+            let (digits, prefixed) = match piece.strip_prefix("0x").or_else(|| piece.strip_prefix("0X")) {
+                Some(digits) => (digits, true),
+                None => (piece, i > 0),
+            };
+            if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("not hex: {}", piece));
+            }
+            let digits = if prefixed && digits.len() == 1 {
+                format!("0{}", digits)
+            } else {
+                digits.to_string()
+            };
+            if !digits.len().is_multiple_of(2) {
+                return Err(format!("odd number of hex digits: {}", piece));
+            }
+            for pair in digits.as_bytes().chunks(2) {
+                let pair = std::str::from_utf8(pair).unwrap();
+                bytes.push(u8::from_str_radix(pair, 16).unwrap());
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        let deadbeef = vec![0xde, 0xad, 0xbe, 0xef];
+        let texts = [
+            "de ad be ef",
+            "deadbeef",
+            "0xde,0xad,0xbe,0xef",
+            "{0xde, 0xad, 0xbe, 0xef}",
+            "\\xde\\xad\\xbe\\xef",
+            "\"de ad be ef\"",
+        ];
+        for text in texts {
+            assert_eq!(parse_hex(text).unwrap(), deadbeef, "{}", text);
+        }
+        assert_eq!(parse_hex("0x1 \\x2").unwrap(), [1, 2]);
+        assert!(parse_hex("dea").is_err());
+        assert!(parse_hex("hello").is_err());
+    }
+
+    #[test]
+    fn test_encode_value() {
+        assert_eq!(encode_value("u32le", "0xdeadbeef").unwrap(), [0xef, 0xbe, 0xad, 0xde]);
+        assert_eq!(encode_value("i16be", "-2").unwrap(), [0xff, 0xfe]);
+        assert_eq!(
+            encode_value("f64be", "1.5").unwrap(),
+            [0x3f, 0xf8, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(encode_value("f32", "1.5").unwrap(), [0, 0, 0xc0, 0x3f]);
+        assert_eq!(encode_value("utf16le", "\"ab\"").unwrap(), [b'a', 0, b'b', 0]);
+        assert_eq!(encode_value("utf16be", "\"ab\"").unwrap(), [0, b'a', 0, b'b']);
+        assert_eq!(encode_value("utf8", "\"a\\x41\\\"\"").unwrap(), b"aA\"");
+        assert_eq!(encode_value("hex", "\"de ad be ef\"").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert!(encode_value("hex", "abc").is_err());
+        assert!(encode_value("hex", "+f").is_err());
+        assert_eq!(encode_value("hex", "\"0xde\"").unwrap(), [0xde]);
+        assert!(encode_value("u8", "256").is_err());
+        assert!(encode_value("f16", "1").is_err());
+    }
+}
@@ -0,0 +1,613 @@
+use std::ops::Range;
+
+use crate::byte_source::ByteSource;
+use crate::outline::{Editor, Layout, OutlineNode, Table};
+
+/// Largest section name table read to name ELF sections
+const MAX_STRING_TABLE: usize = 1 << 20;
+
+/// A named part of the file, as listed in the section table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub range: Range<u64>,
+}
+
+pub fn section_at(sections: &[Section], offset: u64) -> Option<&Section> {
+    sections.iter().find(|section| section.range.contains(&offset))
+}
+
+/// The headers of an ELF or PE file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Executable {
+    /// e.g. "ELF64 little endian"
+    pub format: String,
+    pub roots: Vec<OutlineNode>,
+    /// Sections that have bytes in the file
+    pub sections: Vec<Section>,
+}
+
+impl Executable {
+    /// Reads just the headers and tables, so the rest of the file needn't be loaded.
+    pub fn parse(source: &dyn ByteSource) -> Result<Executable, String> {
+        let magic = source
+            .read_at(0, 4)
+            .map_err(|e| format!("can't read headers: {}", e))?;
+        if magic == b"\x7fELF" {
+            parse_elf(source)
+        } else if magic.starts_with(b"MZ") {
+            parse_pe(source)
+        } else {
+            Err("not an ELF or PE file".into())
+        }
+    }
+}
+
+const ELF32_HEADER: &Layout = &[
+    ("type", 16, 2, Some(elf_type_name)),
+    ("machine", 18, 2, Some(elf_machine_name)),
+    ("version", 20, 4, None),
+    ("entry", 24, 4, None),
+    ("phoff", 28, 4, None),
+    ("shoff", 32, 4, None),
+    ("flags", 36, 4, None),
+    ("ehsize", 40, 2, None),
+    ("phentsize", 42, 2, None),
+    ("phnum", 44, 2, None),
+    ("shentsize", 46, 2, None),
+    ("shnum", 48, 2, None),
+    ("shstrndx", 50, 2, None),
+];
+
+const ELF64_HEADER: &Layout = &[
+    ("type", 16, 2, Some(elf_type_name)),
+    ("machine", 18, 2, Some(elf_machine_name)),
+    ("version", 20, 4, None),
+    ("entry", 24, 8, None),
+    ("phoff", 32, 8, None),
+    ("shoff", 40, 8, None),
+    ("flags", 48, 4, None),
+    ("ehsize", 52, 2, None),
+    ("phentsize", 54, 2, None),
+    ("phnum", 56, 2, None),
+    ("shentsize", 58, 2, None),
+    ("shnum", 60, 2, None),
+    ("shstrndx", 62, 2, None),
+];
+
+const ELF32_PROGRAM_HEADER: &Layout = &[
+    ("type", 0, 4, Some(elf_segment_type_name)),
+    ("offset", 4, 4, None),
+    ("vaddr", 8, 4, None),
+    ("paddr", 12, 4, None),
+    ("filesz", 16, 4, None),
+    ("memsz", 20, 4, None),
+    ("flags", 24, 4, None),
+    ("align", 28, 4, None),
+];
+
+const ELF64_PROGRAM_HEADER: &Layout = &[
+    ("type", 0, 4, Some(elf_segment_type_name)),
+    ("flags", 4, 4, None),
+    ("offset", 8, 8, None),
+    ("vaddr", 16, 8, None),
+    ("paddr", 24, 8, None),
+    ("filesz", 32, 8, None),
+    ("memsz", 40, 8, None),
+    ("align", 48, 8, None),
+];
+
+const ELF32_SECTION_HEADER: &Layout = &[
+    ("name", 0, 4, None),
+    ("type", 4, 4, Some(elf_section_type_name)),
+    ("flags", 8, 4, None),
+    ("addr", 12, 4, None),
+    ("offset", 16, 4, None),
+    ("size", 20, 4, None),
+    ("link", 24, 4, None),
+    ("info", 28, 4, None),
+    ("addralign", 32, 4, None),
+    ("entsize", 36, 4, None),
+];
+
+const ELF64_SECTION_HEADER: &Layout = &[
+    ("name", 0, 4, None),
+    ("type", 4, 4, Some(elf_section_type_name)),
+    ("flags", 8, 8, None),
+    ("addr", 16, 8, None),
+    ("offset", 24, 8, None),
+    ("size", 32, 8, None),
+    ("link", 40, 4, None),
+    ("info", 44, 4, None),
+    ("addralign", 48, 8, None),
+    ("entsize", 56, 8, None),
+];
+
+const SHT_NOBITS: u64 = 8;
+
+fn elf_type_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0 => "NONE",
+        1 => "REL",
+        2 => "EXEC",
+        3 => "DYN",
+        4 => "CORE",
+        _ => return None,
+    })
+}
+
+fn elf_machine_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0x03 => "x86",
+        0x08 => "MIPS",
+        0x14 => "PowerPC",
+        0x15 => "PowerPC64",
+        0x28 => "ARM",
+        0x3e => "x86-64",
+        0xb7 => "AArch64",
+        0xf3 => "RISC-V",
+        _ => return None,
+    })
+}
+
+fn elf_segment_type_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0 => "NULL",
+        1 => "LOAD",
+        2 => "DYNAMIC",
+        3 => "INTERP",
+        4 => "NOTE",
+        5 => "SHLIB",
+        6 => "PHDR",
+        7 => "TLS",
+        0x6474_e550 => "GNU_EH_FRAME",
+        0x6474_e551 => "GNU_STACK",
+        0x6474_e552 => "GNU_RELRO",
+        0x6474_e553 => "GNU_PROPERTY",
+        _ => return None,
+    })
+}
+
+fn elf_section_type_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0 => "NULL",
+        1 => "PROGBITS",
+        2 => "SYMTAB",
+        3 => "STRTAB",
+        4 => "RELA",
+        5 => "HASH",
+        6 => "DYNAMIC",
+        7 => "NOTE",
+        8 => "NOBITS",
+        9 => "REL",
+        11 => "DYNSYM",
+        14 => "INIT_ARRAY",
+        15 => "FINI_ARRAY",
+        0x6fff_fff6 => "GNU_HASH",
+        0x6fff_fffe => "VERNEED",
+        0x6fff_ffff => "VERSYM",
+        _ => return None,
+    })
+}
+
+/// `len` bytes from `offset`, unless that runs past the largest offset.
+fn file_range(offset: u64, len: u64) -> Option<Range<u64>> {
+    Some(offset..offset.checked_add(len)?)
+}
+
+/// The NUL-terminated string at `at` in a string table.
+fn string_at(table: &[u8], at: usize) -> Option<String> {
+    let bytes = table.get(at..)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+fn parse_elf(source: &dyn ByteSource) -> Result<Executable, String> {
+    let ident = Table::read(source, 0, 16, false, "ELF identification")?;
+    let wide = match ident.bytes[4] {
+        1 => false,
+        2 => true,
+        class => return Err(format!("unknown ELF class {}", class)),
+    };
+    let big_endian = match ident.bytes[5] {
+        1 => false,
+        2 => true,
+        data => return Err(format!("unknown ELF byte order {}", data)),
+    };
+    let format = format!(
+        "ELF{} {} endian",
+        if wide { 64 } else { 32 },
+        if big_endian { "big" } else { "little" }
+    );
+    let (header_layout, program_layout, section_layout) = if wide {
+        (ELF64_HEADER, ELF64_PROGRAM_HEADER, ELF64_SECTION_HEADER)
+    } else {
+        (ELF32_HEADER, ELF32_PROGRAM_HEADER, ELF32_SECTION_HEADER)
+    };
+
+    let header = Table::read(source, 0, if wide { 64 } else { 52 }, big_endian, "ELF header")?;
+    let mut header_fields = vec![OutlineNode::new("ident", format.clone(), 0..16)];
+    header_fields.extend(header.fields(0, header_layout));
+    let mut roots = vec![OutlineNode::new("ELF header", "", header.range()).with_children(header_fields)];
+    let header_value = |label| header.get_field(0, header_layout, label);
+
+    let phnum = header_value("phnum") as usize;
+    let phentsize = header_value("phentsize") as usize;
+    if phnum > 0 {
+        if phentsize < program_layout.iter().map(|f| f.1 + f.2).max().unwrap_or(0) {
+            return Err(format!("program header size {} is too small", phentsize));
+        }
+        let table = Table::read(
+            source,
+            header_value("phoff"),
+            phnum.checked_mul(phentsize).ok_or("program header table is too large")?,
+            big_endian,
+            "program header table",
+        )?;
+        let segments = (0..phnum)
+            .map(|i| {
+                let base = i * phentsize;
+                let value = |label| table.get_field(base, program_layout, label);
+                let kind = value("type");
+                let name = elf_segment_type_name(kind)
+                    .map_or_else(|| format!("0x{:x}", kind), str::to_string);
+                let offset = value("offset");
+                OutlineNode::new(
+                    format!("[{}] {}", i, name),
+                    format!("vaddr 0x{:x} size 0x{:x}", value("vaddr"), value("filesz")),
+                    file_range(offset, value("filesz")).unwrap_or(offset..offset),
+                )
+                .with_children(table.fields(base, program_layout))
+            })
+            .collect();
+        roots.push(
+            OutlineNode::new("program headers", format!("{} entries", phnum), table.range())
+                .with_children(segments),
+        );
+    }
+
+    let shnum = header_value("shnum") as usize;
+    let shentsize = header_value("shentsize") as usize;
+    let mut sections = vec![];
+    if shnum > 0 {
+        if shentsize < section_layout.iter().map(|f| f.1 + f.2).max().unwrap_or(0) {
+            return Err(format!("section header size {} is too small", shentsize));
+        }
+        let table = Table::read(
+            source,
+            header_value("shoff"),
+            shnum.checked_mul(shentsize).ok_or("section header table is too large")?,
+            big_endian,
+            "section header table",
+        )?;
+        let shstrndx = header_value("shstrndx") as usize;
+        let names = if shstrndx < shnum {
+            let value = |label| table.get_field(shstrndx * shentsize, section_layout, label);
+            let len = std::cmp::min(value("size") as usize, MAX_STRING_TABLE);
+            source.read_at(value("offset"), len).unwrap_or_default()
+        } else {
+            vec![]
+        };
+
+        let nodes = (0..shnum)
+            .map(|i| {
+                let base = i * shentsize;
+                let value = |label| table.get_field(base, section_layout, label);
+                let name = string_at(&names, value("name") as usize).unwrap_or_default();
+                let kind = value("type");
+                let offset = value("offset");
+                let len = if kind == SHT_NOBITS || kind == 0 { 0 } else { value("size") };
+                let range = file_range(offset, len);
+                if let Some(range) = range.clone().filter(|range| !range.is_empty()) {
+                    sections.push(Section {
+                        name: name.clone(),
+                        range,
+                    });
+                }
+                let kind = elf_section_type_name(kind)
+                    .map_or_else(|| format!("0x{:x}", kind), str::to_string);
+                OutlineNode::new(
+                    format!("[{}] {}", i, name),
+                    format!("{} addr 0x{:x} size 0x{:x}", kind, value("addr"), value("size")),
+                    range.unwrap_or(offset..offset),
+                )
+                .with_children(table.fields(base, section_layout))
+            })
+            .collect();
+        roots.push(
+            OutlineNode::new("sections", format!("{} entries", shnum), table.range())
+                .with_children(nodes),
+        );
+    }
+
+    Ok(Executable {
+        format,
+        roots,
+        sections,
+    })
+}
+
+const DOS_HEADER: &Layout = &[("PE header offset", 0x3c, 4, None)];
+
+const COFF_HEADER: &Layout = &[
+    ("machine", 0, 2, Some(pe_machine_name)),
+    ("number of sections", 2, 2, None),
+    ("timestamp", 4, 4, None),
+    ("symbol table", 8, 4, None),
+    ("number of symbols", 12, 4, None),
+    ("optional header size", 16, 2, None),
+    ("characteristics", 18, 2, None),
+];
+
+const PE32_OPTIONAL_HEADER: &Layout = &[
+    ("magic", 0, 2, Some(pe_magic_name)),
+    ("entry point", 16, 4, None),
+    ("base of code", 20, 4, None),
+    ("image base", 28, 4, None),
+    ("section alignment", 32, 4, None),
+    ("file alignment", 36, 4, None),
+    ("size of image", 56, 4, None),
+    ("size of headers", 60, 4, None),
+    ("checksum", 64, 4, None),
+    ("subsystem", 68, 2, Some(pe_subsystem_name)),
+    ("dll characteristics", 70, 2, None),
+    ("number of directories", 92, 4, None),
+];
+
+const PE32_PLUS_OPTIONAL_HEADER: &Layout = &[
+    ("magic", 0, 2, Some(pe_magic_name)),
+    ("entry point", 16, 4, None),
+    ("base of code", 20, 4, None),
+    ("image base", 24, 8, None),
+    ("section alignment", 32, 4, None),
+    ("file alignment", 36, 4, None),
+    ("size of image", 56, 4, None),
+    ("size of headers", 60, 4, None),
+    ("checksum", 64, 4, None),
+    ("subsystem", 68, 2, Some(pe_subsystem_name)),
+    ("dll characteristics", 70, 2, None),
+    ("number of directories", 108, 4, None),
+];
+
+const PE_DATA_DIRECTORY: &Layout = &[("rva", 0, 4, None), ("size", 4, 4, None)];
+
+const PE_DATA_DIRECTORIES: [&str; 16] = [
+    "export",
+    "import",
+    "resource",
+    "exception",
+    "certificate",
+    "base relocation",
+    "debug",
+    "architecture",
+    "global pointer",
+    "TLS",
+    "load config",
+    "bound import",
+    "IAT",
+    "delay import",
+    "CLR runtime",
+    "reserved",
+];
+
+const PE_SECTION_HEADER: &Layout = &[
+    ("virtual size", 8, 4, None),
+    ("virtual address", 12, 4, None),
+    ("raw size", 16, 4, None),
+    ("raw pointer", 20, 4, None),
+    ("relocations pointer", 24, 4, None),
+    ("line numbers pointer", 28, 4, None),
+    ("number of relocations", 32, 2, None),
+    ("number of line numbers", 34, 2, None),
+    ("characteristics", 36, 4, None),
+];
+
+const PE_SECTION_HEADER_LEN: usize = 40;
+
+fn pe_machine_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0x14c => "i386",
+        0x1c0 => "ARM",
+        0x1c4 => "ARMv7",
+        0x8664 => "AMD64",
+        0xaa64 => "ARM64",
+        _ => return None,
+    })
+}
+
+fn pe_magic_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0x10b => "PE32",
+        0x20b => "PE32+",
+        _ => return None,
+    })
+}
+
+fn pe_subsystem_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        1 => "native",
+        2 => "Windows GUI",
+        3 => "Windows console",
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        _ => return None,
+    })
+}
+
+fn parse_pe(source: &dyn ByteSource) -> Result<Executable, String> {
+    let dos = Table::read(source, 0, 64, false, "DOS header")?;
+    let pe_offset = dos.get(0x3c, 4);
+    let signature = source.read_at(pe_offset, 4).unwrap_or_default();
+    if signature != b"PE\0\0" {
+        return Err(format!("no PE signature at 0x{:x}", pe_offset));
+    }
+    let mut dos_fields = vec![OutlineNode::new("magic", "MZ", 0..2)];
+    dos_fields.extend(dos.fields(0, DOS_HEADER));
+    let mut roots = vec![OutlineNode::new("DOS header", "", dos.range()).with_children(dos_fields)];
+
+    let coff = Table::read(source, pe_offset + 4, 20, false, "COFF header")?;
+    roots.push(OutlineNode::new("COFF header", "", coff.range()).with_children(coff.fields(0, COFF_HEADER)));
+    let optional_len = coff.get_field(0, COFF_HEADER, "optional header size") as usize;
+    let optional_offset = pe_offset + 24;
+
+    let mut format = "PE".to_string();
+    if optional_len > 0 {
+        let optional = Table::read(source, optional_offset, optional_len, false, "optional header")?;
+        let (layout, directories_at) = match optional.get(0, 2) {
+            0x20b => (PE32_PLUS_OPTIONAL_HEADER, 112),
+            _ => (PE32_OPTIONAL_HEADER, 96),
+        };
+        format = pe_magic_name(optional.get(0, 2)).unwrap_or("PE").to_string();
+        let directory_count = std::cmp::min(
+            optional.get_field(0, layout, "number of directories") as usize,
+            optional_len.saturating_sub(directories_at) / 8,
+        );
+        let directories: Vec<OutlineNode> = PE_DATA_DIRECTORIES
+            .iter()
+            .take(directory_count)
+            .enumerate()
+            .map(|(i, name)| {
+                let base = directories_at + i * 8;
+                let value = |label| optional.get_field(base, PE_DATA_DIRECTORY, label);
+                let start = optional.offset + base as u64;
+                OutlineNode::new(
+                    *name,
+                    format!("rva 0x{:x} size 0x{:x}", value("rva"), value("size")),
+                    start..start + 8,
+                )
+                .with_children(optional.fields(base, PE_DATA_DIRECTORY))
+            })
+            .collect();
+        let mut fields = optional.fields(0, layout);
+        if !directories.is_empty() {
+            let start = optional.offset + directories_at as u64;
+            fields.push(
+                OutlineNode::new("data directories", "", start..start + 8 * directory_count as u64)
+                    .with_children(directories),
+            );
+        }
+        roots.push(OutlineNode::new("optional header", "", optional.range()).with_children(fields));
+    }
+
+    let section_count = coff.get_field(0, COFF_HEADER, "number of sections") as usize;
+    let table = Table::read(
+        source,
+        optional_offset + optional_len as u64,
+        section_count * PE_SECTION_HEADER_LEN,
+        false,
+        "section table",
+    )?;
+    let mut sections = vec![];
+    let nodes = (0..section_count)
+        .map(|i| {
+            let base = i * PE_SECTION_HEADER_LEN;
+            let value = |label| table.get_field(base, PE_SECTION_HEADER, label);
+            let name = string_at(&table.bytes[base..base + 8], 0).unwrap_or_default();
+            let offset = value("raw pointer");
+            let range = file_range(offset, value("raw size")).unwrap_or(offset..offset);
+            if !range.is_empty() {
+                sections.push(Section {
+                    name: name.clone(),
+                    range: range.clone(),
+                });
+            }
+            let start = table.offset + base as u64;
+            let mut fields = vec![OutlineNode::new("name", name.clone(), start..start + 8)
+                .with_editor(Editor::Text(8))];
+            fields.extend(table.fields(base, PE_SECTION_HEADER));
+            OutlineNode::new(
+                format!("[{}] {}", i, name),
+                format!("rva 0x{:x} size 0x{:x}", value("virtual address"), value("raw size")),
+                range,
+            )
+            .with_children(fields)
+        })
+        .collect();
+    roots.push(
+        OutlineNode::new("sections", format!("{} entries", section_count), table.range())
+            .with_children(nodes),
+    );
+
+    Ok(Executable {
+        format,
+        roots,
+        sections,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::tests::put;
+
+    #[test]
+    fn test_elf64_sections() {
+        // Header, two section headers at 0x40 (null, .shstrtab), then the names
+        let mut elf = vec![0u8; 0xd0];
+        elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        put(&mut elf, 16, 2, 2);
+        put(&mut elf, 18, 0x3e, 2);
+        put(&mut elf, 40, 0x40, 8);
+        put(&mut elf, 58, 0x40, 2);
+        put(&mut elf, 60, 2, 2);
+        put(&mut elf, 62, 1, 2);
+        put(&mut elf, 0x80 + 4, 3, 4);
+        put(&mut elf, 0x80 + 24, 0xc0, 8);
+        put(&mut elf, 0x80 + 32, 0x0b, 8);
+        elf[0xc0..0xcb].copy_from_slice(b"\0.shstrtab\0");
+        put(&mut elf, 0x80, 1, 4);
+
+        let exe = Executable::parse(&elf).unwrap();
+        assert_eq!(exe.format, "ELF64 little endian");
+        assert_eq!(exe.roots[0].children[1].value, "EXEC (0x2)");
+        assert_eq!(
+            exe.sections,
+            vec![Section {
+                name: ".shstrtab".into(),
+                range: 0xc0..0xcb
+            }]
+        );
+        assert_eq!(section_at(&exe.sections, 0xc4).unwrap().name, ".shstrtab");
+        assert!(section_at(&exe.sections, 0x10).is_none());
+        assert_eq!(exe.roots[1].children[1].label, "[1] .shstrtab");
+
+        assert!(Executable::parse(&elf[..0x30].to_vec()).is_err());
+        assert!(Executable::parse(&vec![0u8; 64]).is_err());
+
+        // Ranges and tables that don't fit are dropped, not read
+        put(&mut elf, 0x80 + 24, u64::MAX - 1, 8);
+        assert!(Executable::parse(&elf).unwrap().sections.is_empty());
+        put(&mut elf, 32, 0xff00, 8);
+        put(&mut elf, 54, 0x38, 2);
+        put(&mut elf, 56, 0xffff, 2);
+        assert!(Executable::parse(&elf).is_err());
+    }
+
+    #[test]
+    fn test_pe_sections() {
+        let mut pe = vec![0u8; 0x200];
+        pe[..2].copy_from_slice(b"MZ");
+        put(&mut pe, 0x3c, 0x80, 4);
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        put(&mut pe, 0x84, 0x8664, 2);
+        put(&mut pe, 0x86, 1, 2);
+        put(&mut pe, 0x94, 0x70, 2);
+        put(&mut pe, 0x98, 0x20b, 2);
+        let section = 0x98 + 0x70;
+        pe[section..section + 5].copy_from_slice(b".text");
+        put(&mut pe, section + 16, 0x10, 4);
+        put(&mut pe, section + 20, 0x1f0, 4);
+
+        let exe = Executable::parse(&pe).unwrap();
+        assert_eq!(exe.format, "PE32+");
+        assert_eq!(exe.roots[1].children[0].value, "AMD64 (0x8664)");
+        assert_eq!(
+            exe.sections,
+            vec![Section {
+                name: ".text".into(),
+                range: 0x1f0..0x200
+            }]
+        );
+    }
+}
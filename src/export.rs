@@ -0,0 +1,228 @@
+use std::fmt::Write;
+
+/// Most bytes `:export` reads for an offset range.
+pub const MAX_EXPORT_LEN: usize = 16 * 1024 * 1024;
+
+/// Text formats bytes can be exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `xxd`
+    Xxd,
+    /// `hexdump -C`
+    HexdumpCanonical,
+    /// `od -Ax -tx1`
+    Od,
+    /// Continuous lowercase hex digits
+    Hex,
+    /// A C `uint8_t` array
+    CArray,
+    /// A Rust `[u8; N]` array
+    RustArray,
+    /// A Python bytes literal
+    Python,
+    Base64,
+}
+
+pub const FORMAT_NAMES: &str = "xxd hexdump od hex c rust python base64";
+
+const BYTES_PER_DUMP_LINE: usize = 16;
+const BYTES_PER_ARRAY_LINE: usize = 12;
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<ExportFormat> {
+        Some(match name {
+            "xxd" => ExportFormat::Xxd,
+            "hexdump" | "hexdump-c" => ExportFormat::HexdumpCanonical,
+            "od" => ExportFormat::Od,
+            "hex" => ExportFormat::Hex,
+            "c" => ExportFormat::CArray,
+            "rust" => ExportFormat::RustArray,
+            "python" => ExportFormat::Python,
+            "base64" => ExportFormat::Base64,
+            _ => return None,
+        })
+    }
+
+    /// Formats `bytes`, which start at file offset `offset`. Arrays are
+    /// called `name`.
+    pub fn format(self, bytes: &[u8], offset: usize, name: &str) -> String {
+        match self {
+            ExportFormat::Xxd => xxd(bytes, offset),
+            ExportFormat::HexdumpCanonical => hexdump_canonical(bytes, offset),
+            ExportFormat::Od => od(bytes, offset),
+            ExportFormat::Hex => {
+                let mut text: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                text.push('\n');
+                text
+            }
+            ExportFormat::CArray => format!(
+                "uint8_t {}[{}] = {{\n{}}};\n",
+                name,
+                bytes.len(),
+                array_lines(bytes)
+            ),
+            ExportFormat::RustArray => format!(
+                "const {}: [u8; {}] = [\n{}];\n",
+                name.to_ascii_uppercase(),
+                bytes.len(),
+                array_lines(bytes)
+            ),
+            ExportFormat::Python => python_bytes(bytes),
+            ExportFormat::Base64 => base64(bytes) + "\n",
+        }
+    }
+}
+
+fn printable(byte: u8) -> char {
+    if (0x20..0x7f).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+fn xxd(bytes: &[u8], offset: usize) -> String {
+    let mut text = String::new();
+    for (i, line) in bytes.chunks(BYTES_PER_DUMP_LINE).enumerate() {
+        let groups: Vec<String> = line
+            .chunks(2)
+            .map(|pair| pair.iter().map(|byte| format!("{:02x}", byte)).collect())
+            .collect();
+        let ascii: String = line.iter().copied().map(printable).collect();
+        let _ = writeln!(
+            text,
+            "{:08x}: {:<39}  {}",
+            offset + i * BYTES_PER_DUMP_LINE,
+            groups.join(" "),
+            ascii
+        );
+    }
+    text
+}
+
+/// Lines of a dump where runs of lines with the same bytes as the previous
+/// one are replaced by `*`, as `hexdump` and `od` do.
+fn squeezed_lines(bytes: &[u8], mut line: impl FnMut(usize, &[u8]) -> String) -> String {
+    let mut text = String::new();
+    let mut previous: Option<&[u8]> = None;
+    let mut squeezing = false;
+    for (i, chunk) in bytes.chunks(BYTES_PER_DUMP_LINE).enumerate() {
+        if previous == Some(chunk) && chunk.len() == BYTES_PER_DUMP_LINE {
+            if !squeezing {
+                text += "*\n";
+                squeezing = true;
+            }
+            continue;
+        }
+        squeezing = false;
+        previous = Some(chunk);
+        text += &line(i * BYTES_PER_DUMP_LINE, chunk);
+    }
+    text
+}
+
+fn hexdump_canonical(bytes: &[u8], offset: usize) -> String {
+    let mut text = squeezed_lines(bytes, |start, line| {
+        let mut hex = String::new();
+        for (i, byte) in line.iter().enumerate() {
+            if i == BYTES_PER_DUMP_LINE / 2 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, " {:02x}", byte);
+        }
+        let ascii: String = line.iter().copied().map(printable).collect();
+        format!("{:08x} {:<49}  |{}|\n", offset + start, hex, ascii)
+    });
+    let _ = writeln!(text, "{:08x}", offset + bytes.len());
+    text
+}
+
+fn od(bytes: &[u8], offset: usize) -> String {
+    let mut text = squeezed_lines(bytes, |start, line| {
+        let hex: String = line.iter().map(|byte| format!(" {:02x}", byte)).collect();
+        format!("{:06x}{}\n", offset + start, hex)
+    });
+    let _ = writeln!(text, "{:06x}", offset + bytes.len());
+    text
+}
+
+fn array_lines(bytes: &[u8]) -> String {
+    bytes
+        .chunks(BYTES_PER_ARRAY_LINE)
+        .map(|line| {
+            let items: Vec<String> = line.iter().map(|byte| format!("0x{:02x},", byte)).collect();
+            format!("    {}\n", items.join(" "))
+        })
+        .collect()
+}
+
+fn python_bytes(bytes: &[u8]) -> String {
+    let mut text = String::from("b\"");
+    for &byte in bytes {
+        match byte {
+            b'\\' => text += "\\\\",
+            b'"' => text += "\\\"",
+            b'\n' => text += "\\n",
+            b'\r' => text += "\\r",
+            b'\t' => text += "\\t",
+            0x20..=0x7e => text.push(byte as char),
+            _ => {
+                let _ = write!(text, "\\x{:02x}", byte);
+            }
+        }
+    }
+    text += "\"\n";
+    text
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let bytes: Vec<u8> = (0x41..0x53).collect();
+        assert_eq!(
+            ExportFormat::Xxd.format(&bytes, 0x10, "data"),
+            "00000010: 4142 4344 4546 4748 494a 4b4c 4d4e 4f50  ABCDEFGHIJKLMNOP\n\
+             00000020: 5152                                     QR\n"
+        );
+        assert_eq!(
+            ExportFormat::HexdumpCanonical.format(&bytes, 0, "data"),
+            "00000000  41 42 43 44 45 46 47 48  49 4a 4b 4c 4d 4e 4f 50  |ABCDEFGHIJKLMNOP|\n\
+             00000010  51 52                                             |QR|\n\
+             00000012\n"
+        );
+        assert_eq!(
+            ExportFormat::Od.format(&[0; 40], 0, "data"),
+            "000000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n*\n\
+             000020 00 00 00 00 00 00 00 00\n000028\n"
+        );
+        assert_eq!(
+            ExportFormat::RustArray.format(&[1, 0xff], 0, "data"),
+            "const DATA: [u8; 2] = [\n    0x01, 0xff,\n];\n"
+        );
+        assert_eq!(ExportFormat::Python.format(b"a\"\x00", 0, "data"), "b\"a\\\"\\x00\"\n");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
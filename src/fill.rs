@@ -0,0 +1,129 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::encoding::parse_hex;
+use crate::outline::{parse_int, IntFormat};
+
+pub const FILL_USAGE: &str =
+    "usage: :fill zero|ff|hex \"..\"|seq <int type> <start> [step]|random [seed]";
+
+/// What `:fill` writes over each selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fill {
+    /// Repeated from the start of every selection
+    Pattern(Vec<u8>),
+    /// Counters wrapping around at the size of the type, continuing from
+    /// one selection to the next
+    Sequence {
+        format: IntFormat,
+        next: i128,
+        step: i128,
+    },
+    /// Pseudo-random bytes, continuing from one selection to the next
+    Random { state: u64 },
+}
+
+impl Fill {
+    pub fn parse(args: &str) -> Result<Fill, String> {
+        let args = args.trim();
+        let (kind, rest) = args.split_once(' ').unwrap_or((args, ""));
+        let rest = rest.trim();
+        let fill = match kind {
+            "zero" if rest.is_empty() => Fill::Pattern(vec![0]),
+            "ff" if rest.is_empty() => Fill::Pattern(vec![0xff]),
+            "hex" => match parse_hex(rest)? {
+                pattern if pattern.is_empty() => return Err("empty pattern".into()),
+                pattern => Fill::Pattern(pattern),
+            },
+            "seq" => {
+                let parts: Vec<&str> = rest.split_whitespace().collect();
+                let (ty, start, step) = match parts[..] {
+                    [ty, start] => (ty, start, "1"),
+                    [ty, start, step] => (ty, start, step),
+                    _ => return Err(FILL_USAGE.into()),
+                };
+                let format = IntFormat::parse(ty).ok_or_else(|| format!("not an int type: {}", ty))?;
+                let start = parse_int(start)?;
+                format.encode(start)?;
+                Fill::Sequence {
+                    format,
+                    next: start,
+                    step: parse_int(step)?,
+                }
+            }
+            "random" => {
+                let seed = match rest {
+                    "" => SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |time| time.as_nanos() as u64),
+                    seed => parse_int(seed)? as u64,
+                };
+                Fill::Random { state: seed }
+            }
+            _ => return Err(FILL_USAGE.into()),
+        };
+        Ok(fill)
+    }
+
+    /// The `len` bytes to write over the next selection.
+    pub fn next_bytes(&mut self, len: usize) -> Vec<u8> {
+        match self {
+            Fill::Pattern(pattern) => pattern.iter().copied().cycle().take(len).collect(),
+            Fill::Sequence { format, next, step } => {
+                let mut bytes = Vec::with_capacity(len + format.size);
+                while bytes.len() < len {
+                    let le_bytes = (*next as u128).to_le_bytes();
+                    let mut item = le_bytes[..format.size].to_vec();
+                    if format.big_endian {
+                        item.reverse();
+                    }
+                    bytes.extend(item);
+                    *next = next.wrapping_add(*step);
+                }
+                bytes.truncate(len);
+                bytes
+            }
+            Fill::Random { state } => {
+                let mut bytes = Vec::with_capacity(len + 8);
+                while bytes.len() < len {
+                    bytes.extend(splitmix64(state).to_le_bytes());
+                }
+                bytes.truncate(len);
+                bytes
+            }
+        }
+    }
+}
+
+/// SplitMix64: small and good enough for test data, not for secrets.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        let mut fill = Fill::parse("hex \"de ad be\"").unwrap();
+        assert_eq!(fill.next_bytes(5), [0xde, 0xad, 0xbe, 0xde, 0xad]);
+        assert_eq!(fill.next_bytes(2), [0xde, 0xad]);
+
+        let mut fill = Fill::parse("seq u16be 0xfffe 1").unwrap();
+        assert_eq!(fill.next_bytes(5), [0xff, 0xfe, 0xff, 0xff, 0]);
+        assert_eq!(fill.next_bytes(2), [0, 1]);
+        let mut fill = Fill::parse("seq i8 1 -1").unwrap();
+        assert_eq!(fill.next_bytes(3), [1, 0, 0xff]);
+        assert!(Fill::parse("seq u8 256").is_err());
+
+        let random = |seed| Fill::parse(&format!("random {}", seed)).unwrap().next_bytes(16);
+        assert_eq!(random(7), random(7));
+        assert_ne!(random(7), random(8));
+        assert_eq!(Fill::parse("zero").unwrap().next_bytes(2), [0, 0]);
+        assert!(Fill::parse("ones").is_err());
+    }
+}
@@ -1,58 +1,15 @@
 use crate::hex_view::{
     colorize_byte, make_padding, OutputColorizer, PrioritizedStyle, Priority, StylingCommand,
 };
-use crossterm::style::{Attributes, Color};
 use crossterm::{style, ErrorKind};
-use lazy_static::lazy_static;
 use std::convert::TryInto;
 use std::io::Write;
 
-lazy_static! {
-    static ref BIN_ZERO_STYLE: StylingCommand =
-        StylingCommand::default().with_start_style(PrioritizedStyle {
-            style: style::ContentStyle {
-                foreground_color: Some(Color::AnsiValue(150)),
-                background_color: Some(Color::Reset),
-                attributes: Attributes::default(),
-            },
-            priority: Priority::Basic,
-        });
-    static ref BIN_ONE_STYLE: StylingCommand =
-        StylingCommand::default().with_start_style(PrioritizedStyle {
-            style: style::ContentStyle {
-                foreground_color: Some(Color::Blue),
-                background_color: Some(Color::Reset),
-                attributes: Attributes::default(),
-            },
-            priority: Priority::Basic,
-        });
-    static ref INVALID_DATA_STYLE: StylingCommand =
-        StylingCommand::default().with_start_style(PrioritizedStyle {
-            style: style::ContentStyle {
-                foreground_color: Some(Color::Red),
-                background_color: Some(Color::Reset),
-                attributes: Attributes::default(),
-            },
-            priority: Priority::Basic,
-        });
-    static ref DEFAULT_STYLE: StylingCommand =
-        StylingCommand::default().with_start_style(PrioritizedStyle {
-            style: style::ContentStyle {
-                foreground_color: Some(Color::DarkMagenta),
-                background_color: Some(Color::Reset),
-                attributes: Attributes::default(),
-            },
-            priority: Priority::Basic,
-        });
-    static ref DEFAULT_VALUE_STYLE: StylingCommand =
-        StylingCommand::default().with_start_style(PrioritizedStyle {
-            style: style::ContentStyle {
-                foreground_color: Some(Color::AnsiValue(150)),
-                background_color: Some(Color::Reset),
-                attributes: Attributes::default(),
-            },
-            priority: Priority::Basic,
-        });
+fn basic_style(style: style::ContentStyle) -> StylingCommand {
+    StylingCommand::default().with_start_style(PrioritizedStyle {
+        style,
+        priority: Priority::Basic,
+    })
 }
 
 fn format_binary_byte(
@@ -60,10 +17,12 @@ fn format_binary_byte(
     colorizer: &OutputColorizer,
     byte: u8,
 ) -> Result<(), ErrorKind> {
+    let zero_style = basic_style(colorizer.theme().properties_bit_zero);
+    let one_style = basic_style(colorizer.theme().properties_bit_one);
     for c in format!("{:08b}", byte).chars() {
         match c {
-            '0' => colorizer.draw(stdout, '0', &BIN_ZERO_STYLE)?,
-            '1' => colorizer.draw(stdout, '1', &BIN_ONE_STYLE)?,
+            '0' => colorizer.draw(stdout, '0', &zero_style)?,
+            '1' => colorizer.draw(stdout, '1', &one_style)?,
             _ => {}
         }
     }
@@ -146,85 +105,89 @@ impl<'a> BytePropertiesFormatter<'a> {
         } else {
             0
         };
+        let theme = colorizer.theme();
+        let label_style = basic_style(theme.properties_label);
+        let value_style = basic_style(theme.properties_value);
+        let invalid_style = basic_style(theme.properties_invalid);
 
         match self.line {
             0 => {
-                colorizer.draw(stdout, "hex u8: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, "hex u8: ", &label_style)?;
                 colorizer.draw_hex_byte(
                     stdout,
                     first_byte,
-                    &colorize_byte(first_byte, &DEFAULT_VALUE_STYLE),
+                    &colorize_byte(first_byte, &value_style, theme),
                 )?;
 
-                colorizer.draw(stdout, "          hex u32: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, "          hex u32: ", &label_style)?;
                 for byte in self.data.iter() {
                     colorizer.draw_hex_byte(
                         stdout,
                         *byte,
-                        &colorize_byte(*byte, &DEFAULT_VALUE_STYLE),
+                        &colorize_byte(*byte, &value_style, theme),
                     )?;
                 }
             }
             1 => {
-                colorizer.draw(stdout, "bin u8: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, "bin u8: ", &label_style)?;
                 format_binary_byte(stdout, colorizer, first_byte)?;
 
-                colorizer.draw(stdout, "     bin u32: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, "     bin u32: ", &label_style)?;
                 for byte in self.data.iter() {
                     format_binary_byte(stdout, colorizer, *byte)?;
-                    colorizer.draw(stdout, ' ', &DEFAULT_STYLE)?;
+                    colorizer.draw(stdout, ' ', &label_style)?;
                 }
             }
             2 => {
                 let byte_literal = format!("{}", first_byte);
                 let len = byte_literal.len();
 
-                colorizer.draw(stdout, "dec u8: ", &DEFAULT_STYLE)?;
-                colorizer.draw(stdout, byte_literal, &DEFAULT_VALUE_STYLE)?;
+                colorizer.draw(stdout, "dec u8: ", &label_style)?;
+                colorizer.draw(stdout, byte_literal, &value_style)?;
 
-                colorizer.draw(stdout, make_padding(12 - len), &DEFAULT_STYLE)?;
-                colorizer.draw(stdout, " dec u32: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, make_padding(12 - len), &label_style)?;
+                colorizer.draw(stdout, " dec u32: ", &label_style)?;
                 colorizer.draw(
                     stdout,
                     u32::from_be_bytes(bytes_to_4_byte_vec(self.data).try_into().unwrap()),
-                    &DEFAULT_VALUE_STYLE,
+                    &value_style,
                 )?;
             }
             3 => {
                 let byte_literal = format!("{}", first_byte as i8);
                 let len = byte_literal.len();
 
-                colorizer.draw(stdout, "dec i8: ", &DEFAULT_STYLE)?;
-                colorizer.draw(stdout, byte_literal, &DEFAULT_VALUE_STYLE)?;
+                colorizer.draw(stdout, "dec i8: ", &label_style)?;
+                colorizer.draw(stdout, byte_literal, &value_style)?;
 
-                colorizer.draw(stdout, make_padding(12 - len), &DEFAULT_STYLE)?;
-                colorizer.draw(stdout, " dec i32: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, make_padding(12 - len), &label_style)?;
+                colorizer.draw(stdout, " dec i32: ", &label_style)?;
                 colorizer.draw(
                     stdout,
                     i32::from_be_bytes(bytes_to_4_byte_vec(self.data).try_into().unwrap()),
-                    &DEFAULT_VALUE_STYLE,
+                    &value_style,
                 )?;
             }
             4 => {
-                colorizer.draw(stdout, " utf-8: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, " utf-8: ", &label_style)?;
                 let len = match utf8_into_char(self.data) {
                     Ok(c) => {
                         let c = format_char(c);
                         let len = c.len();
-                        colorizer.draw(stdout, c, &DEFAULT_VALUE_STYLE)?;
+                        colorizer.draw(stdout, c, &value_style)?;
                         len
                     }
                     Err(c) => {
-                        colorizer.draw(stdout, c, &INVALID_DATA_STYLE)?;
+                        colorizer.draw(stdout, c, &invalid_style)?;
                         1
                     }
                 };
 
-                colorizer.draw(stdout, make_padding(12 - len), &DEFAULT_STYLE)?;
-                colorizer.draw(stdout, "  utf-16: ", &DEFAULT_STYLE)?;
+                colorizer.draw(stdout, make_padding(12 - len), &label_style)?;
+                colorizer.draw(stdout, "  utf-16: ", &label_style)?;
                 match utf16_into_char(self.data) {
-                    Ok(c) => colorizer.draw(stdout, format_char(c), &DEFAULT_VALUE_STYLE),
-                    Err(c) => colorizer.draw(stdout, c, &INVALID_DATA_STYLE),
+                    Ok(c) => colorizer.draw(stdout, format_char(c), &value_style),
+                    Err(c) => colorizer.draw(stdout, c, &invalid_style),
                 }?;
             }
             _ => (),
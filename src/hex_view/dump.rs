@@ -0,0 +1,96 @@
+use std::cmp;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use crossterm::{queue, style, Result};
+
+use super::{OutputColorizer, StylingCommand};
+use crate::byte_source::{ByteSource, FileSource};
+use crate::outline::parse_int;
+
+const DUMP_BYTES_PER_LINE: usize = 0x10;
+
+/// Rows read from the file at a time, so any range is dumped in little memory.
+const DUMP_CHUNK_ROWS: usize = 4096;
+
+/// `--offset`/`--length` values: decimal, or hex with `0x`.
+pub fn parse_offset(text: &str) -> std::result::Result<u64, String> {
+    u64::try_from(parse_int(text)?).map_err(|_| format!("not an offset: {}", text))
+}
+
+/// Dumps `length` bytes (or everything) of the file at `path` from `offset`.
+pub fn dump_file(
+    stdout: &mut impl Write,
+    path: &Path,
+    offset: u64,
+    length: Option<u64>,
+    colorizer: &OutputColorizer,
+) -> Result<()> {
+    let source = FileSource::open(path)?;
+    let end = match length {
+        Some(length) => cmp::min(offset.saturating_add(length), source.len()),
+        None => source.len(),
+    };
+    dump(stdout, &source, offset..end, DUMP_BYTES_PER_LINE, colorizer)
+}
+
+/// Writes `range` of `source` as rows of the hex view, each after its
+/// offset.
+fn dump(
+    stdout: &mut impl Write,
+    source: &dyn ByteSource,
+    range: Range<u64>,
+    bytes_per_line: usize,
+    colorizer: &OutputColorizer,
+) -> Result<()> {
+    let chunk_len = (DUMP_CHUNK_ROWS * bytes_per_line) as u64;
+    let mut offset = range.start;
+    while offset < range.end {
+        let bytes = source.read_at(offset, cmp::min(chunk_len, range.end - offset) as usize)?;
+        if bytes.is_empty() {
+            break;
+        }
+        for (i, row) in bytes.chunks(bytes_per_line).enumerate() {
+            let row_offset = offset + (i * bytes_per_line) as u64;
+            dump_row(stdout, row, row_offset, bytes_per_line, colorizer)?;
+        }
+        offset += bytes.len() as u64;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn dump_row(
+    stdout: &mut impl Write,
+    bytes: &[u8],
+    offset: u64,
+    bytes_per_line: usize,
+    colorizer: &OutputColorizer,
+) -> Result<()> {
+    let styled = || bytes.iter().map(|&byte| (byte, StylingCommand::default()));
+    queue!(stdout, style::Print(format!("{:08x}  ", offset)))?;
+    colorizer.draw_hex_row(stdout, styled())?;
+    queue!(stdout, style::Print(" ".repeat(3 * (bytes_per_line - bytes.len()))))?;
+    colorizer.draw_separator(stdout)?;
+    colorizer.draw_ascii_row(stdout, styled())?;
+    colorizer.reset(stdout)?;
+    queue!(stdout, style::Print("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let source: Vec<u8> = (0x40..0x60).collect();
+        let mut out = vec![];
+        dump(&mut out, &source, 0x0e..0x40, 0x10, &OutputColorizer::plain()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0000000e  4e 4f 50 51 52 53 54 55 56 57 58 59 5a 5b 5c 5d │ NOPQRSTUVWXYZ[\\]\n\
+             0000001e  5e 5f                                           │ ^_\n"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::cmp;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crossterm::style::ContentStyle;
+
+use super::theme::Theme;
+
+const STREAM_CHUNK: usize = 64 * 1024;
+
+/// Width of the minimap sidebar in columns: a window marker and the cell itself.
+pub const MINIMAP_WIDTH: u16 = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MinimapKind {
+    Entropy,
+    ByteClass,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ByteClass {
+    Zeros,
+    Ascii,
+    Control,
+    High,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CellStats {
+    /// Shannon entropy in bits per byte, 0.0..=8.0
+    pub entropy: f32,
+    pub dominant_class: ByteClass,
+}
+
+impl CellStats {
+    pub fn from_histogram(histogram: &[u64; 256]) -> CellStats {
+        let total: u64 = histogram.iter().sum();
+        let entropy = if total == 0 {
+            0.0
+        } else {
+            histogram
+                .iter()
+                .filter(|&&count| count > 0)
+                .map(|&count| {
+                    let p = count as f64 / total as f64;
+                    -p * p.log2()
+                })
+                .sum::<f64>() as f32
+        };
+
+        let count_where = |f: fn(u8) -> bool| -> u64 {
+            (0..=255u8)
+                .filter(|&b| f(b))
+                .map(|b| histogram[b as usize])
+                .sum()
+        };
+        let classes = [
+            (ByteClass::Zeros, histogram[0]),
+            (
+                ByteClass::Ascii,
+                count_where(|b| b.is_ascii_graphic() || b.is_ascii_whitespace()),
+            ),
+            (
+                ByteClass::Control,
+                count_where(|b| b != 0 && b.is_ascii() && !b.is_ascii_graphic() && !b.is_ascii_whitespace()),
+            ),
+            (ByteClass::High, count_where(|b| !b.is_ascii())),
+        ];
+        let dominant_class = classes
+            .iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(class, _)| *class)
+            .unwrap();
+
+        CellStats {
+            entropy,
+            dominant_class,
+        }
+    }
+
+    pub fn glyph(&self, kind: MinimapKind) -> char {
+        match kind {
+            MinimapKind::Entropy => match self.entropy {
+                e if e < 2.0 => '░',
+                e if e < 5.0 => '▒',
+                e if e < 7.2 => '▓',
+                _ => '█',
+            },
+            MinimapKind::ByteClass => match self.dominant_class {
+                ByteClass::Zeros => '0',
+                ByteClass::Ascii => 'a',
+                ByteClass::Control => 'c',
+                ByteClass::High => 'h',
+            },
+        }
+    }
+
+    pub fn style(&self, kind: MinimapKind, theme: &Theme) -> ContentStyle {
+        let fg = match kind {
+            MinimapKind::Entropy => {
+                let index = match self.entropy {
+                    e if e < 2.0 => 0,
+                    e if e < 5.0 => 1,
+                    e if e < 7.2 => 2,
+                    _ => 3,
+                };
+                theme.minimap_entropy[index]
+            }
+            MinimapKind::ByteClass => match self.dominant_class {
+                ByteClass::Zeros => theme.byte_null,
+                ByteClass::Ascii => theme.byte_ascii_printable,
+                ByteClass::Control => theme.byte_ascii_other,
+                ByteClass::High => theme.byte_nonascii,
+            },
+        };
+        ContentStyle {
+            foreground_color: Some(fg),
+            ..theme.default
+        }
+    }
+}
+
+/// Number of file bytes summarized by each of `cells` cells.
+pub fn cell_len(file_len: u64, cells: usize) -> u64 {
+    cmp::max(1, file_len.div_ceil(cmp::max(1, cells) as u64))
+}
+
+/// A whole-file overview, computed on a background thread by streaming the
+/// file from disk, so it doesn't depend on how much of it is loaded.
+pub struct Minimap {
+    pub kind: MinimapKind,
+    path: PathBuf,
+    file_len: u64,
+    cells: Arc<Mutex<Vec<Option<CellStats>>>>,
+    updated: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Minimap {
+    pub fn compute(path: &Path, kind: MinimapKind, cell_count: usize) -> std::io::Result<Minimap> {
+        let file_len = std::fs::metadata(path)?.len();
+        let minimap = Minimap {
+            kind,
+            path: path.to_owned(),
+            file_len,
+            cells: Arc::new(Mutex::new(vec![None; cell_count])),
+            updated: Arc::new(AtomicBool::new(true)),
+            finished: Arc::new(AtomicBool::new(false)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+
+        let path = minimap.path.clone();
+        let cells = Arc::clone(&minimap.cells);
+        let updated = Arc::clone(&minimap.updated);
+        let finished = Arc::clone(&minimap.finished);
+        let cancelled = Arc::clone(&minimap.cancelled);
+        thread::spawn(move || {
+            let _ = stream_cells(&path, file_len, &cells, &updated, &cancelled);
+            finished.store(true, Ordering::Relaxed);
+            updated.store(true, Ordering::Relaxed);
+        });
+
+        Ok(minimap)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells.lock().unwrap().len()
+    }
+
+    pub fn cell_len(&self) -> u64 {
+        cell_len(self.file_len, self.cell_count())
+    }
+
+    pub fn cell_of_offset(&self, offset: u64) -> usize {
+        cmp::min(
+            (offset / self.cell_len()) as usize,
+            self.cell_count().saturating_sub(1),
+        )
+    }
+
+    pub fn cells(&self) -> Vec<Option<CellStats>> {
+        self.cells.lock().unwrap().clone()
+    }
+
+    pub fn is_computing(&self) -> bool {
+        !self.finished.load(Ordering::Relaxed)
+    }
+
+    /// Whether new cells arrived since the last call.
+    pub fn take_updated(&self) -> bool {
+        self.updated.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for Minimap {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+fn stream_cells(
+    path: &Path,
+    file_len: u64,
+    cells: &Mutex<Vec<Option<CellStats>>>,
+    updated: &AtomicBool,
+    cancelled: &AtomicBool,
+) -> std::io::Result<()> {
+    let mut file = File::open(path)?;
+    let cell_count = cells.lock().unwrap().len();
+    let cell_len = cell_len(file_len, cell_count);
+    let mut chunk = vec![0u8; STREAM_CHUNK];
+
+    for i in 0..cell_count {
+        let start = i as u64 * cell_len;
+        if start >= file_len {
+            break;
+        }
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut histogram = [0u64; 256];
+        let mut remaining = cmp::min(cell_len, file_len - start);
+        while remaining > 0 {
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let wanted = cmp::min(remaining, chunk.len() as u64) as usize;
+            let read = file.read(&mut chunk[..wanted])?;
+            if read == 0 {
+                break;
+            }
+            for byte in &chunk[..read] {
+                histogram[*byte as usize] += 1;
+            }
+            remaining -= read as u64;
+        }
+
+        cells.lock().unwrap()[i] = Some(CellStats::from_histogram(&histogram));
+        updated.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_stats() {
+        let mut histogram = [0u64; 256];
+        histogram[0] = 100;
+        let zeros = CellStats::from_histogram(&histogram);
+        assert_eq!(zeros.entropy, 0.0);
+        assert_eq!(zeros.dominant_class, ByteClass::Zeros);
+
+        let uniform = CellStats::from_histogram(&[4; 256]);
+        assert!((uniform.entropy - 8.0).abs() < 1e-6);
+        assert_eq!(uniform.dominant_class, ByteClass::High);
+    }
+
+    #[test]
+    fn test_cell_len() {
+        assert_eq!(cell_len(100, 10), 10);
+        assert_eq!(cell_len(101, 10), 11);
+        assert_eq!(cell_len(0, 10), 1);
+    }
+}
@@ -1,6 +1,6 @@
 use crossterm::{
     queue,
-    style::{self, Color},
+    style,
     ErrorKind,
 };
 use std::fmt;
@@ -8,22 +8,16 @@ use std::fmt::Display;
 use std::io::Write;
 
 mod byte_properties;
+pub mod dump;
+pub mod minimap;
+pub mod theme;
 pub mod view;
 
-const COLOR_NULL: Color = Color::AnsiValue(150);
-const COLOR_ASCII_PRINTABLE: Color = Color::Cyan;
-const COLOR_ASCII_WHITESPACE: Color = Color::Green;
-const COLOR_ASCII_OTHER: Color = Color::Rgb {
-    r: 232,
-    g: 52,
-    b: 210,
-};
-const COLOR_NONASCII: Color = Color::Yellow;
+use theme::Theme;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Priority {
     Basic,
-    #[allow(dead_code)]
     Mark,
     Selection,
     Cursor,
@@ -117,21 +111,7 @@ fn queue_style(stdout: &mut impl Write, style: &style::ContentStyle) -> Result<(
     Ok(())
 }
 
-fn get_byte_color(byte: u8) -> Color {
-    if byte == 0x00 {
-        COLOR_NULL
-    } else if byte.is_ascii_graphic() {
-        COLOR_ASCII_PRINTABLE
-    } else if byte.is_ascii_whitespace() {
-        COLOR_ASCII_WHITESPACE
-    } else if byte.is_ascii() {
-        COLOR_ASCII_OTHER
-    } else {
-        COLOR_NONASCII
-    }
-}
-
-fn colorize_byte(byte: u8, style_cmd: &StylingCommand) -> StylingCommand {
+fn colorize_byte(byte: u8, style_cmd: &StylingCommand, theme: &Theme) -> StylingCommand {
     let default_content_style = style::ContentStyle {
         foreground_color: None,
         background_color: None,
@@ -142,7 +122,7 @@ fn colorize_byte(byte: u8, style_cmd: &StylingCommand) -> StylingCommand {
 
     style_cmd.clone().with_start_style(PrioritizedStyle {
         style: style::ContentStyle {
-            foreground_color: Some(get_byte_color(byte)),
+            foreground_color: Some(theme.byte_color(byte)),
             background_color: start_style.background_color,
             attributes: start_style.attributes,
         },
@@ -150,6 +130,10 @@ fn colorize_byte(byte: u8, style_cmd: &StylingCommand) -> StylingCommand {
     })
 }
 
+pub const VERTICAL: &str = "│";
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
 pub fn make_padding(len: usize) -> &'static str {
     debug_assert!(len < 0x40, "can't make padding of len {}", len);
     &"                                                                "[..len]
@@ -167,11 +151,81 @@ impl fmt::Display for ByteAsciiRepr {
     }
 }
 
-pub struct OutputColorizer;
+pub struct OutputColorizer {
+    theme: Theme,
+    /// Whether styles are written at all; off for plain text output
+    colored: bool,
+}
 
 impl OutputColorizer {
     pub fn new() -> Self {
-        Self {}
+        OutputColorizer::with_theme(Theme::default())
+    }
+
+    pub fn with_theme(theme: Theme) -> Self {
+        Self { theme, colored: true }
+    }
+
+    /// Writes text only, without escape sequences.
+    pub fn plain() -> Self {
+        Self {
+            theme: Theme::monochrome(),
+            colored: false,
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    fn queue_style(&self, stdout: &mut impl Write, style: &style::ContentStyle) -> Result<(), ErrorKind> {
+        if self.colored {
+            queue_style(stdout, style)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn draw_hex_row(
+        &self,
+        stdout: &mut impl Write,
+        styled_bytes: impl IntoIterator<Item = (u8, StylingCommand)>,
+    ) -> Result<(), ErrorKind> {
+        for (byte, style_cmd) in styled_bytes.into_iter() {
+            self.draw_hex_byte(stdout, byte, &style_cmd)?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_ascii_row(
+        &self,
+        stdout: &mut impl Write,
+        styled_bytes: impl IntoIterator<Item = (u8, StylingCommand)>,
+    ) -> Result<(), ErrorKind> {
+        for (byte, style_cmd) in styled_bytes.into_iter() {
+            self.draw_ascii_byte(stdout, byte, &style_cmd)?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_separator(&self, stdout: &mut impl Write) -> Result<(), ErrorKind> {
+        if self.colored {
+            queue!(
+                stdout,
+                style::PrintStyledContent(style::StyledContent::new(self.theme.separator, VERTICAL))
+            )?;
+        } else {
+            queue!(stdout, style::Print(VERTICAL))?;
+        }
+        queue!(stdout, style::Print(" "))
+    }
+
+    /// Back to the terminal's own colors, e.g. at the end of a line of output.
+    pub fn reset(&self, stdout: &mut impl Write) -> Result<(), ErrorKind> {
+        if self.colored {
+            queue!(stdout, style::ResetColor, style::SetAttribute(style::Attribute::Reset))?;
+        }
+        Ok(())
     }
 
     pub fn draw_hex_byte(
@@ -180,25 +234,25 @@ impl OutputColorizer {
         byte: u8,
         style: &StylingCommand,
     ) -> Result<(), ErrorKind> {
-        let style_cmd = colorize_byte(byte, style);
+        let style_cmd = colorize_byte(byte, style, &self.theme);
 
         if let Some(start_cmd) = style_cmd.start_style() {
-            queue_style(stdout, start_cmd)?;
+            self.queue_style(stdout, start_cmd)?;
         }
 
-        queue!(stdout, style::Print(format!("{:x}", byte >> 4)))?;
+        queue!(stdout, style::Print(HEX_DIGITS[(byte >> 4) as usize] as char))?;
 
         if let Some(mid_cmd) = style_cmd.mid_style() {
-            queue_style(stdout, mid_cmd)?;
+            self.queue_style(stdout, mid_cmd)?;
         }
 
-        queue!(stdout, style::Print(format!("{:x}", byte & 0xf)))?;
+        queue!(stdout, style::Print(HEX_DIGITS[(byte & 0xf) as usize] as char))?;
 
         if let Some(end_cmd) = style_cmd.end_style() {
-            queue_style(stdout, end_cmd)?;
+            self.queue_style(stdout, end_cmd)?;
         }
 
-        queue!(stdout, style::Print(" ".to_string()))
+        queue!(stdout, style::Print(' '))
     }
 
     pub fn draw_ascii_byte(
@@ -207,16 +261,16 @@ impl OutputColorizer {
         byte: u8,
         style: &StylingCommand,
     ) -> Result<(), ErrorKind> {
-        let style_cmd = colorize_byte(byte, style);
+        let style_cmd = colorize_byte(byte, style, &self.theme);
 
         if let Some(start_cmd) = style_cmd.start_style() {
-            queue_style(stdout, start_cmd)?;
+            self.queue_style(stdout, start_cmd)?;
         }
 
-        queue!(stdout, style::Print(format!("{}", ByteAsciiRepr(byte))))?;
+        queue!(stdout, style::Print(ByteAsciiRepr(byte)))?;
 
         if let Some(end_cmd) = style_cmd.end_style() {
-            queue_style(stdout, end_cmd)?;
+            self.queue_style(stdout, end_cmd)?;
         }
 
         Ok(())
@@ -229,13 +283,13 @@ impl OutputColorizer {
         style: &StylingCommand,
     ) -> Result<(), ErrorKind> {
         if let Some(start_cmd) = style.start_style() {
-            queue_style(stdout, start_cmd)?;
+            self.queue_style(stdout, start_cmd)?;
         }
 
         queue!(stdout, style::Print(c))?;
 
         if let Some(end_cmd) = style.end_style() {
-            queue_style(stdout, end_cmd)?;
+            self.queue_style(stdout, end_cmd)?;
         }
 
         Ok(())
@@ -0,0 +1,642 @@
+use crossterm::style::{Attribute, Attributes, Color, ContentStyle};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// How many colors the terminal can render.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Guesses the color depth from `COLORTERM` and `TERM`.
+    pub fn detect() -> ColorDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        if env::var("TERM").unwrap_or_default().contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// Every color and style the hex view draws with.
+///
+/// Byte classes only set a foreground color; everything else is a full
+/// `ContentStyle` so that themes without colors can fall back to attributes.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub byte_null: Color,
+    pub byte_ascii_printable: Color,
+    pub byte_ascii_whitespace: Color,
+    pub byte_ascii_other: Color,
+    pub byte_nonascii: Color,
+
+    pub default: ContentStyle,
+    pub active_selection: ContentStyle,
+    pub inactive_selection: ContentStyle,
+    pub active_caret: ContentStyle,
+    pub inactive_caret: ContentStyle,
+    pub empty_caret: ContentStyle,
+    pub mark: ContentStyle,
+    pub separator: ContentStyle,
+
+    pub powerline_name: ContentStyle,
+    pub powerline_mode: ContentStyle,
+    pub powerline_selections: ContentStyle,
+    pub powerline_offset: ContentStyle,
+    pub info: ContentStyle,
+    pub prompt: ContentStyle,
+    pub prompt_cursor: ContentStyle,
+    pub prompt_special: ContentStyle,
+    /// Bytes shown as `<xx>` in text prompts
+    pub prompt_escaped: ContentStyle,
+
+    pub properties_label: ContentStyle,
+    pub properties_value: ContentStyle,
+    pub properties_bit_zero: ContentStyle,
+    pub properties_bit_one: ContentStyle,
+    pub properties_invalid: ContentStyle,
+
+    /// Colors for low, medium, high and maximal entropy cells
+    pub minimap_entropy: [Color; 4],
+    pub minimap_window: ContentStyle,
+
+    pub diff: ContentStyle,
+    /// Annotated ranges without a color of their own
+    pub annotation: ContentStyle,
+}
+
+const BUILTIN_THEMES: &[&str] = &["dark", "light", "monochrome"];
+
+fn styled(fg: Option<Color>, bg: Option<Color>) -> ContentStyle {
+    ContentStyle {
+        foreground_color: fg,
+        background_color: bg,
+        attributes: Attributes::default(),
+    }
+}
+
+fn with_attrs(attributes: &[Attribute]) -> ContentStyle {
+    ContentStyle {
+        foreground_color: None,
+        background_color: None,
+        attributes: Attributes::from(attributes),
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            byte_null: Color::AnsiValue(150),
+            byte_ascii_printable: Color::Cyan,
+            byte_ascii_whitespace: Color::Green,
+            byte_ascii_other: Color::Rgb {
+                r: 232,
+                g: 52,
+                b: 210,
+            },
+            byte_nonascii: Color::Yellow,
+
+            default: styled(Some(Color::White), Some(Color::Reset)),
+            active_selection: styled(
+                Some(Color::Black),
+                Some(Color::Rgb {
+                    r: 110,
+                    g: 97,
+                    b: 16,
+                }),
+            ),
+            inactive_selection: styled(Some(Color::Black), Some(Color::DarkGrey)),
+            active_caret: styled(
+                Some(Color::AnsiValue(16)),
+                Some(Color::Rgb {
+                    r: 107,
+                    g: 108,
+                    b: 128,
+                }),
+            ),
+            inactive_caret: styled(Some(Color::Black), Some(Color::DarkGrey)),
+            empty_caret: styled(None, Some(Color::Green)),
+            mark: styled(
+                Some(Color::Black),
+                Some(Color::Rgb {
+                    r: 64,
+                    g: 110,
+                    b: 160,
+                }),
+            ),
+            separator: styled(Some(Color::White), None),
+
+            powerline_name: styled(Some(Color::White), Some(Color::Red)),
+            powerline_mode: styled(Some(Color::AnsiValue(16)), Some(Color::DarkYellow)),
+            powerline_selections: styled(Some(Color::AnsiValue(16)), Some(Color::White)),
+            powerline_offset: styled(Some(Color::White), Some(Color::Blue)),
+            info: styled(Some(Color::White), Some(Color::Blue)),
+            prompt: styled(Some(Color::White), Some(Color::Blue)),
+            prompt_cursor: styled(Some(Color::Black), Some(Color::White)),
+            prompt_special: styled(Some(Color::DarkRed), None),
+            prompt_escaped: styled(Some(Color::Black), Some(Color::DarkGrey)),
+
+            properties_label: styled(Some(Color::DarkMagenta), Some(Color::Reset)),
+            properties_value: styled(Some(Color::AnsiValue(150)), Some(Color::Reset)),
+            properties_bit_zero: styled(Some(Color::AnsiValue(150)), Some(Color::Reset)),
+            properties_bit_one: styled(Some(Color::Blue), Some(Color::Reset)),
+            properties_invalid: styled(Some(Color::Red), Some(Color::Reset)),
+
+            minimap_entropy: [
+                Color::DarkBlue,
+                Color::DarkCyan,
+                Color::DarkYellow,
+                Color::Red,
+            ],
+            minimap_window: styled(Some(Color::White), None),
+
+            diff: styled(None, Some(Color::Rgb { r: 110, g: 30, b: 30 })),
+            annotation: styled(None, Some(Color::Rgb { r: 30, g: 70, b: 40 })),
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            byte_null: Color::Grey,
+            byte_ascii_printable: Color::DarkBlue,
+            byte_ascii_whitespace: Color::DarkGreen,
+            byte_ascii_other: Color::DarkMagenta,
+            byte_nonascii: Color::DarkYellow,
+
+            default: styled(Some(Color::Black), Some(Color::Reset)),
+            active_selection: styled(
+                Some(Color::Black),
+                Some(Color::Rgb {
+                    r: 250,
+                    g: 220,
+                    b: 120,
+                }),
+            ),
+            inactive_selection: styled(Some(Color::Black), Some(Color::Grey)),
+            active_caret: styled(
+                Some(Color::White),
+                Some(Color::Rgb {
+                    r: 70,
+                    g: 90,
+                    b: 160,
+                }),
+            ),
+            inactive_caret: styled(Some(Color::White), Some(Color::DarkGrey)),
+            empty_caret: styled(None, Some(Color::DarkGreen)),
+            mark: styled(
+                Some(Color::Black),
+                Some(Color::Rgb {
+                    r: 150,
+                    g: 200,
+                    b: 240,
+                }),
+            ),
+            separator: styled(Some(Color::DarkGrey), None),
+
+            powerline_name: styled(Some(Color::White), Some(Color::DarkRed)),
+            powerline_mode: styled(Some(Color::Black), Some(Color::Yellow)),
+            powerline_selections: styled(Some(Color::White), Some(Color::DarkGrey)),
+            powerline_offset: styled(Some(Color::White), Some(Color::DarkBlue)),
+            info: styled(Some(Color::White), Some(Color::DarkBlue)),
+            prompt: styled(Some(Color::White), Some(Color::DarkBlue)),
+            prompt_cursor: styled(Some(Color::White), Some(Color::Black)),
+            prompt_special: styled(Some(Color::Red), None),
+            prompt_escaped: styled(Some(Color::White), Some(Color::DarkGrey)),
+
+            properties_label: styled(Some(Color::DarkMagenta), Some(Color::Reset)),
+            properties_value: styled(Some(Color::DarkBlue), Some(Color::Reset)),
+            properties_bit_zero: styled(Some(Color::Grey), Some(Color::Reset)),
+            properties_bit_one: styled(Some(Color::DarkBlue), Some(Color::Reset)),
+            properties_invalid: styled(Some(Color::Red), Some(Color::Reset)),
+
+            minimap_entropy: [
+                Color::Grey,
+                Color::DarkCyan,
+                Color::DarkYellow,
+                Color::DarkRed,
+            ],
+            minimap_window: styled(Some(Color::Black), None),
+
+            diff: styled(None, Some(Color::Rgb { r: 250, g: 180, b: 180 })),
+            annotation: styled(None, Some(Color::Rgb { r: 190, g: 235, b: 190 })),
+        }
+    }
+
+    /// A theme that never emits a color, only text attributes. Every style
+    /// that doesn't set an attribute explicitly clears the ones used by the
+    /// others, since nothing else would reset them between bytes.
+    pub fn monochrome() -> Theme {
+        let plain = with_attrs(&[
+            Attribute::NoReverse,
+            Attribute::NoUnderline,
+            Attribute::NormalIntensity,
+        ]);
+        Theme {
+            byte_null: Color::Reset,
+            byte_ascii_printable: Color::Reset,
+            byte_ascii_whitespace: Color::Reset,
+            byte_ascii_other: Color::Reset,
+            byte_nonascii: Color::Reset,
+
+            default: plain,
+            active_selection: with_attrs(&[Attribute::Reverse]),
+            inactive_selection: with_attrs(&[Attribute::Underlined]),
+            active_caret: with_attrs(&[
+                Attribute::NoReverse,
+                Attribute::Underlined,
+                Attribute::Bold,
+            ]),
+            inactive_caret: with_attrs(&[Attribute::NoReverse, Attribute::Underlined]),
+            empty_caret: with_attrs(&[Attribute::Reverse]),
+            mark: with_attrs(&[Attribute::Bold, Attribute::Underlined]),
+            separator: styled(None, None),
+
+            powerline_name: with_attrs(&[Attribute::Reverse]),
+            powerline_mode: with_attrs(&[Attribute::Bold]),
+            powerline_selections: with_attrs(&[Attribute::Reverse]),
+            powerline_offset: with_attrs(&[Attribute::Bold]),
+            info: with_attrs(&[Attribute::Reverse]),
+            prompt: with_attrs(&[Attribute::Reverse]),
+            prompt_cursor: with_attrs(&[Attribute::Reverse]),
+            prompt_special: with_attrs(&[Attribute::Underlined]),
+            prompt_escaped: with_attrs(&[Attribute::Underlined]),
+
+            properties_label: plain,
+            properties_value: with_attrs(&[Attribute::Bold]),
+            properties_bit_zero: plain,
+            properties_bit_one: with_attrs(&[Attribute::Bold]),
+            properties_invalid: with_attrs(&[Attribute::Reverse]),
+
+            minimap_entropy: [Color::Reset; 4],
+            minimap_window: with_attrs(&[Attribute::Bold]),
+
+            diff: with_attrs(&[Attribute::Reverse]),
+            annotation: with_attrs(&[Attribute::Underlined]),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "monochrome" | "mono" | "none" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Loads a builtin theme by name, or else a theme file from disk.
+    pub fn load(name_or_path: &str) -> Result<Theme, String> {
+        if let Some(theme) = Theme::builtin(name_or_path) {
+            return Ok(theme);
+        }
+        let path = Path::new(name_or_path);
+        let text = fs::read_to_string(path).map_err(|e| {
+            format!(
+                "can't load theme {} ({}); builtin themes are: {}",
+                name_or_path,
+                e,
+                BUILTIN_THEMES.join(", ")
+            )
+        })?;
+        Theme::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Picks the theme for this session: an explicitly requested theme wins,
+    /// then `TOFU_THEME`, then `NO_COLOR` (which selects `monochrome`). The
+    /// result is downgraded to what the terminal can display.
+    pub fn for_terminal(requested: Option<&str>) -> Result<Theme, String> {
+        let from_env = env::var("TOFU_THEME").ok().filter(|x| !x.is_empty());
+        let no_color = env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty());
+
+        let theme = match requested.map(str::to_owned).or(from_env) {
+            Some(name) => Theme::load(&name)?,
+            None if no_color => Theme::monochrome(),
+            None => Theme::dark(),
+        };
+        Ok(theme.downgraded(ColorDepth::detect()))
+    }
+
+    /// Parses a theme file. Each line is `key = style`, where a style is
+    /// `[fg] [on bg] [+attribute...]`, e.g. `selection.active = black on #6e6110`.
+    /// Byte classes only take a color. `inherit = <builtin>` picks the theme
+    /// that unspecified keys are taken from (default: `dark`); it has to come
+    /// first, as it would undo the keys before it.
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let mut theme = Theme::dark();
+        let mut is_first = true;
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_no + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            let set = match key {
+                "inherit" if is_first => Theme::builtin(value)
+                    .map(|builtin| theme = builtin)
+                    .ok_or_else(|| format!("unknown theme {}", value)),
+                "inherit" => Err("inherit has to be the first key".into()),
+                _ => theme.set(key, value),
+            };
+            set.map_err(|e| format!("line {}: {}", line_no + 1, e))?;
+            is_first = false;
+        }
+        Ok(theme)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let color_slot = match key {
+            "byte.null" => Some(&mut self.byte_null),
+            "byte.ascii_printable" => Some(&mut self.byte_ascii_printable),
+            "byte.ascii_whitespace" => Some(&mut self.byte_ascii_whitespace),
+            "byte.ascii_other" => Some(&mut self.byte_ascii_other),
+            "byte.nonascii" => Some(&mut self.byte_nonascii),
+            "minimap.entropy_low" => Some(&mut self.minimap_entropy[0]),
+            "minimap.entropy_mid" => Some(&mut self.minimap_entropy[1]),
+            "minimap.entropy_high" => Some(&mut self.minimap_entropy[2]),
+            "minimap.entropy_max" => Some(&mut self.minimap_entropy[3]),
+            _ => None,
+        };
+        if let Some(slot) = color_slot {
+            *slot = parse_color(value)?;
+            return Ok(());
+        }
+        let slot = match key {
+            "default" => &mut self.default,
+            "selection.active" => &mut self.active_selection,
+            "selection.inactive" => &mut self.inactive_selection,
+            "caret.active" => &mut self.active_caret,
+            "caret.inactive" => &mut self.inactive_caret,
+            "caret.empty" => &mut self.empty_caret,
+            "mark" => &mut self.mark,
+            "separator" => &mut self.separator,
+            "powerline.name" => &mut self.powerline_name,
+            "powerline.mode" => &mut self.powerline_mode,
+            "powerline.selections" => &mut self.powerline_selections,
+            "powerline.offset" => &mut self.powerline_offset,
+            "info" => &mut self.info,
+            "prompt" => &mut self.prompt,
+            "prompt.cursor" => &mut self.prompt_cursor,
+            "prompt.special" => &mut self.prompt_special,
+            "prompt.escaped" => &mut self.prompt_escaped,
+            "properties.label" => &mut self.properties_label,
+            "properties.value" => &mut self.properties_value,
+            "properties.bit_zero" => &mut self.properties_bit_zero,
+            "properties.bit_one" => &mut self.properties_bit_one,
+            "properties.invalid" => &mut self.properties_invalid,
+            "minimap.window" => &mut self.minimap_window,
+            "diff" => &mut self.diff,
+            "annotation" => &mut self.annotation,
+            _ => return Err(format!("unknown key {}", key)),
+        };
+        *slot = parse_style(value)?;
+        Ok(())
+    }
+
+    pub fn byte_color(&self, byte: u8) -> Color {
+        if byte == 0x00 {
+            self.byte_null
+        } else if byte.is_ascii_graphic() {
+            self.byte_ascii_printable
+        } else if byte.is_ascii_whitespace() {
+            self.byte_ascii_whitespace
+        } else if byte.is_ascii() {
+            self.byte_ascii_other
+        } else {
+            self.byte_nonascii
+        }
+    }
+
+    /// Replaces every color the terminal can't show with its closest ANSI-16 color.
+    pub fn downgraded(self, depth: ColorDepth) -> Theme {
+        let c = |color: Color| downgrade_color(color, depth);
+        let s = |style: ContentStyle| ContentStyle {
+            foreground_color: style.foreground_color.map(c),
+            background_color: style.background_color.map(c),
+            attributes: style.attributes,
+        };
+        Theme {
+            byte_null: c(self.byte_null),
+            byte_ascii_printable: c(self.byte_ascii_printable),
+            byte_ascii_whitespace: c(self.byte_ascii_whitespace),
+            byte_ascii_other: c(self.byte_ascii_other),
+            byte_nonascii: c(self.byte_nonascii),
+
+            default: s(self.default),
+            active_selection: s(self.active_selection),
+            inactive_selection: s(self.inactive_selection),
+            active_caret: s(self.active_caret),
+            inactive_caret: s(self.inactive_caret),
+            empty_caret: s(self.empty_caret),
+            mark: s(self.mark),
+            separator: s(self.separator),
+
+            powerline_name: s(self.powerline_name),
+            powerline_mode: s(self.powerline_mode),
+            powerline_selections: s(self.powerline_selections),
+            powerline_offset: s(self.powerline_offset),
+            info: s(self.info),
+            prompt: s(self.prompt),
+            prompt_cursor: s(self.prompt_cursor),
+            prompt_special: s(self.prompt_special),
+            prompt_escaped: s(self.prompt_escaped),
+
+            properties_label: s(self.properties_label),
+            properties_value: s(self.properties_value),
+            properties_bit_zero: s(self.properties_bit_zero),
+            properties_bit_one: s(self.properties_bit_one),
+            properties_invalid: s(self.properties_invalid),
+
+            minimap_entropy: self.minimap_entropy.map(c),
+            minimap_window: s(self.minimap_window),
+
+            diff: s(self.diff),
+            annotation: s(self.annotation),
+        }
+    }
+}
+
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn ansi_256_to_rgb(value: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match value {
+        0..=15 => ANSI_16[value as usize].1,
+        16..=231 => {
+            let i = value - 16;
+            (
+                LEVELS[(i / 36) as usize],
+                LEVELS[(i / 6 % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (value - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn nearest_ansi_16((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let dr = r as i32 - r2 as i32;
+        let dg = g as i32 - g2 as i32;
+        let db = b as i32 - b2 as i32;
+        dr * dr + dg * dg + db * db
+    };
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+fn downgrade_color(color: Color, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (_, ColorDepth::TrueColor) => color,
+        (Color::Rgb { r, g, b }, _) => nearest_ansi_16((r, g, b)),
+        (Color::AnsiValue(value), ColorDepth::Ansi16) => nearest_ansi_16(ansi_256_to_rgb(value)),
+        _ => color,
+    }
+}
+
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb {
+                    r: (rgb >> 16) as u8,
+                    g: (rgb >> 8) as u8,
+                    b: rgb as u8,
+                });
+            }
+        }
+        return Err(format!("invalid color {}", text));
+    }
+    if let Ok(value) = text.parse::<u8>() {
+        return Ok(Color::AnsiValue(value));
+    }
+    Ok(match text {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "dark_grey" | "dark_gray" => Color::DarkGrey,
+        "red" => Color::Red,
+        "dark_red" => Color::DarkRed,
+        "green" => Color::Green,
+        "dark_green" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "dark_yellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "dark_blue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "dark_magenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "dark_cyan" => Color::DarkCyan,
+        "white" => Color::White,
+        "grey" | "gray" => Color::Grey,
+        _ => return Err(format!("invalid color {}", text)),
+    })
+}
+
+fn parse_attribute(text: &str) -> Result<Attribute, String> {
+    Ok(match text {
+        "bold" => Attribute::Bold,
+        "dim" => Attribute::Dim,
+        "italic" => Attribute::Italic,
+        "underline" | "underlined" => Attribute::Underlined,
+        "reverse" => Attribute::Reverse,
+        "no_bold" | "normal" => Attribute::NormalIntensity,
+        "no_underline" => Attribute::NoUnderline,
+        "no_reverse" => Attribute::NoReverse,
+        _ => return Err(format!("invalid attribute {}", text)),
+    })
+}
+
+fn parse_style(text: &str) -> Result<ContentStyle, String> {
+    let mut style = styled(None, None);
+    let mut tokens = text.split_whitespace().peekable();
+    if let Some(fg) = tokens.next_if(|x| *x != "on" && !x.starts_with('+')) {
+        style.foreground_color = Some(parse_color(fg)?);
+    }
+    if tokens.next_if(|x| *x == "on").is_some() {
+        let bg = tokens.next().ok_or("expected a color after `on`")?;
+        style.background_color = Some(parse_color(bg)?);
+    }
+    for token in tokens {
+        let attribute = token
+            .strip_prefix('+')
+            .ok_or_else(|| format!("unexpected {}", token))?;
+        style.attributes.set(parse_attribute(attribute)?);
+    }
+    Ok(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = Theme::parse(
+            "# comment\n\
+             inherit = light\n\
+             byte.null = #102030\n\
+             selection.active = black on 42 +bold\n\
+             mark = on dark_blue\n",
+        )
+        .unwrap();
+        assert_eq!(theme.byte_null, Color::Rgb { r: 16, g: 32, b: 48 });
+        assert_eq!(theme.active_selection.foreground_color, Some(Color::Black));
+        assert_eq!(
+            theme.active_selection.background_color,
+            Some(Color::AnsiValue(42))
+        );
+        assert!(theme.active_selection.attributes.has(Attribute::Bold));
+        assert_eq!(theme.mark.foreground_color, None);
+        assert_eq!(theme.byte_ascii_printable, Theme::light().byte_ascii_printable);
+        assert!(Theme::parse("nonsense = red").is_err());
+        assert!(Theme::parse("mark = red\ninherit = light").is_err());
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let theme = Theme::dark().downgraded(ColorDepth::Ansi16);
+        assert_eq!(theme.byte_ascii_other, Color::Magenta);
+        assert_eq!(theme.byte_null, Color::Grey);
+        let theme = Theme::dark().downgraded(ColorDepth::Ansi256);
+        assert_eq!(theme.byte_null, Color::AnsiValue(150));
+    }
+}
@@ -8,7 +8,6 @@ use std::io::{
     Write,
     Read,
     Error,
-    ErrorKind,
 };
 use std::ops::Range;
 use std::time;
@@ -16,9 +15,11 @@ use std::fs::File;
 use std::fs::OpenOptions;
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute, queue, style,
-    style::{Color, Stylize},
     terminal, 
     QueueableCommand, 
     Result,
@@ -26,6 +27,7 @@ use crossterm::{
 use xi_rope::{
     Interval,
     Delta,
+    DeltaBuilder,
     // Rope
 };
 use xi_rope::tree::TreeBuilder;
@@ -36,19 +38,29 @@ use std::time::{
     UNIX_EPOCH
 };
 use super::byte_properties::BytePropertiesFormatter;
+use super::minimap::{Minimap, MINIMAP_WIDTH};
+use crate::byte_source::ByteSource;
+use super::theme::{parse_color, Theme};
 use super::{make_padding, PrioritizedStyle, Priority, StylingCommand};
 use crate::current_buffer::*;
+use crate::annotations::Annotation;
+use crate::executable::section_at;
+use crate::macros::MAX_REPLAY_DEPTH;
 use crate::hex_view::OutputColorizer;
 use crate::modes;
-use crate::modes::mode::{DirtyBytes, Mode, ModeTransition};
+use crate::modes::help::{self, Help};
+use crate::modes::minimap::MinimapJump;
+use crate::modes::outline::OutlineFocus;
+use crate::modes::mode::{jump_window_len, DirtyBytes, Mode, ModeTransition};
 use crate::selection::Direction;
 // use std::path::Path;
 use std::env;
 
 use crate::byte_rope::Rope as CustomByteRope;
 
-const VERTICAL: &str = "│";
 const LEFTARROW: &str = "";
+/// Annotation text shown in the status line is cut to this many characters
+const MAX_STATUS_ANNOTATION: usize = 32;
 
 // Oh my Uma, it's a Debug-Log... 
 // Why is this returning a result???
@@ -73,7 +85,6 @@ fn debug_log(message: &str) {
             if log_path.exists() {
                 // If the path exists, attempt to open the file for writing
                 if let Ok(mut file) = OpenOptions::new()
-                    .write(true)
                     .append(true)
                     .open(&log_path) {
 
@@ -88,6 +99,7 @@ fn debug_log(message: &str) {
 }
 
 
+#[allow(dead_code)]
 struct MixedRepr(u8);
 
 impl fmt::Display for MixedRepr {
@@ -100,10 +112,18 @@ impl fmt::Display for MixedRepr {
     }
 }
 
+fn wildcard_cursor_style(theme: &Theme) -> style::ContentStyle {
+    style::ContentStyle {
+        background_color: theme.prompt_cursor.background_color,
+        ..theme.prompt_special
+    }
+}
+
 trait StatusLinePrompter: Mode {
     fn render_with_size(
         &self,
         stdout: &mut dyn Write,
+        theme: &Theme,
         max_width: usize,
         last_start_col: usize,
     ) -> Result<usize>;
@@ -124,6 +144,7 @@ impl StatusLinePrompter for modes::search::Search {
     fn render_with_size(
         &self,
         stdout: &mut dyn Write,
+        theme: &Theme,
         mut max_width: usize,
         last_start_col: usize,
     ) -> Result<usize> {
@@ -131,9 +152,7 @@ impl StatusLinePrompter for modes::search::Search {
         d_queue!(
             stdout,
             style::PrintStyledContent(
-                style::style("search:")
-                    .with(style::Color::White)
-                    .on(style::Color::Blue),
+                style::StyledContent::new(theme.prompt, "search:"),
             )
         )?;
         max_width -= "search:".len();
@@ -167,9 +186,7 @@ impl StatusLinePrompter for modes::search::Search {
                             stdout,
                             style::Print(format!("{:x}", byte >> 4)),
                             style::PrintStyledContent(
-                                style::style(format!("{:x}", byte & 0xf))
-                                    .with(style::Color::Black)
-                                    .on(style::Color::White)
+                                style::StyledContent::new(theme.prompt_cursor, format!("{:x}", byte & 0xf))
                             ),
                             style::Print(" "),
                         )?
@@ -177,22 +194,18 @@ impl StatusLinePrompter for modes::search::Search {
                     PatternPiece::Literal(byte) => d_queue!(
                         stdout,
                         style::PrintStyledContent(
-                            style::style(format!("{:02x}", byte))
-                                .with(style::Color::Black)
-                                .on(style::Color::White)
+                            style::StyledContent::new(theme.prompt_cursor, format!("{:02x}", byte))
                         ),
                         style::Print(" "),
                     )?,
                     PatternPiece::Wildcard if normalized_cursor != i => d_queue!(
                         stdout,
-                        style::PrintStyledContent(style::style("** ").with(style::Color::DarkRed))
+                        style::PrintStyledContent(style::StyledContent::new(theme.prompt_special, "** "))
                     )?,
                     PatternPiece::Wildcard => d_queue!(
                         stdout,
                         style::PrintStyledContent(
-                            style::style("**")
-                                .with(style::Color::DarkRed)
-                                .on(style::Color::White)
+                            style::StyledContent::new(wildcard_cursor_style(theme), "**")
                         ),
                         style::Print(" "),
                     )?,
@@ -202,9 +215,7 @@ impl StatusLinePrompter for modes::search::Search {
                 d_queue!(
                     stdout,
                     style::PrintStyledContent(
-                        style::style("  ")
-                            .with(style::Color::Black)
-                            .on(style::Color::White)
+                        style::StyledContent::new(theme.prompt_cursor, "  ")
                     ),
                     style::Print(" "),
                 )?
@@ -259,9 +270,7 @@ impl StatusLinePrompter for modes::search::Search {
                 PatternPiece::Literal(byte) if normalized_cursor != i => d_queue!(
                     stdout,
                     style::PrintStyledContent(
-                        style::style(format!("<{:02x}>", byte))
-                            .with(style::Color::Black)
-                            .on(style::Color::DarkGrey)
+                        style::StyledContent::new(theme.prompt_escaped, format!("<{:02x}>", byte))
                     ),
                 )?,
                 PatternPiece::Literal(byte)
@@ -270,30 +279,24 @@ impl StatusLinePrompter for modes::search::Search {
                     d_queue!(
                         stdout,
                         style::PrintStyledContent(
-                            style::style(format!("{}", *byte as char))
-                                .with(style::Color::Black)
-                                .on(style::Color::White)
+                            style::StyledContent::new(theme.prompt_cursor, format!("{}", *byte as char))
                         ),
                     )?
                 }
                 PatternPiece::Literal(byte) => d_queue!(
                     stdout,
                     style::PrintStyledContent(
-                        style::style(format!("<{:02x}>", byte))
-                            .with(style::Color::Black)
-                            .on(style::Color::White)
+                        style::StyledContent::new(theme.prompt_cursor, format!("<{:02x}>", byte))
                     ),
                 )?,
                 PatternPiece::Wildcard if normalized_cursor != i => d_queue!(
                     stdout,
-                    style::PrintStyledContent(style::style("*").with(style::Color::DarkRed))
+                    style::PrintStyledContent(style::StyledContent::new(theme.prompt_special, "*"))
                 )?,
                 PatternPiece::Wildcard => d_queue!(
                     stdout,
                     style::PrintStyledContent(
-                        style::style("*")
-                            .with(style::Color::DarkRed)
-                            .on(style::Color::White)
+                        style::StyledContent::new(wildcard_cursor_style(theme), "*")
                     ),
                 )?,
             }
@@ -303,9 +306,7 @@ impl StatusLinePrompter for modes::search::Search {
             d_queue!(
                 stdout,
                 style::PrintStyledContent(
-                    style::style(" ")
-                        .with(style::Color::Black)
-                        .on(style::Color::White)
+                    style::StyledContent::new(theme.prompt_cursor, " ")
                 ),
             )?;
         }
@@ -318,6 +319,7 @@ impl StatusLinePrompter for modes::command::Command {
     fn render_with_size(
         &self,
         stdout: &mut dyn Write,
+        theme: &Theme,
         mut max_width: usize,
         last_start_col: usize,
     ) -> Result<usize> {
@@ -325,9 +327,7 @@ impl StatusLinePrompter for modes::command::Command {
         d_queue!(
             stdout,
             style::PrintStyledContent(
-                style::style(":")
-                    .with(style::Color::White)
-                    .on(style::Color::Blue),
+                style::StyledContent::new(theme.prompt, ":"),
             )
         )?;
         max_width -= 1;
@@ -358,9 +358,7 @@ impl StatusLinePrompter for modes::command::Command {
             d_queue!(
                 stdout,
                 style::PrintStyledContent(
-                    style::style(" ")
-                        .with(style::Color::Black)
-                        .on(style::Color::White)
+                    style::StyledContent::new(theme.prompt_cursor, " ")
                 ),
             )?;
         }
@@ -369,10 +367,97 @@ impl StatusLinePrompter for modes::command::Command {
     }
 }
 
+/// Lines scrolled per mouse wheel step.
+const MOUSE_SCROLL_LINES: usize = 3;
+
+/// Positions of the status line segments that react to clicks.
+const STATUS_NAME_SEGMENT: usize = 0;
+const STATUS_SELECTIONS_SEGMENT: usize = 2;
+
+/// Widest the help overlay gets on wide terminals.
+const MAX_HELP_WIDTH: usize = 72;
+const HELP_FOOTER: &str = " j/k scroll, any other key closes, :help <mode> for other modes";
+
+/// What a row shows right of its ASCII column.
+enum SidePanel<'a, 'b> {
+    Properties(&'a mut BytePropertiesFormatter<'b>),
+    /// The compared file's bytes for the same row
+    Diff(&'a [u8]),
+    /// Left blank for the outline panel, which is drawn over it
+    Outline,
+}
+
+/// Columns of a byte row: a padding space, three columns per hex byte, a
+/// separator, the ASCII pane with one column of padding, another separator
+/// and the side panel. `draw_row` pads to these and clicks are mapped back
+/// through them.
+struct RowLayout {
+    bytes_per_line: usize,
+}
+
+/// A separator is a bar and a space.
+const SEPARATOR_WIDTH: usize = 2;
+
+impl RowLayout {
+    fn hex_column(&self, index: usize) -> usize {
+        1 + 3 * index
+    }
+
+    fn hex_end(&self) -> usize {
+        self.hex_column(self.bytes_per_line)
+    }
+
+    fn ascii_column(&self, index: usize) -> usize {
+        self.hex_end() + SEPARATOR_WIDTH + index
+    }
+
+    fn ascii_end(&self) -> usize {
+        self.ascii_column(self.bytes_per_line) + 1
+    }
+
+    fn side_panel_column(&self) -> usize {
+        self.ascii_end() + SEPARATOR_WIDTH
+    }
+
+    /// The index in the row of the byte drawn at `column` in either pane.
+    fn index_at(&self, column: usize) -> Option<usize> {
+        if (self.hex_column(0)..self.hex_end()).contains(&column) {
+            Some((column - self.hex_column(0)) / 3)
+        } else if (self.ascii_column(0)..self.ascii_column(self.bytes_per_line)).contains(&column) {
+            Some(column - self.ascii_column(0))
+        } else {
+            None
+        }
+    }
+}
+
+/// `text` cut or padded with spaces to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.push_str(&" ".repeat(width - len));
+    fitted
+}
+
+/// Offsets in `start..` whose byte differs from the compared file's bytes,
+/// which start at the same offset.
+fn differing_offsets(start: usize, bytes: &[u8], other: Option<&[u8]>) -> BTreeSet<usize> {
+    match other {
+        Some(other) => bytes
+            .iter()
+            .enumerate()
+            .filter(|(i, byte)| other.get(*i) != Some(byte))
+            .map(|(i, _)| start + i)
+            .collect(),
+        None => BTreeSet::new(),
+    }
+}
+
 pub struct HexView {
     buffr_collection: BuffrCollection,
     size: (u16, u16),
     bytes_per_line: usize,
+    configured_bytes_per_line: usize,
     start_offset: usize,
     last_visible_rows: Cell<usize>,
     last_visible_prompt_col: Cell<usize>,
@@ -384,11 +469,16 @@ pub struct HexView {
 }
 
 impl HexView {
+    /// Bytes on one screen of rows, the chunk the buffer is trimmed by,
+    /// e.g. 22 rows x 16 bytes is 352
+    fn chunk_size(&self) -> usize {
+        (self.size.1 as usize).saturating_sub(1) * self.bytes_per_line  // Subtract status line
+    }
+
     /// Checks if the buffer size exceeds the threshold for trimming
-    /// Uses dynamic chunk size based on terminal height
+    /// Uses dynamic chunk size based on the view height
     fn should_trim_buffer(&self) -> bool {
-        let (_, height) = terminal::size().unwrap_or((80, 23));
-        let chunk_size = (height as usize - 1) * 16;
+        let chunk_size = self.chunk_size();
         let buffer_threshold = chunk_size * 3;  // 3x chunk size threshold
         
         let current_size = self.buffr_collection.current().data.len();
@@ -398,6 +488,7 @@ impl HexView {
         current_size > buffer_threshold
     }
     
+    #[allow(dead_code)]
     fn is_near_bottom(&self) -> bool {
         debug_log("is_near_bottom()");
         
@@ -412,12 +503,14 @@ impl HexView {
         total_buffer_bytes - current_view_end < (total_buffer_bytes / 10)
     }
     
+    #[allow(dead_code)]
     fn is_near_top(&self) -> bool {
         // Within first 10% of buffer
         self.start_offset < (self.buffr_collection.current().data.len() / 10)
     }
 
     // Similar for add_chunk_to_bottom:
+    #[allow(dead_code)]
     fn add_chunk_to_bottom(&mut self, chunk_size: usize) -> std::result::Result<(), std::io::Error> {
         debug_log(&format!("Attempting to add chunk to bottom, size={}", chunk_size));
         
@@ -461,6 +554,7 @@ impl HexView {
         Ok(())
     }    
 
+    #[allow(dead_code)]
     fn add_chunk_to_top(&mut self, chunk_size: usize) -> std::result::Result<(), std::io::Error> {
         debug_log(&format!("add_chunk_to_top, size={:?}", chunk_size));
         
@@ -547,6 +641,7 @@ impl HexView {
     /// chunk_size: 3
     /// After Trim:   [A B C D E]         (size 5)
     /// ```
+    #[allow(dead_code)]
     fn trim_buffer_bottom(&mut self, chunk_size: usize) {
         debug_log(&format!("trim_buffer_bottom, size={:?}", chunk_size));
         
@@ -682,11 +777,19 @@ impl HexView {
             let new_len = current_buffer.data.len();
             
             debug_log(&format!("Buffer trimmed: {} -> {}", old_len, new_len));
+
+            // Keep file addresses stable: the window now starts further into the file
+            let mut builder = DeltaBuilder::new(old_len);
+            builder.delete(Interval::new(0, chunk_size));
+            current_buffer.selection.apply_delta(&builder.build(), new_len);
+            current_buffer.data_start_offset += chunk_size;
+            current_buffer.window_file_len = current_buffer.window_file_len.saturating_sub(chunk_size);
             
             self.start_offset = self.start_offset.saturating_sub(chunk_size);
         }
     }
 
+    #[allow(dead_code)]
     fn manage_buffer(&mut self) -> std::result::Result<(), std::io::Error> {
 
         let chunk_size = 368;  // Your previous chunk size
@@ -710,6 +813,7 @@ impl HexView {
         HexView {
             buffr_collection,
             bytes_per_line: 0x10,
+            configured_bytes_per_line: 0x10,
             start_offset: 0,
             size: terminal::size().unwrap(),
             last_visible_rows: Cell::new(0),
@@ -724,6 +828,47 @@ impl HexView {
 
     pub fn set_bytes_per_line(&mut self, bpl: usize) {
         self.bytes_per_line = bpl;
+        self.configured_bytes_per_line = bpl;
+    }
+
+    /// The outline panel shows unless the compared file's pane takes its place.
+    fn outline_shown(&self) -> bool {
+        let current_buffer = self.buffr_collection.current();
+        current_buffer.diff_target.is_none() && current_buffer.outline.is_some()
+    }
+
+    /// While diffing or showing an outline, the side pane needs more room, so
+    /// rows are halved.
+    fn sync_layout(&mut self) {
+        let diffing = self.buffr_collection.current().diff_target.is_some();
+        let bytes_per_line = if diffing || self.outline_shown() {
+            cmp::max(1, self.configured_bytes_per_line / 2)
+        } else {
+            self.configured_bytes_per_line
+        };
+        if bytes_per_line != self.bytes_per_line {
+            self.bytes_per_line = bytes_per_line;
+            self.start_offset -= self.start_offset % bytes_per_line;
+        }
+    }
+
+    /// Bytes of the compared file for all rows on screen, at the same file
+    /// offsets as the current buffer's rows.
+    fn diff_other_bytes(&self) -> Option<Vec<u8>> {
+        let target = self.buffr_collection.current().diff_target.as_ref()?;
+        let file_offset = self.buffr_collection.current().data_start_offset + self.start_offset;
+        let len = (self.size.1 as usize).saturating_sub(1) * self.bytes_per_line;
+        target.source.read_at(file_offset as u64, len).ok()
+    }
+
+    /// The part of screen-wide bytes that belongs to the row starting at `start`.
+    fn row_of<'a>(&self, bytes: &'a [u8], start: usize) -> &'a [u8] {
+        let start = cmp::min(start, bytes.len());
+        &bytes[start..cmp::min(bytes.len(), start + self.bytes_per_line)]
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.colorizer = OutputColorizer::with_theme(theme);
     }
 
     fn draw_hex_row(
@@ -731,10 +876,7 @@ impl HexView {
         stdout: &mut impl Write,
         styled_bytes: impl IntoIterator<Item = (u8, StylingCommand)>,
     ) -> Result<()> {
-        for (byte, style_cmd) in styled_bytes.into_iter() {
-            self.colorizer.draw_hex_byte(stdout, byte, &style_cmd)?;
-        }
-        Ok(())
+        self.colorizer.draw_hex_row(stdout, styled_bytes)
     }
 
     fn draw_ascii_row(
@@ -742,18 +884,15 @@ impl HexView {
         stdout: &mut impl Write,
         styled_bytes: impl IntoIterator<Item = (u8, StylingCommand)>,
     ) -> Result<()> {
-        for (byte, style_cmd) in styled_bytes.into_iter() {
-            self.colorizer.draw_ascii_byte(stdout, byte, &style_cmd)?;
-        }
-        Ok(())
+        self.colorizer.draw_ascii_row(stdout, styled_bytes)
     }
 
     fn draw_separator(&self, stdout: &mut impl Write) -> Result<()> {
-        queue!(stdout, style::SetForegroundColor(Color::White))?;
-        queue!(stdout, style::Print(format!("{} ", VERTICAL)))
+        self.colorizer.draw_separator(stdout)
     }
     
     /// Safely calculates if an offset is within valid bounds
+    #[allow(dead_code)]
     fn is_valid_offset(&self, offset: usize) -> bool {
         let buffer_size = self.buffr_collection.current().data.len();
         offset < buffer_size
@@ -775,7 +914,7 @@ impl HexView {
     /// * `Err` - If calculated row would be outside visible screen area
     /// 
     /// # Example
-    /// ```
+    /// ```text
     /// // If start_offset = 32 (viewing starts at 3rd line of file)
     /// // bytes_per_line = 16
     /// // screen height = 24
@@ -803,14 +942,14 @@ impl HexView {
         if offset < self.start_offset {
             debug_log(&format!("offset_to_row: offset {} is before start_offset {}", 
                 offset, self.start_offset));
-            return Err(Error::new(ErrorKind::Other, "Offset before visible area"));
+            return Err(Error::other("Offset before visible area"));
         }
     
         let row = (offset - self.start_offset) / self.bytes_per_line;
         if row >= self.size.1 as usize {
             debug_log(&format!("offset_to_row: row {} exceeds screen height {}", 
                 row, self.size.1));
-            return Err(Error::new(ErrorKind::Other, "Row outside visible area"));
+            return Err(Error::other("Row outside visible area"));
         }
         Ok(row as u16)
     }
@@ -824,7 +963,7 @@ impl HexView {
         offset: usize,
         mark_commands: &[StylingCommand],
         end_style: Option<StylingCommand>,
-        byte_properties: &mut BytePropertiesFormatter,
+        side_panel: SidePanel,
     ) -> Result<()> {
         debug_log(&format!("draw_row, offset={:?}", offset));
         // let row_num = self.offset_to_row(offset).unwrap(); // panic here (don't ever use unwrap!!)
@@ -838,21 +977,18 @@ impl HexView {
             }
         };
 
+        let layout = self.row_layout();
         queue!(stdout, cursor::MoveTo(0, row_num))?;
         queue!(
             stdout,
-            style::Print(" ".to_string()), // Padding
+            style::Print(make_padding(layout.hex_column(0))), // Padding
         )?;
         self.draw_hex_row(
             stdout,
             bytes.iter().copied().zip(mark_commands.iter().cloned()),
         )?;
 
-        let mut padding_length = if bytes.is_empty() {
-            self.bytes_per_line * 3
-        } else {
-            (self.bytes_per_line - bytes.len()) % self.bytes_per_line * 3
-        };
+        let mut padding_length = layout.hex_end() - layout.hex_column(bytes.len());
 
         if let Some(style_cmd) = &end_style {
             padding_length -= 2;
@@ -871,11 +1007,7 @@ impl HexView {
             bytes.iter().copied().zip(mark_commands.iter().cloned()),
         )?;
 
-        let mut padding_length = if bytes.is_empty() {
-            self.bytes_per_line
-        } else {
-            (self.bytes_per_line - bytes.len()) % self.bytes_per_line
-        } + 1;
+        let mut padding_length = layout.ascii_end() - layout.ascii_column(bytes.len());
 
         if let Some(style_cmd) = end_style {
             padding_length -= 1;
@@ -886,13 +1018,82 @@ impl HexView {
         queue!(stdout, style::Print(make_padding(padding_length)))?;
         self.draw_separator(stdout)?;
 
-        byte_properties.draw_line(stdout, &self.colorizer)?;
+        match side_panel {
+            SidePanel::Properties(byte_properties) => {
+                byte_properties.draw_line(stdout, &self.colorizer)?
+            }
+            SidePanel::Diff(other) => self.draw_diff_pane(stdout, bytes, other)?,
+            SidePanel::Outline => {}
+        }
 
         queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine))?;
 
         Ok(())
     }
 
+    /// Draws the compared file's bytes for a row, highlighting those that
+    /// differ from `bytes`.
+    fn draw_diff_pane(&self, stdout: &mut impl Write, bytes: &[u8], other: &[u8]) -> Result<()> {
+        let styled: Vec<(u8, StylingCommand)> = other
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| {
+                let style = if bytes.get(i) != Some(&byte) {
+                    self.diff_style()
+                } else {
+                    self.default_style()
+                };
+                let style_cmd = StylingCommand::default()
+                    .with_start_style(style)
+                    .with_end_style(self.default_style());
+                (byte, style_cmd)
+            })
+            .collect();
+
+        self.draw_hex_row(stdout, styled.iter().cloned())?;
+        queue!(
+            stdout,
+            style::Print(make_padding((self.bytes_per_line - other.len()) * 3))
+        )?;
+        self.draw_separator(stdout)?;
+        self.draw_ascii_row(stdout, styled)
+    }
+
+    /// Rows below the current buffer's data: the rest of the byte properties
+    /// panel, or the rest of the compared file while diffing.
+    fn draw_trailing_rows(
+        &self,
+        stdout: &mut impl Write,
+        end_index: usize,
+        byte_properties: &mut BytePropertiesFormatter,
+        other: Option<&[u8]>,
+    ) -> Result<()> {
+        let a = end_index / self.bytes_per_line;
+        let mut offset = (if end_index.is_multiple_of(self.bytes_per_line) {
+            a
+        } else {
+            a + 1
+        }) * self.bytes_per_line;
+        match other {
+            Some(other) => {
+                while offset < self.start_offset + other.len() {
+                    let row_other = self.row_of(other, offset - self.start_offset);
+                    self.draw_row(stdout, &[], offset, &[], None, SidePanel::Diff(row_other))?;
+                    offset += self.bytes_per_line;
+                }
+            }
+            None if self.outline_shown() => {}
+            None => {
+                while !byte_properties.are_all_printed() {
+                    let side_panel = SidePanel::Properties(byte_properties);
+                    self.draw_row(stdout, &[], offset, &[], None, side_panel)?;
+                    offset += self.bytes_per_line;
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn visible_bytes(&self) -> Range<usize> {
         self.start_offset
             ..cmp::min(
@@ -903,65 +1104,77 @@ impl HexView {
 
     fn default_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new()
-                .with(style::Color::White)
-                .on(style::Color::Reset),
+            style: self.colorizer.theme().default,
             priority: Priority::Basic,
         }
     }
 
     fn active_selection_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new()
-                .with(style::Color::Black)
-                .on(style::Color::Rgb {
-                    r: 110,
-                    g: 97,
-                    b: 16,
-                }),
+            style: self.colorizer.theme().active_selection,
             priority: Priority::Selection,
         }
     }
 
     fn inactive_selection_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new()
-                .with(style::Color::Black)
-                .on(style::Color::DarkGrey),
+            style: self.colorizer.theme().inactive_selection,
             priority: Priority::Selection,
         }
     }
 
     fn active_caret_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new()
-                .with(style::Color::AnsiValue(16))
-                .on(style::Color::Rgb {
-                    r: 107,
-                    g: 108,
-                    b: 128,
-                }),
+            style: self.colorizer.theme().active_caret,
             priority: Priority::Cursor,
         }
     }
 
     fn inactive_caret_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new()
-                .with(style::Color::Black)
-                .on(style::Color::DarkGrey),
+            style: self.colorizer.theme().inactive_caret,
             priority: Priority::Cursor,
         }
     }
 
+    fn diff_style(&self) -> PrioritizedStyle {
+        PrioritizedStyle {
+            style: self.colorizer.theme().diff,
+            priority: Priority::Mark,
+        }
+    }
+
+    /// The theme's annotation style, on the annotation's own color if it has one.
+    fn annotation_style(&self, annotation: &Annotation) -> PrioritizedStyle {
+        let mut style = self.colorizer.theme().annotation;
+        if let Some(color) = annotation.color.as_deref().and_then(|c| parse_color(c).ok()) {
+            style.background_color = Some(color);
+        }
+        PrioritizedStyle {
+            style,
+            priority: Priority::Mark,
+        }
+    }
+
+    fn bookmark_style(&self) -> PrioritizedStyle {
+        PrioritizedStyle {
+            style: self.colorizer.theme().mark,
+            priority: Priority::Mark,
+        }
+    }
+
     fn empty_caret_style(&self) -> PrioritizedStyle {
         PrioritizedStyle {
-            style: style::ContentStyle::new().on(style::Color::Green),
+            style: self.colorizer.theme().empty_caret,
             priority: Priority::Cursor,
         }
     }
 
-    fn mark_commands(&self, visible: Range<usize>) -> Vec<StylingCommand> {
+    fn mark_commands(
+        &self,
+        visible: Range<usize>,
+        differing: &BTreeSet<usize>,
+    ) -> Vec<StylingCommand> {
         let mut mark_commands = vec![StylingCommand::default(); visible.len()];
         let mut selected_regions = self
             .buffr_collection
@@ -970,6 +1183,25 @@ impl HexView {
             .regions_in_range(visible.start, visible.end);
         let mut command_stack = vec![self.default_style()];
         let start = visible.start;
+        let current_buffer = self.buffr_collection.current();
+        let bookmarks: BTreeSet<usize> = current_buffer
+            .marks
+            .iter()
+            .filter_map(|(_, mark)| mark.offset.checked_sub(current_buffer.data_start_offset))
+            .filter(|offset| visible.contains(offset))
+            .collect();
+        // Longer annotations first, so nested ones are drawn over them
+        let mut annotated = vec![None; visible.len()];
+        let mut annotations: Vec<&Annotation> = current_buffer.annotations.iter().collect();
+        annotations.sort_by_key(|annotation| cmp::Reverse(annotation.range.len()));
+        for annotation in annotations {
+            let style = self.annotation_style(annotation);
+            let first = annotation.range.start.saturating_sub(current_buffer.data_start_offset);
+            let end = annotation.range.end.saturating_sub(current_buffer.data_start_offset);
+            for i in cmp::max(first, visible.start)..cmp::min(end, visible.end) {
+                annotated[i - start] = Some(style.clone());
+            }
+        }
 
         // Add to command stack those commands that being out of bounds
         if !selected_regions.is_empty() && selected_regions[0].min() < start {
@@ -1025,6 +1257,25 @@ impl HexView {
                 }
             }
 
+            // Marks, annotations and differences only show where no selection is drawn
+            let highlight = if bookmarks.contains(&i) {
+                Some(self.bookmark_style())
+            } else if annotated[normalized].is_some() {
+                annotated[normalized].clone()
+            } else if differing.contains(&i) {
+                Some(self.diff_style())
+            } else {
+                None
+            };
+            if let Some(highlight) = highlight {
+                if command_stack.len() == 1 && mark_commands[normalized].start_style().is_none() {
+                    mark_commands[normalized] = mark_commands[normalized]
+                        .clone()
+                        .with_start_style(highlight)
+                        .with_end_style(self.default_style());
+                }
+            }
+
             if i % self.bytes_per_line == 0 && mark_commands[normalized].start_style().is_none() {
                 // line starts: restore applied style
                 mark_commands[normalized] = mark_commands[normalized]
@@ -1047,106 +1298,80 @@ impl HexView {
         mark_commands
     }
 
-    fn calculate_powerline_length(&self) -> usize {
+    fn powerline_segments(&self) -> Vec<(String, style::ContentStyle)> {
         let buf = self.buffr_collection.current();
-        let mut length = 0;
-        length += 1; // leftarrow
-        length += 2 + buf.name().len();
-        if buf.dirty {
-            length += 3;
-        }
-        length += 1; // leftarrow
-        length += 2 + self.mode.name().len();
-        length += 1; // leftarrow
-        length += format!(
-            " {} sels ({}) ",
-            buf.selection.len(),
-            buf.selection.main_selection + 1
-        )
-        .len();
-        length += 1; // leftarrow
+        let theme = self.colorizer.theme();
+        let mut segments = vec![
+            (
+                format!(" {}{} ", buf.name(), if buf.dirty { "[+]" } else { "" }),
+                theme.powerline_name,
+            ),
+            (format!(" {} ", self.mode.name()), theme.powerline_mode),
+        ];
+        if let Some(name) = self.buffr_collection.macros.recording() {
+            segments.push((format!(" recording @{} ", name), theme.powerline_mode));
+        }
+        segments.push((
+            format!(
+                " {} sels ({}) ",
+                buf.selection.len(),
+                buf.selection.main_selection + 1
+            ),
+            theme.powerline_selections,
+        ));
+        let cursor_offset = buf.data_start_offset + buf.selection.main_cursor_offset();
+        if let Some(section) = section_at(&buf.sections, cursor_offset as u64) {
+            segments.push((format!(" {} ", section.name), theme.powerline_selections));
+        }
+        if let Some(annotation) = buf.annotations.at(cursor_offset) {
+            let text: String = annotation.text.chars().take(MAX_STATUS_ANNOTATION).collect();
+            segments.push((format!(" {} ", text), theme.powerline_selections));
+        }
+        if !buf.checksum_rules.is_empty() {
+            segments.push(match buf.bad_checksums {
+                Some(0) => (" checksum ok ".to_string(), theme.powerline_selections),
+                Some(bad) => (format!(" {} checksum bad ", bad), theme.properties_invalid),
+                None => (" checksum ? ".to_string(), theme.powerline_selections),
+            });
+        }
         if !buf.data.is_empty() {
-            length += format!(
-                " {:x}/{:x} ",
-                buf.selection.main_cursor_offset(),
-                buf.data.len() - 1
-            )
-            .len();
+            let last_offset = buf
+                .file_len()
+                .map(|len| len as usize)
+                .filter(|&len| len > 0)
+                .unwrap_or(buf.data_start_offset + buf.data.len())
+                .max(buf.data_start_offset + buf.data.len());
+            segments.push((
+                format!(" {:x}/{:x} ", cursor_offset, last_offset - 1),
+                theme.powerline_offset,
+            ));
         } else {
-            length += " empty ".len();
+            segments.push((" empty ".to_string(), theme.powerline_offset));
         }
-        length
+        segments
+    }
+
+    fn calculate_powerline_length(&self) -> usize {
+        self.powerline_segments()
+            .iter()
+            .map(|(text, _)| 1 + text.chars().count()) // leftarrow + text
+            .sum()
     }
 
     fn draw_statusline_here(&self, stdout: &mut impl Write) -> Result<()> {
-        let buf = self.buffr_collection.current();
-        queue!(
-            stdout,
-            style::PrintStyledContent(style::style(LEFTARROW).with(Color::Red)),
-            style::PrintStyledContent(
-                style::style(format!(
-                    " {}{} ",
-                    self.buffr_collection.current().name(),
-                    if self.buffr_collection.current().dirty {
-                        "[+]"
-                    } else {
-                        ""
-                    }
-                ))
-                .with(Color::White)
-                .on(Color::Red)
-            ),
-            style::PrintStyledContent(
-                style::style(LEFTARROW)
-                    .with(Color::DarkYellow)
-                    .on(Color::Red)
-            ),
-            style::PrintStyledContent(
-                style::style(format!(" {} ", self.mode.name()))
-                    .with(Color::AnsiValue(16))
-                    .on(Color::DarkYellow)
-            ),
-            style::PrintStyledContent(
-                style::style(LEFTARROW)
-                    .with(Color::White)
-                    .on(Color::DarkYellow)
-            ),
-            style::PrintStyledContent(
-                style::style(format!(
-                    " {} sels ({}) ",
-                    buf.selection.len(),
-                    buf.selection.main_selection + 1
-                ))
-                .with(Color::AnsiValue(16))
-                .on(Color::White)
-            ),
-        )?;
-        if !buf.data.is_empty() {
-            queue!(
-                stdout,
-                style::PrintStyledContent(
-                    style::style(LEFTARROW).with(Color::Blue).on(Color::White)
-                ),
-                style::PrintStyledContent(
-                    style::style(format!(
-                        " {:x}/{:x} ",
-                        buf.selection.main_cursor_offset(),
-                        buf.data.len() - 1,
-                    ))
-                    .with(Color::White)
-                    .on(Color::Blue),
-                ),
-            )?;
-        } else {
+        let mut previous_bg = None;
+        for (text, segment_style) in self.powerline_segments() {
+            let arrow_style = style::ContentStyle {
+                foreground_color: segment_style.background_color,
+                background_color: previous_bg,
+                attributes: Default::default(),
+            };
             queue!(
                 stdout,
-                style::PrintStyledContent(
-                    style::style(LEFTARROW).with(Color::Blue).on(Color::White)
-                ),
-                style::PrintStyledContent(
-                    style::style(" empty ").with(Color::White).on(Color::Blue),
-                ),
+                style::PrintStyledContent(style::StyledContent::new(arrow_style, LEFTARROW)),
+                style::PrintStyledContent(style::StyledContent::new(segment_style, text)),
             )?;
+            previous_bg = segment_style.background_color;
         }
         Ok(())
     }
@@ -1158,11 +1383,10 @@ impl HexView {
                 stdout,
                 cursor::MoveTo(0, self.size.1 - 1),
                 terminal::Clear(terminal::ClearType::CurrentLine),
-                style::PrintStyledContent(
-                    style::style(info)
-                        .with(style::Color::White)
-                        .on(style::Color::Blue)
-                ),
+                style::PrintStyledContent(style::StyledContent::new(
+                    self.colorizer.theme().info,
+                    info
+                )),
                 cursor::MoveTo(self.size.0 - line_length as u16, self.size.1),
             )?;
         } else {
@@ -1187,7 +1411,12 @@ impl HexView {
         if let Some(statusliner) = prompter {
             queue!(stdout, cursor::MoveTo(0, self.size.1))?;
             let prev_col = self.last_visible_prompt_col.get();
-            let new_col = statusliner.render_with_size(stdout, self.size.0 as usize, prev_col)?;
+            let new_col = statusliner.render_with_size(
+                stdout,
+                self.colorizer.theme(),
+                self.size.0 as usize,
+                prev_col,
+            )?;
             self.last_visible_prompt_col.set(new_col);
         }
 
@@ -1257,7 +1486,9 @@ impl HexView {
             .slice_to_cow(start_index..end_index);
 
         let max_bytes = visible_bytes_cow.len();
-        let mark_commands = self.mark_commands(visible_bytes.clone());
+        let other = self.diff_other_bytes();
+        let differing = differing_offsets(start_index, &visible_bytes_cow, other.as_deref());
+        let mark_commands = self.mark_commands(visible_bytes.clone(), &differing);
 
         let current_bytes = self
             .buffr_collection
@@ -1296,26 +1527,234 @@ impl HexView {
                 } else {
                     None
                 },
-                &mut byte_properties,
+                match &other {
+                    Some(other) => SidePanel::Diff(self.row_of(other, normalized_i)),
+                    None if self.outline_shown() => SidePanel::Outline,
+                    None => SidePanel::Properties(&mut byte_properties),
+                },
             )?;
         }
 
-        let a = end_index / self.bytes_per_line;
-        let mut offset = (if end_index % self.bytes_per_line == 0 {
-            a
+        self.draw_trailing_rows(stdout, end_index, &mut byte_properties, other.as_deref())?;
+
+        self.draw_overlays(stdout)
+    }
+
+    /// Starts, restarts or drops the minimap so it matches the setting and the
+    /// current buffer's file and terminal height.
+    fn sync_minimap(&mut self) {
+        let kind = self.buffr_collection.minimap();
+        let path = self.buffr_collection.current().path.clone();
+        let cell_count = (self.size.1 as usize).saturating_sub(1);
+        let (kind, path) = match (kind, path) {
+            (Some(kind), Some(path)) if cell_count > 0 => (kind, path),
+            _ => {
+                self.buffr_collection.shown_minimap = None;
+                return;
+            }
+        };
+
+        let up_to_date = self.buffr_collection.shown_minimap.as_ref().is_some_and(|minimap| {
+            minimap.kind == kind
+                && minimap.path() == path
+                && minimap.cell_count() == cell_count
+                && Some(minimap.file_len()) == std::fs::metadata(&path).ok().map(|m| m.len())
+        });
+        if !up_to_date {
+            self.buffr_collection.shown_minimap = Minimap::compute(&path, kind, cell_count).ok();
+        }
+    }
+
+    /// Panels drawn over the rows, right of the ASCII column.
+    fn draw_overlays(&mut self, stdout: &mut impl Write) -> Result<()> {
+        self.draw_outline(stdout)?;
+        self.draw_minimap(stdout)?;
+        self.draw_help(stdout)
+    }
+
+    /// Draws the key bindings of the mode asked for in a box over the middle
+    /// of the screen, while in help mode.
+    fn draw_help(&self, stdout: &mut impl Write) -> Result<()> {
+        let help = match self.mode.as_any().downcast_ref::<Help>() {
+            Some(help) => help,
+            None => return Ok(()),
+        };
+        let theme = self.colorizer.theme();
+        let width = cmp::min(self.size.0 as usize, MAX_HELP_WIDTH);
+        let column = (self.size.0 as usize - width) / 2;
+        let bindings = help.bindings();
+        let rows = cmp::min(bindings.len(), help::visible_lines(self.size.1 as usize));
+        let scroll = cmp::min(help.scroll, bindings.len() - rows);
+        let keys_width = bindings
+            .iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .map_or(0, |max| cmp::min(max, width / 3));
+
+        let title = format!(" help: {} mode", help.topic);
+        let position = format!("{}-{}/{} ", scroll + 1, scroll + rows, bindings.len());
+        queue!(
+            stdout,
+            cursor::MoveTo(column as u16, 0),
+            style::PrintStyledContent(
+                theme
+                    .powerline_name
+                    .apply(fit(&title, width.saturating_sub(position.len())) + &position)
+            )
+        )?;
+        for (row, (keys, description)) in bindings[scroll..scroll + rows].iter().enumerate() {
+            let keys = fit(&format!(" {}", keys), keys_width + 1);
+            let description = fit(&format!("  {}", description), width.saturating_sub(keys_width + 1));
+            queue!(
+                stdout,
+                cursor::MoveTo(column as u16, row as u16 + 1),
+                style::PrintStyledContent(theme.properties_label.apply(keys)),
+                style::PrintStyledContent(theme.properties_value.apply(description))
+            )?;
+        }
+        queue!(
+            stdout,
+            cursor::MoveTo(column as u16, rows as u16 + 1),
+            style::PrintStyledContent(theme.info.apply(fit(HELP_FOOTER, width)))
+        )?;
+        Ok(())
+    }
+
+    /// Draws the current buffer's outline as a tree, keeping its cursor line
+    /// scrolled into view.
+    fn draw_outline(&mut self, stdout: &mut impl Write) -> Result<()> {
+        if !self.outline_shown() {
+            return Ok(());
+        }
+        let column = self.row_layout().side_panel_column();
+        let width = (self.minimap_column() as usize).saturating_sub(column);
+        let height = (self.size.1 as usize).saturating_sub(2);
+        if width < 4 {
+            return Ok(());
+        }
+        let focused = self.mode.as_any().is::<OutlineFocus>();
+        let theme = self.colorizer.theme();
+        let outline = match self.buffr_collection.current_mut().outline.as_mut() {
+            Some(outline) => outline,
+            None => return Ok(()),
+        };
+        outline.scroll_to_cursor(height);
+
+        queue!(
+            stdout,
+            cursor::MoveTo(column as u16, 0),
+            style::PrintStyledContent(theme.powerline_name.apply(fit(&format!(" {}", outline.title), width)))
+        )?;
+        let lines = outline.visible();
+        for row in 0..height {
+            queue!(stdout, cursor::MoveTo(column as u16, row as u16 + 1))?;
+            let index = outline.scroll + row;
+            let (depth, node) = match lines.get(index) {
+                Some(line) => *line,
+                None => {
+                    queue!(stdout, style::PrintStyledContent(theme.default.apply(" ".repeat(width))))?;
+                    continue;
+                }
+            };
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => ' ',
+                (false, true) => '▾',
+                (false, false) => '▸',
+            };
+            let label = format!("{}{} {}", "  ".repeat(depth), marker, node.label);
+            let value = if node.value.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", node.value)
+            };
+            if index == outline.cursor {
+                let style = if focused { theme.active_selection } else { theme.inactive_selection };
+                queue!(stdout, style::PrintStyledContent(style.apply(fit(&(label + &value), width))))?;
+            } else {
+                let label: String = label.chars().take(width).collect();
+                let value = fit(&value, width - label.chars().count());
+                queue!(
+                    stdout,
+                    style::PrintStyledContent(theme.properties_label.apply(label)),
+                    style::PrintStyledContent(theme.properties_value.apply(value))
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn row_layout(&self) -> RowLayout {
+        RowLayout { bytes_per_line: self.bytes_per_line }
+    }
+
+    /// Where the minimap starts, or the right edge when it isn't shown.
+    fn minimap_column(&self) -> u16 {
+        match self.buffr_collection.shown_minimap {
+            Some(_) => self.size.0.saturating_sub(MINIMAP_WIDTH),
+            None => self.size.0,
+        }
+    }
+
+    /// The file offset a click on the minimap jumps to: the start of the
+    /// clicked row's cell.
+    fn minimap_offset_at(&self, column: u16, row: u16) -> Option<u64> {
+        let minimap = self.buffr_collection.shown_minimap.as_ref()?;
+        if column < self.minimap_column() || row as usize >= minimap.cell_count() {
+            return None;
+        }
+        let offset = row as u64 * minimap.cell_len();
+        if offset < minimap.file_len() {
+            Some(offset)
         } else {
-            a + 1
-        }) * self.bytes_per_line;
-        while !byte_properties.are_all_printed() {
-            self.draw_row(stdout, &[], offset, &[], None, &mut byte_properties)?;
-            offset += self.bytes_per_line;
+            None
         }
+    }
 
+    fn draw_minimap(&self, stdout: &mut impl Write) -> Result<()> {
+        let minimap = match &self.buffr_collection.shown_minimap {
+            Some(minimap) => minimap,
+            None => return Ok(()),
+        };
+        let theme = self.colorizer.theme();
+        let column = self.minimap_column();
+
+        let buf = self.buffr_collection.current();
+        let window_start = (buf.data_start_offset + self.start_offset) as u64;
+        let window_end = window_start + (self.size.1 as u64 - 1) * self.bytes_per_line as u64;
+        let first_window_cell = minimap.cell_of_offset(window_start);
+        let last_window_cell = minimap.cell_of_offset(window_end.saturating_sub(1));
+        let jump_cursor = self
+            .mode
+            .as_any()
+            .downcast_ref::<MinimapJump>()
+            .map(|mode| mode.cell);
+
+        for (row, cell) in minimap.cells().iter().enumerate() {
+            queue!(stdout, cursor::MoveTo(column, row as u16))?;
+            if (first_window_cell..=last_window_cell).contains(&row) {
+                queue!(stdout, style::PrintStyledContent(theme.minimap_window.apply('▐')))?;
+            } else {
+                queue!(stdout, style::PrintStyledContent(theme.default.apply(' ')))?;
+            }
+
+            let (glyph, cell_style) = match cell {
+                Some(stats) => (stats.glyph(minimap.kind), stats.style(minimap.kind, theme)),
+                None if row as u64 * minimap.cell_len() >= minimap.file_len() => (' ', theme.default),
+                None => ('·', theme.default),
+            };
+            let cell_style = if jump_cursor == Some(row) {
+                theme.prompt_cursor
+            } else {
+                cell_style
+            };
+            queue!(stdout, style::PrintStyledContent(cell_style.apply(glyph)))?;
+        }
         Ok(())
     }
 
     fn draw(&mut self, stdout: &mut impl Write) -> Result<time::Duration> {
         let begin = time::Instant::now();
+        self.sync_layout();
 
         // Try to load more data if needed
         self.ensure_visible_data()?;
@@ -1336,7 +1775,9 @@ impl HexView {
             .slice_to_cow(start_index..end_index);
 
         let max_bytes = visible_bytes_cow.len();
-        let mark_commands = self.mark_commands(visible_bytes.clone());
+        let other = self.diff_other_bytes();
+        let differing = differing_offsets(start_index, &visible_bytes_cow, other.as_deref());
+        let mark_commands = self.mark_commands(visible_bytes.clone(), &differing);
 
         let current_bytes = self
             .buffr_collection
@@ -1371,27 +1812,24 @@ impl HexView {
                 } else {
                     None
                 },
-                &mut byte_properties,
+                match &other {
+                    Some(other) => SidePanel::Diff(self.row_of(other, normalized_i)),
+                    None if self.outline_shown() => SidePanel::Outline,
+                    None => SidePanel::Properties(&mut byte_properties),
+                },
             )?;
         }
 
-        let a = end_index / self.bytes_per_line;
-        let mut offset = (if end_index % self.bytes_per_line == 0 {
-            a
-        } else {
-            a + 1
-        }) * self.bytes_per_line;
-        while !byte_properties.are_all_printed() {
-            self.draw_row(stdout, &[], offset, &[], None, &mut byte_properties)?;
-            offset += self.bytes_per_line;
-        }
+        self.draw_trailing_rows(stdout, end_index, &mut byte_properties, other.as_deref())?;
 
         let new_full_rows =
-            (end_index - start_index + self.bytes_per_line - 1) / self.bytes_per_line;
+            (end_index - start_index).div_ceil(self.bytes_per_line);
         if new_full_rows != self.last_visible_rows.get() {
             self.last_visible_rows.set(new_full_rows);
         }
 
+        self.sync_minimap();
+        self.draw_overlays(stdout)?;
         self.draw_statusline(stdout)?;
 
         Ok(begin.elapsed())
@@ -1406,53 +1844,139 @@ impl HexView {
             }
             Event::Key(KeyEvent { code, modifiers }) => match (code, modifiers) {
                 (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                    
-                    // Configurable chunk size (e.g., 368 bytes)
-                    // default 23 rows x 16 bytes is 368)
-                    let (_, height) = terminal::size().unwrap_or((80, 23));
-                    let chunk_size = (height as usize - 1) * 16;  // Subtract status line
-                    let _ = self.buffr_collection.current_mut().load_next_chunk(chunk_size)?;
-                             
-                    // In view or wherever scrolling occurs
-                    // let _ = self.buffr_collection.current_mut().load_next_chunk(chunk_size)?;
-                    let current_buffer = self.buffr_collection.current_mut();
-                    let max_bytes = current_buffer.data.len();
-                    let bytes_per_line = self.bytes_per_line;
-
-                    current_buffer.map_selections(|region| {
-                        vec![region.simple_move(Direction::Down, bytes_per_line, max_bytes, 1)]
-                    });
-
-                    self.scroll_down(stdout, 1)?;
-                    self.draw(stdout)?;
-                    Ok(())
+                    self.scroll_with_selections(stdout, Direction::Down, 1)
                 }
                 (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
-                    // let _ = self.buffr_collection.current_mut().load_next_chunk(chunk_size)?;
-                    let current_buffer = self.buffr_collection.current_mut();
-                    let max_bytes = current_buffer.data.len();
-                    let bytes_per_line = self.bytes_per_line;
-
-                    current_buffer.map_selections(|region| {
-                        vec![region.simple_move(Direction::Up, bytes_per_line, max_bytes, 1)]
-                    });
-
-                    self.scroll_up(stdout, 1)?;
-                    self.draw(stdout)?;
-                    Ok(())
+                    self.scroll_with_selections(stdout, Direction::Up, 1)
                 }
                 _ => Ok(()),
             },
+            Event::Mouse(mouse) if self.mode.as_any().is::<modes::normal::Normal>() => {
+                self.handle_mouse(stdout, mouse)
+            }
             _ => Ok(()),
         }
     }
+
+    /// Scrolls by `lines`, moving the selections along so they stay on screen.
+    fn scroll_with_selections(
+        &mut self,
+        stdout: &mut impl Write,
+        direction: Direction,
+        lines: usize,
+    ) -> Result<()> {
+        if direction == Direction::Down {
+            let chunk_size = self.chunk_size();
+            let _ = self.buffr_collection.current_mut().load_next_chunk(chunk_size)?;
+        }
+
+        let current_buffer = self.buffr_collection.current_mut();
+        let max_bytes = current_buffer.data.len();
+        let bytes_per_line = self.bytes_per_line;
+
+        current_buffer.map_selections(|region| {
+            vec![region.simple_move(direction, bytes_per_line, max_bytes, lines)]
+        });
+
+        if direction == Direction::Down {
+            self.scroll_down(stdout, lines)?;
+        } else {
+            self.scroll_up(stdout, lines)?;
+        }
+        self.draw(stdout)?;
+        Ok(())
+    }
+
+    /// The byte under a screen position in the hex or ASCII pane, as an offset
+    /// in the loaded window.
+    fn offset_at(&self, column: u16, row: u16) -> Option<usize> {
+        if row + 1 >= self.size.1 {
+            return None;
+        }
+        let index = self.row_layout().index_at(column as usize)?;
+        let offset = self.start_offset + row as usize * self.bytes_per_line + index;
+        Some(cmp::min(offset, self.buffr_collection.current().data.len()))
+    }
+
+    /// Clicks place the main cursor (ctrl-click adds a cursor) and drags
+    /// extend the main selection; clicks on the minimap jump to the clicked
+    /// cell. The wheel scrolls.
+    fn handle_mouse(&mut self, stdout: &mut impl Write, mouse: MouseEvent) -> Result<()> {
+        let dirty = match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                return self.scroll_with_selections(stdout, Direction::Down, MOUSE_SCROLL_LINES)
+            }
+            MouseEventKind::ScrollUp => {
+                return self.scroll_with_selections(stdout, Direction::Up, MOUSE_SCROLL_LINES)
+            }
+            MouseEventKind::Down(button) if mouse.row + 1 == self.size.1 => {
+                return self.click_statusline(stdout, mouse.column, button)
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(offset) = self.minimap_offset_at(mouse.column, mouse.row) {
+                    let bytes_per_line = self.bytes_per_line;
+                    match self.buffr_collection.current_mut().jump_to_file_offset(
+                        offset as usize,
+                        jump_window_len(bytes_per_line),
+                        bytes_per_line,
+                    ) {
+                        Ok(dirty) => return self.transition_dirty_bytes(stdout, dirty),
+                        Err(info) => {
+                            self.info = Some(info);
+                            return Ok(());
+                        }
+                    }
+                }
+                let offset = match self.offset_at(mouse.column, mouse.row) {
+                    Some(offset) => offset,
+                    None => return Ok(()),
+                };
+                let current_buffer = self.buffr_collection.current_mut();
+                if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                    current_buffer.add_cursor(offset)
+                } else {
+                    current_buffer.select_only(offset)
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                match self.offset_at(mouse.column, mouse.row) {
+                    Some(offset) => self.buffr_collection.current_mut().extend_main_to(offset),
+                    None => return Ok(()),
+                }
+            }
+            _ => return Ok(()),
+        };
+        self.transition_dirty_bytes(stdout, dirty)
+    }
+
+    /// Left clicks on the buffer name or selection count go to the next
+    /// buffer or selection, right clicks to the previous one.
+    fn click_statusline(&mut self, stdout: &mut impl Write, column: u16, button: MouseButton) -> Result<()> {
+        let forward = button != MouseButton::Right;
+        let mut start = (self.size.0 as usize).saturating_sub(self.calculate_powerline_length());
+        let segment = self.powerline_segments().iter().position(|(text, _)| {
+            let end = start + 1 + text.chars().count();
+            let hit = (start..end).contains(&(column as usize));
+            start = end;
+            hit
+        });
+        let dirty = match segment {
+            Some(STATUS_NAME_SEGMENT) => {
+                self.buffr_collection.cycle_current_buffer(forward);
+                DirtyBytes::ChangeLength
+            }
+            Some(STATUS_SELECTIONS_SEGMENT) if forward => {
+                self.buffr_collection.current_mut().select_next(1)
+            }
+            Some(STATUS_SELECTIONS_SEGMENT) => self.buffr_collection.current_mut().select_prev(1),
+            _ => return Ok(()),
+        };
+        self.transition_dirty_bytes(stdout, dirty)
+    }
     
 
     fn scroll_down(&mut self, stdout: &mut impl Write, line_count: usize) -> Result<()> {
-        // Configurable chunk size (e.g., 368 bytes)
-        // default 23 rows x 16 bytes is 368)
-        let (_, height) = terminal::size().unwrap_or((80, 23));
-        let chunk_size = (height as usize - 1) * 16;  // Subtract status line
+        let chunk_size = self.chunk_size();
             
         debug_log("\n=== Scroll Down Event ===");
         debug_log(&format!("scroll_down -> chunk_size -> {}", chunk_size));
@@ -1470,12 +1994,12 @@ impl HexView {
         };
         debug_log(&format!("Current buffer size: {}", current_size));
         
-        let next_position = self.start_offset + (line_count * 16);
+        let next_position = self.start_offset + (line_count * self.bytes_per_line);
         debug_log(&format!("Next position would be: {}", next_position));
         
         // Calculate how many rows we can display
         let visible_rows = (self.size.1 - 1) as usize;  // -1 for status line
-        let needed_bytes = next_position + (visible_rows * 16);
+        let needed_bytes = next_position + (visible_rows * self.bytes_per_line);
         debug_log(&format!("Need bytes up to: {}", needed_bytes));
         
         // // If need more data for full display
@@ -1520,9 +2044,7 @@ impl HexView {
                     // }
                     // Check if we should trim (after releasing the borrow)
                     let should_trim = self.should_trim_buffer();
-                    if self.should_trim_buffer() {
-                        let (_, height) = terminal::size().unwrap_or((80, 23));
-                        let chunk_size = (height as usize - 1) * 16;
+                    if should_trim {
                         if chunk_size > 0 {  // Only trim if we have a valid chunk size
                             debug_log(&format!("Trimming with chunk_size: {}", chunk_size));
                             self.trim_buffer_top(chunk_size);
@@ -1595,18 +2117,18 @@ impl HexView {
             let mut invalidated_rows: BTreeSet<u16> =
                 (self.size.1 - 1 - line_count as u16..=self.size.1 - 2).collect();
             invalidated_rows.extend(0..BytePropertiesFormatter::height() as u16);
-            let _ = self.draw_rows(stdout, &invalidated_rows)?;
+            self.draw_rows(stdout, &invalidated_rows)?;
             Ok(())
         }
     }
 
     fn scroll_up(&mut self, stdout: &mut impl Write, line_count: usize) -> Result<()> {
-        if self.start_offset < 0x10 * line_count {
+        if self.start_offset < self.bytes_per_line * line_count {
             // we already at the top the file
             return Ok(());
         }
 
-        self.start_offset -= 0x10 * line_count;
+        self.start_offset -= self.bytes_per_line * line_count;
 
         if line_count > (self.size.1 - 1) as usize {
             self.draw(stdout)?;
@@ -1643,7 +2165,7 @@ impl HexView {
         if delta < 0 {
             let line_delta =
                 (delta - self.bytes_per_line as isize + 1) / self.bytes_per_line as isize;
-            self.scroll_up(stdout, line_delta.abs() as usize)
+            self.scroll_up(stdout, line_delta.unsigned_abs())
         } else {
             let line_delta =
                 (delta + self.bytes_per_line as isize - 1) / self.bytes_per_line as isize;
@@ -1652,6 +2174,7 @@ impl HexView {
     }
 
     fn maybe_update_offset_and_draw(&mut self, stdout: &mut impl Write) -> Result<()> {
+        self.sync_layout();
         let main_cursor_offset = self.buffr_collection.current().selection.main_cursor_offset();
         let visible_bytes = self.visible_bytes();
         if main_cursor_offset < visible_bytes.start {
@@ -1717,11 +2240,87 @@ impl HexView {
                 self.info = Some(info);
                 Ok(())
             }
+            ModeTransition::ModeDirtyBytesAndInfo(mode, dirty_bytes, info) => {
+                self.mode = mode;
+                self.transition_dirty_bytes(stdout, dirty_bytes)?;
+                self.info = Some(info);
+                Ok(())
+            }
+        }
+    }
+
+    /// Feeds an event to the mode, falling back to the default handling.
+    fn handle_event(&mut self, stdout: &mut impl Write, evt: Event) -> Result<()> {
+        let transition = self
+            .mode
+            .transition(&evt, &mut self.buffr_collection, self.bytes_per_line);
+        if let Some(transition) = transition {
+            self.transition(stdout, transition)
+        } else {
+            self.handle_event_default(stdout, evt)
+        }
+    }
+
+    /// In normal mode without a count or register, where changes start.
+    fn normal_at_rest(&self) -> bool {
+        self.mode.as_any().downcast_ref::<modes::normal::Normal>() == Some(&modes::normal::Normal::new())
+    }
+
+    /// Replays the macro the last event asked for, if any, and tells whether
+    /// there was one. Its changes to each buffer are undone in one step.
+    fn replay_macros(&mut self, stdout: &mut impl Write, depth: usize) -> Result<bool> {
+        let (name, count) = match self.buffr_collection.macros.take_replay() {
+            Some(replay) => replay,
+            None => return Ok(false),
+        };
+        if depth >= MAX_REPLAY_DEPTH {
+            self.info = Some("macros replay each other too deeply".into());
+            return Ok(true);
+        }
+        let events = self.buffr_collection.macros.get(name).cloned().unwrap_or_default();
+        for buf in self.buffr_collection.iter_mut() {
+            buf.begin_undo_group();
+        }
+        let mut result = Ok(true);
+        'replay: for _ in 0..count {
+            for evt in &events {
+                if !self.mode.takes_input() {
+                    break 'replay;
+                }
+                result = self
+                    .handle_event(stdout, *evt)
+                    .and_then(|()| self.replay_macros(stdout, depth + 1))
+                    .map(|_| true);
+                if result.is_err() {
+                    break 'replay;
+                }
+            }
+        }
+        for buf in self.buffr_collection.iter_mut() {
+            buf.end_undo_group();
+        }
+        result
+    }
+
+    /// Shows the outcome of `:diffsummary` once its thread is done.
+    fn finish_diff_summary(&mut self, stdout: &mut impl Write) -> Result<()> {
+        let result = match &self.buffr_collection.diff_summary {
+            Some(job) => job.take_result(),
+            None => return Ok(()),
+        };
+        if let Some(result) = result {
+            self.buffr_collection.diff_summary = None;
+            self.info = Some(match result {
+                Ok(summary) => summary.to_string(),
+                Err(e) => format!("compare failed: {}", e),
+            });
+            self.draw_statusline(stdout)?;
         }
+        Ok(())
     }
 
     pub fn run_event_loop(mut self, stdout: &mut impl Write) -> Result<()> {
-        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide, EnableMouseCapture)?;
 
         self.last_draw_time = self.draw(stdout)?;
         terminal::enable_raw_mode()?;
@@ -1731,20 +2330,40 @@ impl HexView {
             if !self.mode.takes_input() {
                 break;
             }
+            // While the minimap or a diff summary is being computed, wake up
+            // regularly to show progress
+            let computing = self.buffr_collection.shown_minimap.as_ref().is_some_and(|minimap| minimap.is_computing())
+                || self.buffr_collection.diff_summary.is_some();
+            if computing && !event::poll(time::Duration::from_millis(100))? {
+                if self.buffr_collection.shown_minimap.as_ref().is_some_and(|minimap| minimap.take_updated()) {
+                    self.draw_minimap(stdout)?;
+                }
+                self.finish_diff_summary(stdout)?;
+                stdout.flush()?;
+                continue;
+            }
             let evt = event::read()?;
-            let transition = self
-                .mode
-                .transition(&evt, &mut self.buffr_collection, self.bytes_per_line);
-            if let Some(transition) = transition {
-                self.transition(stdout, transition)?;
-            } else {
-                self.handle_event_default(stdout, evt)?;
+            if let Event::Mouse(MouseEvent { kind: MouseEventKind::Moved, .. }) = evt {
+                continue;
+            }
+            let at_rest = self.normal_at_rest();
+            self.buffr_collection.macros.record(evt);
+            self.buffr_collection.macros.track_change(evt, at_rest);
+            self.handle_event(stdout, evt)?;
+            let replayed = self.replay_macros(stdout, 0)?;
+            if let Some(escape) = self.buffr_collection.clipboard.take_escape() {
+                stdout.write_all(escape.as_bytes())?;
+            }
+            if self.normal_at_rest() {
+                let changed = self.buffr_collection.take_changed();
+                self.buffr_collection.macros.finish_change(changed, replayed);
             }
 
+            self.draw_overlays(stdout)?;
             self.draw_statusline(stdout)?;
             stdout.flush()?;
         }
-        execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+        execute!(stdout, DisableMouseCapture, cursor::Show, terminal::LeaveAlternateScreen)?;
         terminal::disable_raw_mode()?;
         Ok(())
     }
@@ -1,5 +1,11 @@
-use super::byte_rope::{Rope, RopeDelta};
+use super::byte_rope::{Rope, RopeDelta, RopeDeltaElement};
 use super::selection::Selection;
+use super::encoding::parse_hex;
+use super::state;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use xi_rope::multiset::Subset;
 
 #[derive(Clone)]
@@ -71,12 +77,70 @@ impl Action {
     }
 }
 
-#[derive(Clone, Default)]
+/// Undo files longer than this aren't written, so the history of a large
+/// paste only lasts the session.
+const MAX_UNDO_FILE_LEN: usize = 64 << 20;
+
+/// A history saved with a file, for the edits made to a window of it: `len`
+/// bytes starting at `start`, as they were when the file was written.
+pub struct SavedHistory {
+    pub window_start: usize,
+    pub window_len: usize,
+    pub history: History,
+}
+
+/// A change between a state of the undo tree and its parent.
+#[derive(Clone)]
+struct Change {
+    /// Turns this state's text back into the parent's
+    undo: Action,
+    /// Turns the parent's text into this state's; only known once the change
+    /// has been undone, as it's computed from this state's text
+    redo: Option<Action>,
+    /// Selections to restore on undo and on redo
+    selection_before: Selection,
+    selection_after: Selection,
+}
+
+#[derive(Clone)]
+struct State {
+    parent: usize,
+    /// `None` for the root, the text before any change
+    change: Option<Change>,
+    /// Child that redo goes to: the last one made or left by undo
+    redo_child: Option<usize>,
+    time: SystemTime,
+}
+
+/// Every state the buffer has been in, as a tree: edits after an undo start a
+/// new branch instead of dropping the undone ones. States are numbered in the
+/// order they were made, the root being 0.
+#[derive(Clone)]
 pub struct History {
     partial: Option<(Action, Selection)>,
 
-    undo: Vec<(Action, Selection)>,
-    redo: Vec<(Action, Selection)>,
+    states: Vec<State>,
+    current: usize,
+
+    /// How many `begin_group` calls are open. Until it drops back to 0, every
+    /// change is chained into the partial action, so it's undone in one step.
+    group_depth: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            partial: None,
+            states: vec![State {
+                parent: 0,
+                change: None,
+                redo_child: None,
+                time: SystemTime::now(),
+            }],
+            current: 0,
+            group_depth: 0,
+        }
+    }
 }
 
 impl History {
@@ -85,9 +149,10 @@ impl History {
     }
 
     pub fn perform_final(&mut self, current_rope: &Rope, delta: RopeDelta, selection: Selection) {
-        self.undo
-            .push((Action::from_delta(delta).invert(current_rope), selection));
-        self.redo = vec![];
+        if self.group_depth > 0 {
+            return self.perform_partial(current_rope, delta, &selection);
+        }
+        self.push_state(Action::from_delta(delta).invert(current_rope), selection);
     }
 
     pub fn perform_partial(
@@ -111,41 +176,332 @@ impl History {
     }
 
     pub fn commit_partial(&mut self) {
+        if self.group_depth == 0 {
+            self.push_partial();
+        }
+    }
+
+    fn push_partial(&mut self) {
         if let Some((partial, selection)) = self.partial.take() {
-            self.undo.push((partial, selection));
-            self.redo = vec![];
+            self.push_state(partial, selection);
         }
     }
 
+    /// Adds a child of the current state and moves to it.
+    fn push_state(&mut self, undo: Action, selection: Selection) {
+        let state = self.states.len();
+        self.states.push(State {
+            parent: self.current,
+            change: Some(Change {
+                undo,
+                redo: None,
+                selection_before: selection.clone(),
+                selection_after: selection,
+            }),
+            redo_child: None,
+            time: SystemTime::now(),
+        });
+        self.states[self.current].redo_child = Some(state);
+        self.current = state;
+    }
+
+    pub fn begin_group(&mut self) {
+        self.commit_partial();
+        self.group_depth += 1;
+    }
+
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        self.commit_partial();
+    }
+
     pub fn undo(
         &mut self,
         current_rope: &Rope,
         selection: Selection,
     ) -> Option<(RopeDelta, Selection)> {
-        match self.undo.pop() {
-            Some((action, old_selection)) => {
-                self.redo.push((action.invert(current_rope), selection));
-                let undo_delta = action.delta;
-                Some((undo_delta, old_selection))
+        // An undo in the middle of a group ends the group's undo step there
+        self.push_partial();
+        let state = self.current;
+        let parent = self.states[state].parent;
+        let change = self.states[state].change.as_mut()?;
+        if change.redo.is_none() {
+            change.redo = Some(change.undo.invert(current_rope));
+        }
+        change.selection_after = selection;
+        let undo = (change.undo.delta.clone(), change.selection_before.clone());
+        self.states[parent].redo_child = Some(state);
+        self.current = parent;
+        Some(undo)
+    }
+
+    pub fn redo(&mut self) -> Option<(RopeDelta, Selection)> {
+        self.push_partial();
+        let child = self.states[self.current].redo_child?;
+        let change = self.states[child].change.as_ref()?;
+        let redo = (change.redo.as_ref()?.delta.clone(), change.selection_after.clone());
+        self.current = child;
+        Some(redo)
+    }
+
+    /// The number of the current state.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The number of states, including the root.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn time(&self, state: usize) -> SystemTime {
+        self.states[state].time
+    }
+
+    /// The last state made at or before `time`, or the root.
+    pub fn state_at(&self, time: SystemTime) -> usize {
+        (0..self.states.len())
+            .rev()
+            .find(|&state| self.states[state].time <= time)
+            .unwrap_or(0)
+    }
+
+    fn is_ancestor(&self, ancestor: usize, mut state: usize) -> bool {
+        while state != ancestor {
+            if state == 0 {
+                return false;
             }
-            None => None,
+            state = self.states[state].parent;
         }
+        true
     }
 
-    pub fn redo(
+    /// Undoes or redoes one change on the way from the current state to
+    /// `target`, or gives `None` once there.
+    pub fn step_towards(
         &mut self,
+        target: usize,
         current_rope: &Rope,
         selection: Selection,
     ) -> Option<(RopeDelta, Selection)> {
-        match self.redo.pop() {
-            Some((action, old_selection)) => {
-                self.undo.push((action.invert(current_rope), selection));
-                let redo_delta = action.delta;
-                Some((redo_delta, old_selection))
+        if target == self.current || target >= self.states.len() {
+            return None;
+        }
+        if !self.is_ancestor(self.current, target) {
+            return self.undo(current_rope, selection);
+        }
+        let mut child = target;
+        while self.states[child].parent != self.current {
+            child = self.states[child].parent;
+        }
+        self.states[self.current].redo_child = Some(child);
+        self.redo()
+    }
+
+    /// Branch tips: states without children, with how many changes lead to
+    /// them.
+    pub fn leaves(&self) -> Vec<(usize, usize)> {
+        let mut has_children = vec![false; self.states.len()];
+        for state in &self.states[1..] {
+            has_children[state.parent] = true;
+        }
+        (1..self.states.len())
+            .filter(|&state| !has_children[state])
+            .map(|state| {
+                let mut depth = 0;
+                let mut ancestor = state;
+                while ancestor != 0 {
+                    ancestor = self.states[ancestor].parent;
+                    depth += 1;
+                }
+                (state, depth)
+            })
+            .collect()
+    }
+
+    /// Keeps the history in the state directory for `path`, which was just
+    /// written, as edits to its `window_len` bytes from `window_start`. The
+    /// change being made, if any, isn't kept.
+    pub fn save(&self, path: &Path, window_start: usize, window_len: usize) -> io::Result<()> {
+        let window = window_start as u64..(window_start + window_len) as u64;
+        let fingerprint = state::fingerprint(path, window)?;
+        let text = self.serialize(fingerprint, window_start, window_len, MAX_UNDO_FILE_LEN);
+        state::write_state(path, "undo", text.as_deref().unwrap_or(""))
+    }
+
+    /// The history saved for `path`, if the file hasn't changed since.
+    pub fn load(path: &Path) -> Option<SavedHistory> {
+        let text = state::read_state(path, "undo")?;
+        let (fingerprint, saved) = History::parse(&text)?;
+        let window = saved.window_start as u64..(saved.window_start + saved.window_len) as u64;
+        if state::fingerprint(path, window).ok()? != fingerprint {
+            return None;
+        }
+        Some(saved)
+    }
+
+    /// A header, then one line per state: its parent, time, redo child, the
+    /// undo and redo deltas and the selections before and after the change,
+    /// `-` standing for what's missing. `None` once longer than `max_len`.
+    fn serialize(
+        &self,
+        fingerprint: u64,
+        window_start: usize,
+        window_len: usize,
+        max_len: usize,
+    ) -> Option<String> {
+        let mut text = format!(
+            "tofu-undo 1\nfile {:016x}\nwindow {} {}\ncurrent {}\n",
+            fingerprint, window_start, window_len, self.current
+        );
+        for state in &self.states {
+            let secs = state.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let redo_child = state.redo_child.map_or("-".into(), |child| child.to_string());
+            text += &format!("{} {} {}", state.parent, secs, redo_child);
+            match &state.change {
+                Some(change) => {
+                    let redo = change.redo.as_ref();
+                    // Inserted bytes take two hex digits, so large ones are
+                    // refused before they're formatted
+                    let inserted = inserted_len(&change.undo.delta)
+                        + redo.map_or(0, |redo| inserted_len(&redo.delta));
+                    if text.len() + 2 * inserted > max_len {
+                        return None;
+                    }
+                    let redo = redo.map_or("-".into(), |redo| format_delta(&redo.delta));
+                    text += &format!(
+                        " {} {} {} {}\n",
+                        format_delta(&change.undo.delta),
+                        redo,
+                        change.selection_before,
+                        change.selection_after
+                    );
+                }
+                None => text += " - - - -\n",
+            }
+            if text.len() > max_len {
+                return None;
             }
-            None => None,
         }
+        Some(text)
     }
+
+    fn parse(text: &str) -> Option<(u64, SavedHistory)> {
+        let mut lines = text.lines();
+        if lines.next()? != "tofu-undo 1" {
+            return None;
+        }
+        let fingerprint = u64::from_str_radix(lines.next()?.strip_prefix("file ")?, 16).ok()?;
+        let mut header = |key: &str| -> Option<Vec<usize>> {
+            let line = lines.next()?.strip_prefix(key)?;
+            line.split_whitespace().map(|x| x.parse().ok()).collect()
+        };
+        let window = header("window ")?;
+        let current = header("current ")?;
+        let (&[window_start, window_len], &[current]) = (&window[..], &current[..]) else {
+            return None;
+        };
+        fn optional(field: &str) -> Option<&str> {
+            (field != "-").then_some(field)
+        }
+        let mut states = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let [parent, secs, redo_child, undo, redo, before, after] = fields[..] else {
+                return None;
+            };
+            let redo_child = match optional(redo_child) {
+                Some(child) => Some(child.parse().ok()?),
+                None => None,
+            };
+            let change = match optional(undo) {
+                Some(undo) => Some(Change {
+                    undo: Action::from_delta(parse_delta(undo)?),
+                    redo: match optional(redo) {
+                        Some(redo) => Some(Action::from_delta(parse_delta(redo)?)),
+                        None => None,
+                    },
+                    selection_before: before.parse().ok()?,
+                    selection_after: after.parse().ok()?,
+                }),
+                None => None,
+            };
+            states.push(State {
+                parent: parent.parse().ok()?,
+                change,
+                redo_child,
+                time: UNIX_EPOCH + Duration::from_secs(secs.parse().ok()?),
+            });
+        }
+        let len = states.len();
+        let valid = current < len
+            && states.iter().enumerate().all(|(i, state)| {
+                (i == 0) == state.change.is_none()
+                    && (i == 0 || state.parent < i)
+                    && state.redo_child.is_none_or(|child| child < len && child > i)
+            });
+        if !valid {
+            return None;
+        }
+        let history = History {
+            partial: None,
+            states,
+            current,
+            group_depth: 0,
+        };
+        Some((fingerprint, SavedHistory { window_start, window_len, history }))
+    }
+}
+
+/// Elements of `delta` after its base length: `<start>-<end>` for copied
+/// ranges and `+<hex>` for inserted bytes, comma separated.
+fn inserted_len(delta: &RopeDelta) -> usize {
+    delta
+        .els
+        .iter()
+        .map(|el| match el {
+            RopeDeltaElement::Copy(..) => 0,
+            RopeDeltaElement::Insert(node) => node.len(),
+        })
+        .sum()
+}
+
+fn format_delta(delta: &RopeDelta) -> String {
+    let els = delta
+        .els
+        .iter()
+        .map(|el| match el {
+            RopeDeltaElement::Copy(start, end) => format!("{}-{}", start, end),
+            RopeDeltaElement::Insert(node) => {
+                let bytes = Rope(node.clone()).slice_to_cow(..).into_owned();
+                let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("+{}", hex)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{}:{}", delta.base_len, els)
+}
+
+fn parse_delta(text: &str) -> Option<RopeDelta> {
+    let (base_len, els) = text.split_once(':')?;
+    let base_len: usize = base_len.parse().ok()?;
+    let els = els
+        .split(',')
+        .filter(|el| !el.is_empty())
+        .map(|el| match el.strip_prefix('+') {
+            Some(hex) => {
+                let bytes = parse_hex(hex).ok()?;
+                Some(RopeDeltaElement::Insert(Rope::from(bytes).into_node()))
+            }
+            None => {
+                let (start, end) = el.split_once('-')?;
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                (start <= end && end <= base_len).then_some(RopeDeltaElement::Copy(start, end))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(RopeDelta { els, base_len })
 }
 
 #[cfg(test)]
@@ -317,4 +673,87 @@ mod test {
         let chain_final_rope = base_rope.apply_delta(&chained_delta.delta);
         assert_eq!(&chain_final_rope.slice_to_cow(..), &vec![0, 5, 6, 1, 2, 3]);
     }
+
+    #[test]
+    fn test_nested_group_is_one_undo() {
+        let mut history = History::new();
+        let mut rope: Rope = vec![0, 1, 2].into();
+        let edit = |history: &mut History, rope: &mut Rope, at: usize, byte: u8| {
+            let mut delta_builder = DeltaBuilder::new(rope.len());
+            delta_builder.replace(at..at + 1, Into::<Rope>::into(vec![byte]).into_node());
+            let delta = delta_builder.build();
+            history.perform_final(rope, delta.clone(), Selection::new());
+            *rope = rope.apply_delta(&delta);
+        };
+        edit(&mut history, &mut rope, 0, 5);
+        // A replay that replays another macro
+        history.begin_group();
+        edit(&mut history, &mut rope, 1, 6);
+        history.begin_group();
+        edit(&mut history, &mut rope, 2, 7);
+        history.end_group();
+        edit(&mut history, &mut rope, 0, 8);
+        history.end_group();
+        assert_eq!(&rope.slice_to_cow(..), &vec![8, 6, 7]);
+
+        let (delta, _) = history.undo(&rope, Selection::new()).unwrap();
+        rope = rope.apply_delta(&delta);
+        assert_eq!(&rope.slice_to_cow(..), &vec![5, 1, 2]);
+        let (delta, _) = history.redo().unwrap();
+        rope = rope.apply_delta(&delta);
+        assert_eq!(&rope.slice_to_cow(..), &vec![8, 6, 7]);
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn test_undo_tree() {
+        let replace = |rope: &Rope, byte: u8| {
+            let mut delta_builder = DeltaBuilder::new(rope.len());
+            delta_builder.replace(0..1, Into::<Rope>::into(vec![byte]).into_node());
+            delta_builder.build()
+        };
+        let mut history = History::new();
+        let mut rope: Rope = vec![0, 1].into();
+
+        let delta = replace(&rope, 5);
+        history.perform_final(&rope, delta.clone(), Selection::new());
+        rope = rope.apply_delta(&delta);
+        let (delta, _) = history.undo(&rope, Selection::new()).unwrap();
+        rope = rope.apply_delta(&delta);
+        let delta = replace(&rope, 6);
+        history.perform_final(&rope, delta.clone(), Selection::new());
+        rope = rope.apply_delta(&delta);
+        assert_eq!(history.current(), 2);
+        assert_eq!(history.leaves(), vec![(1, 1), (2, 1)]);
+
+        while let Some((delta, _)) = history.step_towards(1, &rope, Selection::new()) {
+            rope = rope.apply_delta(&delta);
+        }
+        assert_eq!(&rope.slice_to_cow(..), &vec![5, 1]);
+        assert_eq!(history.current(), 1);
+        let (delta, _) = history.undo(&rope, Selection::new()).unwrap();
+        rope = rope.apply_delta(&delta);
+        let (delta, _) = history.redo().unwrap();
+        rope = rope.apply_delta(&delta);
+        assert_eq!(&rope.slice_to_cow(..), &vec![5, 1]);
+        assert_eq!(history.state_at(history.time(2)), 2);
+
+        let selection: Selection = "0..3,*5..5".parse().unwrap();
+        history.perform_final(&rope, replace(&rope, 7), selection.clone());
+        rope = rope.apply_delta(&replace(&rope, 7));
+        assert!(history.serialize(0xdead, 0, 2, 100).is_none());
+        let text = history.serialize(0xdead, 0, 2, MAX_UNDO_FILE_LEN).unwrap();
+        let (fingerprint, saved) = History::parse(&text).unwrap();
+        assert_eq!((fingerprint, saved.window_start, saved.window_len), (0xdead, 0, 2));
+        let mut history = saved.history;
+        assert_eq!(history.serialize(0xdead, 0, 2, MAX_UNDO_FILE_LEN), Some(text.clone()));
+        let (delta, restored) = history.undo(&rope, Selection::new()).unwrap();
+        assert_eq!(restored, selection);
+        rope = rope.apply_delta(&delta);
+        while let Some((delta, _)) = history.step_towards(2, &rope, Selection::new()) {
+            rope = rope.apply_delta(&delta);
+        }
+        assert_eq!(&rope.slice_to_cow(..), &vec![6, 1]);
+        assert!(History::parse(&text.replace("current 3", "current 9")).is_none());
+    }
 }
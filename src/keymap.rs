@@ -1,6 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crossterm::event::{Event, KeyEvent, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+/// Actions that can say what they do, for the help overlay.
+pub trait Describe {
+    fn describe(&self) -> &'static str;
+}
+
+/// One line of help: the keys bound to an action and what it does.
+pub type Binding = (String, &'static str);
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct KeyMap<T: Copy> {
@@ -22,6 +30,82 @@ impl<T: Copy> KeyMap<T> {
     }
 }
 
+impl<T: Copy + Describe> KeyMap<T> {
+    /// The bindings, with all keys of the same action on one line, ordered by
+    /// their first key.
+    pub fn bindings(&self) -> Vec<Binding> {
+        let mut by_action: BTreeMap<&'static str, Vec<KeyEvent>> = BTreeMap::new();
+        for (key, action) in &self.maps {
+            by_action.entry(action.describe()).or_default().push(*key);
+        }
+        let mut groups: Vec<_> = by_action
+            .into_iter()
+            .map(|(description, mut keys)| {
+                keys.sort_by_key(key_order);
+                (keys, description)
+            })
+            .collect();
+        groups.sort_by(|(a, _), (b, _)| key_order(&a[0]).cmp(&key_order(&b[0])));
+        groups
+            .into_iter()
+            .map(|(keys, description)| (key_list(&keys), description))
+            .collect()
+    }
+}
+
+/// Character keys first, in case-insensitive order and with modified keys after
+/// plain ones, then named keys.
+fn key_order(key: &KeyEvent) -> (bool, char, bool, KeyModifiers, String) {
+    match key.code {
+        KeyCode::Char(c) => (false, c.to_ascii_lowercase(), c.is_ascii_uppercase(), key.modifiers, String::new()),
+        _ => (true, ' ', false, key.modifiers, key_name(key)),
+    }
+}
+
+/// `h`, `alt-s`, `ctrl-n`, `Space`, `Left`...
+pub fn key_name(key: &KeyEvent) -> String {
+    let mut name = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        name += "ctrl-";
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        name += "alt-";
+    }
+    match key.code {
+        KeyCode::Char(' ') => name += "Space",
+        KeyCode::Char(c) => name.push(c),
+        code => name += &format!("{:?}", code),
+    }
+    name
+}
+
+/// Keys joined by commas, with runs of three or more consecutive plain
+/// characters written as `0-9`.
+fn key_list(keys: &[KeyEvent]) -> String {
+    let mut parts: Vec<String> = vec![];
+    let mut i = 0;
+    while i < keys.len() {
+        let mut end = i + 1;
+        if let (KeyCode::Char(first), KeyModifiers::NONE) = (keys[i].code, keys[i].modifiers) {
+            while end < keys.len()
+                && keys[end].modifiers == KeyModifiers::NONE
+                && Some(keys[end].code)
+                    == char::from_u32(first as u32 + (end - i) as u32).map(KeyCode::Char)
+            {
+                end += 1;
+            }
+        }
+        if end - i >= 3 {
+            parts.push(format!("{}-{}", key_name(&keys[i]), key_name(&keys[end - 1])));
+        } else {
+            end = i + 1;
+            parts.push(key_name(&keys[i]));
+        }
+        i = end;
+    }
+    parts.join(", ")
+}
+
 macro_rules! normalized_char {
     ($ch:expr) => {
         /*if $ch.is_ascii_uppercase() {
@@ -75,3 +159,48 @@ macro_rules! keys {
     	}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    enum Action {
+        Digit(u8),
+        Left,
+        Quit,
+    }
+
+    impl Describe for Action {
+        fn describe(&self) -> &'static str {
+            match self {
+                Action::Digit(_) => "digit",
+                Action::Left => "left",
+                Action::Quit => "quit",
+            }
+        }
+    }
+
+    #[test]
+    fn test_bindings() {
+        let map = KeyMap {
+            maps: keys!(
+                ('3' => Action::Digit(3)),
+                ('1' => Action::Digit(1)),
+                ('2' => Action::Digit(2)),
+                ('5' => Action::Digit(5)),
+                (key KeyCode::Left => Action::Left),
+                ('h' => Action::Left),
+                (ctrl 'q' => Action::Quit)
+            ),
+        };
+        assert_eq!(
+            map.bindings(),
+            [
+                ("1-3, 5".to_string(), "digit"),
+                ("h, Left".to_string(), "left"),
+                ("ctrl-q".to_string(), "quit"),
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 #![deny(clippy::all)]
 
+mod annotations;
 mod current_buffer;
 mod byte_rope;
 pub mod hex_view;
@@ -7,8 +8,27 @@ mod history;
 #[macro_use]
 mod keymap;
 mod cmd_count;
+mod byte_source;
+mod checksum;
+mod checksum_rules;
+mod clipboard;
+mod diff;
+mod digest;
+mod encoding;
+mod executable;
+mod export;
+mod fill;
+mod macros;
+mod marks;
 mod modes;
+mod outline;
 mod operations;
+mod partitions;
+mod registers;
 mod selection;
+mod state;
+mod template;
+mod transform;
+mod window_edit;
 
 pub use current_buffer::{CurrentBuffer, BuffrCollection};
@@ -0,0 +1,116 @@
+//! Recorded key events, replayed with `q<register>`.
+
+use std::collections::HashMap;
+
+use crossterm::event::Event;
+
+/// How deep macros may replay other macros, so one replaying itself stops.
+pub const MAX_REPLAY_DEPTH: usize = 16;
+
+/// Holds the keys of the last change, which `.` replays. Not a valid name for
+/// recording.
+pub const LAST_CHANGE: char = '.';
+
+/// Macros by register name. They're kept apart from the byte registers, so
+/// `"ay` and `Qa` don't overwrite each other.
+#[derive(Debug, Default)]
+pub struct Macros {
+    macros: HashMap<char, Vec<Event>>,
+    recording: Option<(char, Vec<Event>)>,
+    /// Macro and count asked for by `<count>q<register>` or `<count>.`
+    replay: Option<(char, usize)>,
+    /// Events since normal mode was last at rest, maybe the keys of a change
+    pending_change: Vec<Event>,
+}
+
+impl Macros {
+    pub fn new() -> Macros {
+        Macros::default()
+    }
+
+    /// The register being recorded into, if any.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(name, _)| *name)
+    }
+
+    pub fn start_recording(&mut self, name: char) {
+        self.recording = Some((name, vec![]));
+    }
+
+    /// Keeps what was recorded, minus the event that stopped the recording.
+    pub fn stop_recording(&mut self) {
+        if let Some((name, mut events)) = self.recording.take() {
+            events.pop();
+            self.macros.insert(name, events);
+        }
+    }
+
+    /// Adds an event read from the terminal to the recording, if any.
+    pub fn record(&mut self, event: Event) {
+        if let Some((_, events)) = &mut self.recording {
+            events.push(event);
+        }
+    }
+
+    /// Adds an event read from the terminal to the keys of the change being
+    /// made, which start over when `at_rest` (in normal mode without a count
+    /// or register).
+    pub fn track_change(&mut self, event: Event, at_rest: bool) {
+        if at_rest {
+            self.pending_change.clear();
+        }
+        self.pending_change.push(event);
+    }
+
+    /// Called when back at rest: keeps the tracked keys as `LAST_CHANGE` if
+    /// they `changed` a buffer, unless they replayed a macro or change. `.`
+    /// replays these keys rather than the edit they made.
+    pub fn finish_change(&mut self, changed: bool, replayed: bool) {
+        let events = std::mem::take(&mut self.pending_change);
+        if changed && !replayed {
+            self.macros.insert(LAST_CHANGE, events);
+        }
+    }
+
+    pub fn get(&self, name: char) -> Option<&Vec<Event>> {
+        self.macros.get(&name)
+    }
+
+    pub fn request_replay(&mut self, name: char, count: usize) {
+        self.replay = Some((name, count));
+    }
+
+    pub fn take_replay(&mut self) -> Option<(char, usize)> {
+        self.replay.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_recording() {
+        let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let mut macros = Macros::new();
+        macros.record(key('x'));
+        macros.start_recording('a');
+        assert_eq!(macros.recording(), Some('a'));
+        for c in "lyQ".chars() {
+            macros.record(key(c));
+        }
+        macros.stop_recording();
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.get('a'), Some(&vec![key('l'), key('y')]));
+
+        macros.track_change(key('r'), true);
+        macros.track_change(key('x'), false);
+        macros.finish_change(true, false);
+        macros.track_change(key('l'), true);
+        macros.finish_change(false, false);
+        macros.track_change(key('.'), true);
+        macros.finish_change(true, true);
+        assert_eq!(macros.get(LAST_CHANGE), Some(&vec![key('r'), key('x')]));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use crate::byte_rope::RopeDelta;
+use crate::state;
+use crate::window_edit::WindowEdit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    /// File offset of the marked byte
+    pub offset: usize,
+    pub label: Option<String>,
+}
+
+/// Named marks of a buffer, kept in file offsets so they stay valid when the
+/// loaded window moves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Marks {
+    marks: BTreeMap<char, Mark>,
+}
+
+impl Marks {
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_alphabetic()
+    }
+
+    pub fn set(&mut self, name: char, offset: usize, label: Option<String>) {
+        self.marks.insert(name, Mark { offset, label });
+    }
+
+    pub fn get(&self, name: char) -> Option<&Mark> {
+        self.marks.get(&name)
+    }
+
+    pub fn remove(&mut self, name: char) -> Option<Mark> {
+        self.marks.remove(&name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Mark)> {
+        self.marks.iter().map(|(name, mark)| (*name, mark))
+    }
+
+    /// Moves marks along with an edit of the loaded window, which starts at file
+    /// offset `window_start` and was `window_len` bytes long before the edit.
+    pub fn apply_delta(&mut self, delta: &RopeDelta, window_start: usize, window_len: usize) {
+        let mut edit = WindowEdit::new(delta, window_start, window_len);
+        for mark in self.marks.values_mut() {
+            mark.offset = edit.transform(mark.offset);
+        }
+    }
+
+    /// One mark per line: `<name> 0x<offset> [label]`
+    pub fn parse(text: &str) -> Marks {
+        let mut marks = Marks::default();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let mut parts = line.splitn(3, ' ');
+            let name = parts.next().and_then(|name| name.chars().next());
+            let offset = parts
+                .next()
+                .and_then(|offset| usize::from_str_radix(offset.trim_start_matches("0x"), 16).ok());
+            if let (Some(name), Some(offset)) = (name, offset) {
+                if Marks::is_valid_name(name) {
+                    let label = parts.next().map(str::to_string).filter(|l| !l.is_empty());
+                    marks.set(name, offset, label);
+                }
+            }
+        }
+        marks
+    }
+
+    pub fn serialize(&self) -> String {
+        self.iter()
+            .map(|(name, mark)| match &mark.label {
+                Some(label) => format!("{} 0x{:x} {}\n", name, mark.offset, label),
+                None => format!("{} 0x{:x}\n", name, mark.offset),
+            })
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> Marks {
+        state::read_state(path, "marks")
+            .map(|text| Marks::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        state::write_state(path, "marks", &self.serialize())
+    }
+
+    /// Single-line listing for the info line.
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "no marks".into();
+        }
+        self.iter()
+            .map(|(name, mark)| match &mark.label {
+                Some(label) => format!("{}:{:x} {}", name, mark.offset, label),
+                None => format!("{}:{:x}", name, mark.offset),
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xi_rope::{DeltaBuilder, Interval};
+
+    use crate::byte_rope::Rope;
+
+    #[test]
+    fn test_marks_roundtrip() {
+        let mut marks = Marks::default();
+        marks.set('a', 0x1f0, Some("ELF header".into()));
+        marks.set('b', 0x10, None);
+        assert_eq!(Marks::parse(&marks.serialize()), marks);
+    }
+
+    #[test]
+    fn test_marks_follow_edits() {
+        let mut marks = Marks::default();
+        marks.set('a', 0x105, None); // inside the window, after the edit
+        marks.set('b', 0x101, None); // inside the window, before the edit
+        marks.set('c', 0x50, None); // before the window
+        marks.set('d', 0x200, None); // after the window
+
+        // Window 0x100..0x110, insert 4 bytes at window offset 3
+        let mut builder = DeltaBuilder::new(0x10);
+        builder.replace(Interval::new(3, 3), Rope::from(vec![0u8; 4]).into_node());
+        marks.apply_delta(&builder.build(), 0x100, 0x10);
+
+        assert_eq!(marks.get('a').unwrap().offset, 0x109);
+        assert_eq!(marks.get('b').unwrap().offset, 0x101);
+        assert_eq!(marks.get('c').unwrap().offset, 0x50);
+        assert_eq!(marks.get('d').unwrap().offset, 0x204);
+    }
+}
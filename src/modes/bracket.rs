@@ -0,0 +1,111 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use lazy_static::lazy_static;
+
+use crate::diff;
+use crate::keymap::{Binding, Describe, KeyMap};
+use crate::modes::{
+    mode::{jump_window_len, Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::BuffrCollection;
+
+/// Waits for the target of `]` (next) or `[` (previous).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Bracket {
+    pub forward: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Difference,
+}
+
+fn default_maps() -> KeyMap<Action> {
+    KeyMap {
+        maps: keys!(('c' => Action::Difference)),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
+}
+
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Difference => "difference",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
+impl Bracket {
+    fn jump_to_difference(
+        &self,
+        buffr_collection: &mut BuffrCollection,
+        bytes_per_line: usize,
+    ) -> Result<ModeTransition, String> {
+        let current_buffer = buffr_collection.current();
+        let other = &current_buffer
+            .diff_target
+            .as_ref()
+            .ok_or("not comparing, use :diff <file>")?
+            .source;
+        let from =
+            (current_buffer.data_start_offset + current_buffer.selection.main_cursor_offset()) as u64;
+        let found = if self.forward {
+            diff::next_difference(current_buffer, other, from)
+        } else {
+            diff::prev_difference(current_buffer, other, from)
+        }
+        .map_err(|e| format!("{}", e))?;
+
+        let current_buffer = buffr_collection.current_mut();
+
+        let offset = found.ok_or("no more differences")?;
+        // Differences past the end of this buffer are shown on its last row
+        let offset = std::cmp::min(offset, current_buffer.logical_len().saturating_sub(1));
+        let dirty = current_buffer.jump_to_file_offset(
+            offset as usize,
+            jump_window_len(bytes_per_line),
+            bytes_per_line,
+        )?;
+        Ok(ModeTransition::new_mode_and_dirty(Normal::new(), dirty))
+    }
+}
+
+impl Mode for Bracket {
+    fn name(&self) -> Cow<'static, str> {
+        if self.forward {
+            "NEXT".into()
+        } else {
+            "PREV".into()
+        }
+    }
+
+    fn transition(
+        &self,
+        evt: &Event,
+        buffr_collection: &mut BuffrCollection,
+        bytes_per_line: usize,
+    ) -> Option<ModeTransition> {
+        match DEFAULT_MAPS.event_to_action(evt) {
+            Some(Action::Difference) => Some(
+                self.jump_to_difference(buffr_collection, bytes_per_line)
+                    .unwrap_or_else(|info| ModeTransition::new_mode_and_info(Normal::new(), info)),
+            ),
+            None if matches!(evt, Event::Key(_)) => Some(ModeTransition::new_mode(Normal::new())),
+            None => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,12 +1,11 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs;
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 use maplit::hashmap;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
     mode::{Mode, ModeTransition},
     normal::Normal,
@@ -16,6 +15,8 @@ use crate::BuffrCollection;
 pub struct Command {
     pub command: String,
     pub cursor: usize,
+    /// Help topic of the mode command mode was entered from, for `:help`
+    pub from: &'static str,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -43,8 +44,32 @@ fn default_maps() -> KeyMap<Action> {
 
 mod cmd {
     use super::*;
+    use std::cmp;
+    use std::fs::File;
+    use std::io::Write;
+    use std::ops::Range;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
     use crate::modes::mode::DirtyBytes;
+    use crate::checksum::{Hasher, HASH_NAMES};
+    use crate::checksum_rules::ChecksumRule;
+    use crate::clipboard::{ClipboardFormat, FORMAT_NAMES as CLIPBOARD_FORMAT_NAMES};
+    use crate::byte_source::{ByteSource, FileSource};
+    use crate::diff::SummaryJob;
+    use crate::encoding::{encode_value, TYPE_NAMES};
+    use crate::fill::Fill;
+    use crate::export::{ExportFormat, FORMAT_NAMES, MAX_EXPORT_LEN};
+    use crate::hex_view::minimap::MinimapKind;
+    use crate::hex_view::theme::parse_color;
+    use crate::marks::Marks;
+    use crate::modes::help::Help;
+    use crate::modes::outline::OutlineFocus;
     use crate::modes::quitting;
+    use crate::operations as ops;
+    use crate::outline::{parse_int, OutlineSource};
+    use crate::transform::Transform;
+    use crate::CurrentBuffer;
 
     pub fn quit(buf: &mut BuffrCollection, _: &str) -> ModeTransition {
         if buf.iter().any(|x| x.dirty && x.path.is_some()) {
@@ -61,7 +86,14 @@ mod cmd {
         ModeTransition::new_mode(quitting::Quitting {})
     }
 
+    /// `:w [file]` writes the buffer, `:w <file> <start>..<end>` only that
+    /// range of file offsets.
     pub fn write(buf: &mut BuffrCollection, filename: &str) -> ModeTransition {
+        match parse_write_args(filename) {
+            Ok(Some((target, range))) => return write_range(buf.current(), target, range),
+            Ok(None) => {}
+            Err(e) => return ModeTransition::new_mode_and_info(Normal::new(), e),
+        }
         let path = if filename.is_empty() {
             buf.current().path.as_deref()
         } else {
@@ -69,51 +101,173 @@ mod cmd {
         };
 
         if let Some(path) = path {
-            if let Err(e) = fs::write(&path, buf.current().data.slice_to_cow(..)) {
-                return ModeTransition::new_mode_and_info(
-                    Normal::new(),
-                    format!("write failed: {}", e),
-                );
-            }
-
             let owned_path = path.to_owned();
             let buf_mut = buf.current_mut();
+            let unfixed = match buf_mut.write_to(&owned_path) {
+                Ok(unfixed) => unfixed,
+                Err(e) => {
+                    return ModeTransition::new_mode_and_info(
+                        Normal::new(),
+                        format!("write failed: {}", e),
+                    )
+                }
+            };
+
             buf_mut.dirty = false;
             buf_mut.update_path_if_missing(owned_path);
-            ModeTransition::new_mode(Normal::new())
+            buf.restart_minimap();
+            match unfixed {
+                Some(e) => ModeTransition::new_mode_and_info(Normal::new(), unfixed_info(&e)),
+                None => ModeTransition::new_mode(Normal::new()),
+            }
         } else {
             ModeTransition::new_mode_and_info(Normal::new(), "current_buffer has no path".into())
         }
     }
 
-    pub fn write_all(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
-        for buf in buffr_collection.iter_mut() {
-            if let Some(path) = buf.path.as_ref() {
-                if let Err(e) = fs::write(&path, buf.data.slice_to_cow(..)) {
-                    return ModeTransition::new_mode_and_info(
-                        Normal::new(),
-                        format!("write failed: {}", e),
-                    );
+    /// The file and range of `:w <file> <start>..<end>`, `None` for a plain
+    /// `:w [file]`. A range without a file is refused rather than taken as a
+    /// file name.
+    fn parse_write_args(args: &str) -> Result<Option<(&str, Range<usize>)>, String> {
+        let args = args.trim();
+        if parse_range(args).is_ok() {
+            return Err("usage: :w <file> <start>..<end>".into());
+        }
+        Ok(args
+            .rsplit_once(' ')
+            .and_then(|(target, range)| Some((target.trim(), parse_range(range).ok()?))))
+    }
+
+    /// Whether `path` is the file the buffer was read from, which a partial
+    /// write must not truncate.
+    fn write_range(current_buffer: &CurrentBuffer, filename: &str, range: Range<usize>) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let path = Path::new(filename);
+        if current_buffer.is_own_file(path) {
+            return info("can't write part of a buffer over its own file".into());
+        }
+        let written = File::create(path).and_then(|mut file| {
+            let mut offset = range.start;
+            while offset < range.end {
+                let len = cmp::min(COPY_CHUNK_LEN, range.end - offset);
+                let bytes = current_buffer.read_at(offset as u64, len)?;
+                if bytes.is_empty() {
+                    break;
                 }
-                buf.dirty = false;
+                file.write_all(&bytes)?;
+                offset += bytes.len();
             }
+            Ok(offset - range.start)
+        });
+        match written {
+            Ok(len) => info(format!("wrote 0x{:x} bytes to {}", len, filename)),
+            Err(e) => info(format!("write failed: {}", e)),
         }
-        ModeTransition::new_mode(Normal::new())
     }
 
-    pub fn write_quit(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+    /// `:wsel <file>`: writes the selected bytes, selections back to back.
+    pub fn write_selections(buffr_collection: &mut BuffrCollection, filename: &str) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let filename = filename.trim();
+        if filename.is_empty() {
+            return info("usage: :wsel <file>".into());
+        }
+        let current_buffer = buffr_collection.current();
+        if current_buffer.is_own_file(Path::new(filename)) {
+            return info("can't write part of a buffer over its own file".into());
+        }
+        let bytes = current_buffer.selection_contents().concat();
+        match std::fs::write(filename, &bytes) {
+            Ok(()) => info(format!("wrote 0x{:x} bytes to {}", bytes.len(), filename)),
+            Err(e) => info(format!("write failed: {}", e)),
+        }
+    }
+
+    /// `:r <file> [offset [len]]`: inserts the file, or part of it, at every
+    /// caret.
+    pub fn read(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        read_file(buffr_collection, args, true)
+    }
+
+    /// `:r! <file> [offset [len]]`: overwrites the bytes at every caret with
+    /// the file, or part of it.
+    pub fn read_overwrite(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        read_file(buffr_collection, args, false)
+    }
+
+    /// The file, offset and length of `:r <file> [offset [len]]`.
+    fn parse_read_args(args: &str) -> Result<(&str, u64, Option<u64>), String> {
+        let (filename, offset, len) = match args.split_whitespace().collect::<Vec<_>>()[..] {
+            [filename] => (filename, None, None),
+            [filename, offset] => (filename, Some(offset), None),
+            [filename, offset, len] => (filename, Some(offset), Some(len)),
+            _ => return Err("usage: :r[!] <file> [offset [len]]".into()),
+        };
+        let number = |text: Option<&str>| match text {
+            Some(text) => u64::try_from(parse_int(text)?)
+                .map(Some)
+                .map_err(|_| format!("not an offset: {}", text)),
+            None => Ok(None),
+        };
+        Ok((filename, number(offset)?.unwrap_or(0), number(len)?))
+    }
+
+    fn read_file(buffr_collection: &mut BuffrCollection, args: &str, insert: bool) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let (filename, offset, len) = match parse_read_args(args) {
+            Ok(args) => args,
+            Err(e) => return info(e),
+        };
+        let source = match FileSource::open(Path::new(filename)) {
+            Ok(source) => source,
+            Err(e) => return info(format!("{}: {}", filename, e)),
+        };
+        let len = len.unwrap_or_else(|| source.len().saturating_sub(offset));
+        if len > MAX_READ_LEN as u64 {
+            return info(format!("can't read more than 0x{:x} bytes", MAX_READ_LEN));
+        }
+        match source.read_exact_at(offset, len as usize) {
+            Ok(bytes) if bytes.is_empty() => info(format!("nothing to read from {}", filename)),
+            Ok(bytes) => put_bytes(buffr_collection, bytes, insert),
+            Err(e) => info(format!("{}: {}", filename, e)),
+        }
+    }
+
+    /// Why a written buffer's checksums may still be wrong.
+    fn unfixed_info(e: &str) -> String {
+        format!("written, but a checksum wasn't fixed: {}", e)
+    }
+
+    /// Writes every buffer with a path. Returns why a checksum wasn't fixed,
+    /// if one wasn't.
+    fn write_buffers(buffr_collection: &mut BuffrCollection) -> Result<Option<String>, String> {
+        buffr_collection.restart_minimap();
+        let mut unfixed = None;
         for buf in buffr_collection.iter_mut() {
-            if let Some(path) = buf.path.as_ref() {
-                if let Err(e) = fs::write(&path, buf.data.slice_to_cow(..)) {
-                    return ModeTransition::new_mode_and_info(
-                        Normal::new(),
-                        format!("write failed: {}", e),
-                    );
-                }
+            if let Some(path) = buf.path.clone() {
+                let written = buf.write_to(&path).map_err(|e| format!("write failed: {}", e))?;
+                unfixed = unfixed.or(written);
                 buf.dirty = false;
             }
         }
-        ModeTransition::new_mode(quitting::Quitting {})
+        Ok(unfixed)
+    }
+
+    pub fn write_all(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        match write_buffers(buffr_collection) {
+            Ok(None) => ModeTransition::new_mode(Normal::new()),
+            Ok(Some(e)) => ModeTransition::new_mode_and_info(Normal::new(), unfixed_info(&e)),
+            Err(e) => ModeTransition::new_mode_and_info(Normal::new(), e),
+        }
+    }
+
+    /// Like `:wa`, then quits, unless a checksum couldn't be fixed.
+    pub fn write_quit(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        match write_buffers(buffr_collection) {
+            Ok(None) => ModeTransition::new_mode(quitting::Quitting {}),
+            Ok(Some(e)) => ModeTransition::new_mode_and_info(Normal::new(), unfixed_info(&e)),
+            Err(e) => ModeTransition::new_mode_and_info(Normal::new(), e),
+        }
     }
 
     pub fn edit(buffr_collection: &mut BuffrCollection, filename: &str) -> ModeTransition {
@@ -139,6 +293,857 @@ mod cmd {
         buffr_collection.delete_current();
         ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
     }
+
+    pub fn mark(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let mut parts = args.trim().splitn(2, ' ');
+        let name = parts.next().and_then(|name| {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if Marks::is_valid_name(c) => Some(c),
+                _ => None,
+            }
+        });
+        let name = match name {
+            Some(name) => name,
+            None => {
+                return ModeTransition::new_mode_and_info(
+                    Normal::new(),
+                    "usage: :mark <letter> [label]".into(),
+                )
+            }
+        };
+        let label = parts.next().map(str::trim).filter(|l| !l.is_empty());
+        buffr_collection
+            .current_mut()
+            .set_mark(name, label.map(str::to_string));
+        ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+    }
+
+    pub fn marks(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        ModeTransition::new_mode_and_info(
+            Normal::new(),
+            buffr_collection.current().marks.describe(),
+        )
+    }
+
+    pub fn delete_marks(buffr_collection: &mut BuffrCollection, names: &str) -> ModeTransition {
+        let current_buffer = buffr_collection.current_mut();
+        let missing: String = names
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .filter(|&c| !current_buffer.remove_mark(c))
+            .collect();
+        if missing.is_empty() {
+            ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+        } else {
+            ModeTransition::new_mode_and_info(Normal::new(), format!("no such marks: {}", missing))
+        }
+    }
+
+    /// `:annotate [color=<color>|#rrggbb] text`, where the text may be quoted.
+    pub fn annotate(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let args = args.trim();
+        let (first, rest) = args.split_once(' ').unwrap_or((args, ""));
+        let color = first.strip_prefix("color=").or_else(|| first.starts_with('#').then_some(first));
+        let (color, text) = match color {
+            Some(color) => match parse_color(color) {
+                Ok(_) => (Some(color.to_string()), rest.trim()),
+                Err(e) => return ModeTransition::new_mode_and_info(Normal::new(), e),
+            },
+            None => (None, args),
+        };
+        let text = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        if text.is_empty() {
+            return ModeTransition::new_mode_and_info(
+                Normal::new(),
+                "usage: :annotate [color=<color>|#rrggbb] <text>".into(),
+            );
+        }
+        buffr_collection.current_mut().annotate(text, color);
+        ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+    }
+
+    pub fn annotations(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        ModeTransition::new_mode_and_info(
+            Normal::new(),
+            buffr_collection.current().annotations.describe(),
+        )
+    }
+
+    pub fn unannotate(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        match buffr_collection.current_mut().remove_annotation_at_cursor() {
+            Some(_) => ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength),
+            None => ModeTransition::new_mode_and_info(
+                Normal::new(),
+                "no annotation at the cursor".into(),
+            ),
+        }
+    }
+
+    pub fn diff(buffr_collection: &mut BuffrCollection, filename: &str) -> ModeTransition {
+        let filename = filename.trim();
+        if filename.is_empty() || filename == "off" {
+            buffr_collection.current_mut().diff_target = None;
+            return ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength);
+        }
+        match buffr_collection.current_mut().compare_with(Path::new(filename)) {
+            Ok(()) => ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength),
+            Err(e) => ModeTransition::new_mode_and_info(
+                Normal::new(),
+                format!("can't compare with {}: {}", filename, e),
+            ),
+        }
+    }
+
+    /// Compares in the background; the view shows the summary when it's done.
+    pub fn diff_summary(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        let current_buffer = buffr_collection.current();
+        let info = match &current_buffer.diff_target {
+            None => "not comparing, use :diff <file>".to_string(),
+            Some(target) => match FileSource::open(&target.path) {
+                Ok(other) => {
+                    let job = SummaryJob::start(current_buffer.snapshot(), other);
+                    buffr_collection.diff_summary = Some(job);
+                    "comparing...".to_string()
+                }
+                Err(e) => format!("compare failed: {}", e),
+            },
+        };
+        ModeTransition::new_mode_and_info(Normal::new(), info)
+    }
+
+    /// `start..end` in file offsets, end exclusive.
+    fn parse_range(text: &str) -> Result<std::ops::Range<usize>, String> {
+        let (start, end) = text
+            .split_once("..")
+            .ok_or_else(|| format!("not a range: {}", text))?;
+        let offset = |text: &str| {
+            usize::try_from(parse_int(text)?).map_err(|_| format!("not an offset: {}", text))
+        };
+        let range = offset(start)?..offset(end)?;
+        if range.is_empty() {
+            return Err(format!("empty range: {}", text));
+        }
+        Ok(range)
+    }
+
+    /// `:export <format> [@start..end] [file|"r]`: the selections, or the given
+    /// range, as text in a file or a register (`"` by default).
+    pub fn export(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let (format, rest) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+        let format = match ExportFormat::parse(format) {
+            Some(format) => format,
+            None => {
+                return info(format!(
+                    "usage: :export <format> [@start..end] [file|\"r], formats: {}",
+                    FORMAT_NAMES
+                ))
+            }
+        };
+        let mut target = rest.trim();
+        let mut range = None;
+        if let Some(range_arg) = target.strip_prefix('@') {
+            let (range_text, after) = range_arg.split_once(' ').unwrap_or((range_arg, ""));
+            match parse_range(range_text) {
+                Ok(parsed) => range = Some(parsed),
+                Err(e) => return info(e),
+            }
+            target = after.trim();
+        }
+
+        let current_buffer = buffr_collection.current_mut();
+        let chunks = match range {
+            Some(range) if range.len() > MAX_EXPORT_LEN => {
+                return info(format!("can't export more than 0x{:x} bytes", MAX_EXPORT_LEN))
+            }
+            Some(range) => match current_buffer.read_range(range.start, range.len()) {
+                Ok(bytes) if bytes.is_empty() => return info("the range is past the end".into()),
+                Ok(bytes) => vec![(range.start, bytes)],
+                Err(e) => return info(format!("{}", e)),
+            },
+            None => current_buffer.selected_bytes(),
+        };
+        let texts: Vec<String> = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, (offset, bytes))| {
+                let name = if chunks.len() == 1 {
+                    "data".to_string()
+                } else {
+                    format!("data_{}", i)
+                };
+                format.format(bytes, *offset, &name)
+            })
+            .collect();
+        let len: usize = chunks.iter().map(|(_, bytes)| bytes.len()).sum();
+
+        let register = match target.strip_prefix('"') {
+            Some(register) => match register.chars().collect::<Vec<_>>()[..] {
+                [register] => Some(register),
+                _ => return info(format!("not a register: {}", target)),
+            },
+            None if target.is_empty() => Some('"'),
+            None => None,
+        };
+        match register {
+            Some(register) => {
+                buffr_collection
+                    .registers
+                    .set(register, texts.into_iter().map(|text| text.into_bytes().into()).collect());
+                info(format!("exported 0x{:x} bytes to register {}", len, register))
+            }
+            None => match std::fs::write(target, texts.join("\n")) {
+                Ok(()) => info(format!("exported 0x{:x} bytes to {}", len, target)),
+                Err(e) => info(format!("{}: {}", target, e)),
+            },
+        }
+    }
+
+    /// `:put <type> <value>`: overwrites the encoded value at every caret.
+    pub fn put(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        put_value(buffr_collection, args, false)
+    }
+
+    /// `:put! <type> <value>`: inserts the encoded value at every caret.
+    pub fn put_insert(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        put_value(buffr_collection, args, true)
+    }
+
+    fn put_value(buffr_collection: &mut BuffrCollection, args: &str, insert: bool) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let (ty, value) = match args.trim().split_once(' ') {
+            Some(split) => split,
+            None => return info(format!("usage: :put[!] <type> <value>, types: {}", TYPE_NAMES)),
+        };
+        match encode_value(ty, value) {
+            Ok(bytes) if bytes.is_empty() => info("nothing to put".into()),
+            Ok(bytes) => put_bytes(buffr_collection, bytes, insert),
+            Err(e) => info(e),
+        }
+    }
+
+    /// Inserts `bytes` at every caret, or overwrites the bytes there. Only the
+    /// end of the file may grow: overwriting past the end of a loaded window
+    /// is refused, as the bytes after it would be shifted on write.
+    fn put_bytes(buffr_collection: &mut BuffrCollection, bytes: Vec<u8>, insert: bool) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let current_buffer = buffr_collection.current_mut();
+        let delta = if insert {
+            ops::insert(&current_buffer.data, &current_buffer.selection, bytes)
+        } else {
+            let carets: Vec<usize> = current_buffer.selection.iter().map(|r| r.caret).collect();
+            if carets.windows(2).any(|pair| pair[1] - pair[0] < bytes.len()) {
+                return info(format!("cursors are closer than the 0x{:x} bytes to write", bytes.len()));
+            }
+            let loaded_end = current_buffer.data_start_offset + current_buffer.data.len();
+            let runs_past_window = carets
+                .last()
+                .is_some_and(|caret| caret + bytes.len() > current_buffer.data.len());
+            if runs_past_window && current_buffer.logical_len() > loaded_end as u64 {
+                return info("bytes to overwrite aren't loaded".into());
+            }
+            ops::overwrite_at_carets(&current_buffer.data, &current_buffer.selection, &bytes)
+        };
+        ModeTransition::new_mode_and_dirty(Normal::new(), current_buffer.apply_delta(delta))
+    }
+
+    /// `:fill <kind>`: overwrites every selection, see `Fill::parse`.
+    pub fn fill(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let mut fill = match Fill::parse(args) {
+            Ok(fill) => fill,
+            Err(e) => return ModeTransition::new_mode_and_info(Normal::new(), e),
+        };
+        let current_buffer = buffr_collection.current_mut();
+        if current_buffer.data.is_empty() {
+            return ModeTransition::new_mode(Normal::new());
+        }
+        let delta = ops::fill(&current_buffer.data, &current_buffer.selection, |len| {
+            fill.next_bytes(len)
+        });
+        ModeTransition::new_mode_and_dirty(Normal::new(), current_buffer.apply_delta_in_place(delta))
+    }
+
+    /// Transforms the bytes of every selection in place, see `Transform::parse`.
+    fn transform(buffr_collection: &mut BuffrCollection, name: &str, args: &str) -> ModeTransition {
+        let transform = match Transform::parse(name, args) {
+            Ok(transform) => transform,
+            Err(e) => return ModeTransition::new_mode_and_info(Normal::new(), e),
+        };
+        let current_buffer = buffr_collection.current_mut();
+        if current_buffer.data.is_empty() {
+            return ModeTransition::new_mode(Normal::new());
+        }
+        let delta = ops::transform(&current_buffer.data, &current_buffer.selection, |bytes| {
+            transform.apply(bytes)
+        });
+        ModeTransition::new_mode_and_dirty(Normal::new(), current_buffer.apply_delta_in_place(delta))
+    }
+
+    pub fn xor(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "xor", args)
+    }
+
+    pub fn and(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "and", args)
+    }
+
+    pub fn or(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "or", args)
+    }
+
+    pub fn not(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "not", args)
+    }
+
+    pub fn add(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "add", args)
+    }
+
+    pub fn sub(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "sub", args)
+    }
+
+    pub fn shift_left(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "shl", args)
+    }
+
+    pub fn shift_right(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "shr", args)
+    }
+
+    pub fn rotate_left(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "rol", args)
+    }
+
+    pub fn rotate_right(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "ror", args)
+    }
+
+    pub fn reverse(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        transform(buffr_collection, "reverse", args)
+    }
+
+    /// `:hash <algorithm> [file] ["r]`: checksums of every selection, or of the
+    /// whole file read in pieces, shown or yanked into register `r`.
+    pub fn hash(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let info = |info: String| ModeTransition::new_mode_and_info(Normal::new(), info);
+        let usage = || info(format!("usage: :hash <algorithm> [file] [\"r], algorithms: {}", HASH_NAMES));
+        let mut args = args.split_whitespace();
+        let name = args.next().unwrap_or("");
+        let hasher = match Hasher::new(name) {
+            Some(hasher) => hasher,
+            None => return usage(),
+        };
+        let mut whole_file = false;
+        let mut register = None;
+        for arg in args {
+            match (arg, arg.strip_prefix('"').map(|r| r.chars().collect::<Vec<_>>())) {
+                ("file", _) => whole_file = true,
+                (_, Some(r)) if r.len() == 1 => register = Some(r[0]),
+                _ => return usage(),
+            }
+        }
+
+        let current_buffer = buffr_collection.current_mut();
+        let sums = if whole_file {
+            let mut hasher = hasher;
+            let len = current_buffer.logical_len();
+            let mut offset = 0;
+            while offset < len {
+                match current_buffer.read_at(offset, HASH_CHUNK_LEN) {
+                    Ok(bytes) if bytes.is_empty() => break,
+                    Ok(bytes) => {
+                        hasher.update(&bytes);
+                        offset += bytes.len() as u64;
+                    }
+                    Err(e) => return info(format!("{}", e)),
+                }
+            }
+            vec![hasher.finish()]
+        } else {
+            current_buffer
+                .selected_bytes()
+                .iter()
+                .map(|(_, bytes)| {
+                    let mut hasher = hasher.clone();
+                    hasher.update(bytes);
+                    hasher.finish()
+                })
+                .collect()
+        };
+        match register {
+            Some(register) => {
+                let count = sums.len();
+                buffr_collection
+                    .registers
+                    .set(register, sums.into_iter().map(|sum| sum.into_bytes().into()).collect());
+                info(format!("yanked {} {} sums to register {}", count, name, register))
+            }
+            None => info(format!("{}: {}", name, sums.join(" "))),
+        }
+    }
+
+    /// `:checksum` lists the rules, `:checksum <rule>` adds one (see
+    /// `ChecksumRule::parse`), `:checksum fix` applies them and `:checksum
+    /// clear` removes them all.
+    pub fn checksum(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let current_buffer = buffr_collection.current_mut();
+        let info = match args.trim() {
+            "" if current_buffer.checksum_rules.is_empty() => "no checksum rules".to_string(),
+            "" => {
+                let checks: Vec<(String, std::io::Result<bool>)> = current_buffer
+                    .checksum_rules
+                    .iter()
+                    .map(|rule| (rule.to_string(), rule.check(current_buffer)))
+                    .collect();
+                current_buffer.bad_checksums =
+                    Some(checks.iter().filter(|(_, check)| !matches!(check, Ok(true))).count());
+                checks
+                    .into_iter()
+                    .map(|(rule, check)| match check {
+                        Ok(true) => format!("{} (ok)", rule),
+                        Ok(false) => format!("{} (bad)", rule),
+                        Err(e) => format!("{} ({})", rule, e),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            }
+            "fix" => match current_buffer.fix_checksums() {
+                Ok(0) => "checksums are up to date".into(),
+                Ok(fixed) => format!("fixed {} checksums", fixed),
+                Err(e) => e,
+            },
+            "clear" => {
+                current_buffer.clear_checksum_rules();
+                "removed the checksum rules".into()
+            }
+            rule => match ChecksumRule::parse(rule) {
+                Ok(rule) => {
+                    current_buffer.add_checksum_rule(rule);
+                    return ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength);
+                }
+                Err(e) => e,
+            },
+        };
+        ModeTransition::new_mode_and_info(Normal::new(), info)
+    }
+
+    /// `:registers`: a hex preview of every register, with how many more
+    /// selections it holds.
+    pub fn registers(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        let info = buffr_collection.registers.describe(REGISTER_PREVIEW_LEN);
+        ModeTransition::new_mode_and_info(Normal::new(), info)
+    }
+
+    pub fn help(_: &mut BuffrCollection, topic: &str) -> ModeTransition {
+        let topic = match topic.trim() {
+            "" => "normal",
+            topic => topic,
+        };
+        match Help::find(topic) {
+            Some(help) => ModeTransition::new_mode_and_dirty(help, DirtyBytes::ChangeLength),
+            None => ModeTransition::new_mode_and_info(
+                Normal::new(),
+                format!("no help for '{}', try one of: {}", topic, Help::topic_names()),
+            ),
+        }
+    }
+
+    /// `:clipboard [<format>] [sync|nosync]` sets how selections go on the
+    /// system clipboard and whether plain yanks are copied too.
+    pub fn clipboard(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let clipboard = &mut buffr_collection.clipboard;
+        for arg in args.split_whitespace() {
+            match arg {
+                "sync" => clipboard.sync = true,
+                "nosync" => clipboard.sync = false,
+                format => match ClipboardFormat::parse(format) {
+                    Some(format) => clipboard.format = format,
+                    None => {
+                        let formats = CLIPBOARD_FORMAT_NAMES.replace(' ', "|");
+                        return ModeTransition::new_mode_and_info(
+                            Normal::new(),
+                            format!("usage: :clipboard [{}] [sync|nosync]", formats),
+                        );
+                    }
+                },
+            }
+        }
+        ModeTransition::new_mode_and_info(Normal::new(), clipboard.describe())
+    }
+
+    /// `:undolist`: the tips of the undo tree's branches, with how many
+    /// changes lead to them and how long ago they were made.
+    pub fn undo_list(buffr_collection: &mut BuffrCollection, _: &str) -> ModeTransition {
+        let history = &buffr_collection.current().history;
+        let leaves = history.leaves();
+        if leaves.is_empty() {
+            return ModeTransition::new_mode_and_info(Normal::new(), "no changes".into());
+        }
+        let now = SystemTime::now();
+        let branches = leaves
+            .iter()
+            .map(|&(state, changes)| {
+                let age = now.duration_since(history.time(state)).unwrap_or_default();
+                let plural = if changes == 1 { "" } else { "s" };
+                format!("{}: {} change{}, {} ago", state, changes, plural, format_age(age))
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        let info = format!("at {} of {}  {}", history.current(), history.len() - 1, branches);
+        ModeTransition::new_mode_and_info(Normal::new(), info)
+    }
+
+    /// `:undo [<state>]` undoes the last change, or goes to a state numbered
+    /// by `:undolist`, whichever branch it is on.
+    pub fn undo(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let current_buffer = buffr_collection.current_mut();
+        let dirty = match args.trim() {
+            "" => current_buffer.perform_undo(),
+            state => match state.parse::<usize>() {
+                Ok(state) if state < current_buffer.history.len() => {
+                    current_buffer.goto_history_state(state)
+                }
+                _ => {
+                    let last = current_buffer.history.len() - 1;
+                    return ModeTransition::new_mode_and_info(
+                        Normal::new(),
+                        format!("usage: :undo [0..{}]", last),
+                    );
+                }
+            },
+        };
+        history_moved(dirty)
+    }
+
+    pub fn earlier(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        travel(buffr_collection, args, false)
+    }
+
+    pub fn later(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        travel(buffr_collection, args, true)
+    }
+
+    /// Moves through the undo tree in the order changes were made, by a
+    /// number of changes or by time.
+    fn travel(buffr_collection: &mut BuffrCollection, args: &str, forward: bool) -> ModeTransition {
+        let offset = match parse_undo_offset(args.trim()) {
+            Some(offset) => offset,
+            None => {
+                let name = if forward { "later" } else { "earlier" };
+                return ModeTransition::new_mode_and_info(
+                    Normal::new(),
+                    format!("usage: :{} <count>|<n>s|<n>m|<n>h|<n>d", name),
+                );
+            }
+        };
+        let current_buffer = buffr_collection.current_mut();
+        let dirty = match offset {
+            UndoOffset::Changes(count) => {
+                let count = count.min(isize::MAX as usize) as isize;
+                current_buffer.step_history(if forward { count } else { -count })
+            }
+            UndoOffset::Time(duration) => {
+                let history = &current_buffer.history;
+                let now = history.time(history.current());
+                let time = if forward {
+                    now.checked_add(duration)
+                } else {
+                    now.checked_sub(duration)
+                };
+                let target = match time {
+                    Some(time) => history.state_at(time),
+                    None if forward => history.len() - 1,
+                    None => 0,
+                };
+                current_buffer.goto_history_state(target)
+            }
+        };
+        history_moved(dirty)
+    }
+
+    fn history_moved(dirty: Option<DirtyBytes>) -> ModeTransition {
+        match dirty {
+            Some(dirty) => ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
+            None => ModeTransition::new_mode_and_info(Normal::new(), "no change to go to".into()),
+        }
+    }
+
+    enum UndoOffset {
+        Changes(usize),
+        Time(Duration),
+    }
+
+    /// A count of changes, or a time such as `30s`, `5m`, `2h` or `1d`.
+    fn parse_undo_offset(text: &str) -> Option<UndoOffset> {
+        let unit = match text.chars().last()? {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return text.parse().ok().map(UndoOffset::Changes),
+        };
+        let count: u64 = text[..text.len() - 1].parse().ok()?;
+        Some(UndoOffset::Time(Duration::from_secs(count.saturating_mul(unit))))
+    }
+
+    fn format_age(age: Duration) -> String {
+        match age.as_secs() {
+            secs @ 0..=59 => format!("{}s", secs),
+            secs @ 60..=3599 => format!("{}m", secs / 60),
+            secs @ 3600..=86399 => format!("{}h", secs / 3600),
+            secs => format!("{}d", secs / 86400),
+        }
+    }
+
+    pub fn minimap(buffr_collection: &mut BuffrCollection, kind: &str) -> ModeTransition {
+        let kind = match kind.trim() {
+            "" if buffr_collection.minimap().is_some() => None,
+            "" | "entropy" => Some(MinimapKind::Entropy),
+            "class" => Some(MinimapKind::ByteClass),
+            "off" => None,
+            _ => {
+                return ModeTransition::new_mode_and_info(
+                    Normal::new(),
+                    "usage: :minimap [entropy|class|off]".into(),
+                )
+            }
+        };
+        if kind.is_some() && buffr_collection.current().path.is_none() {
+            return ModeTransition::new_mode_and_info(
+                Normal::new(),
+                "the minimap needs a file on disk".into(),
+            );
+        }
+        buffr_collection.set_minimap(kind);
+        ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+    }
+
+    pub fn template(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        let usage = || {
+            ModeTransition::new_mode_and_info(
+                Normal::new(),
+                "usage: :template apply <file> [@offset] | :template close".into(),
+            )
+        };
+        let current_buffer = buffr_collection.current_mut();
+        let mut words = args.split_whitespace();
+        match (words.next(), words.next(), words.next(), words.next()) {
+            (Some("close"), None, _, _) => {
+                current_buffer.outline = None;
+                ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+            }
+            (Some("apply"), Some(path), offset, None) => {
+                let offset = match offset {
+                    None => (current_buffer.data_start_offset
+                        + current_buffer.selection.main_cursor_offset())
+                        as u64,
+                    Some(at) => match at.strip_prefix('@').map(parse_int) {
+                        Some(Ok(offset)) if offset >= 0 => offset as u64,
+                        _ => return usage(),
+                    },
+                };
+                let source = OutlineSource::Template {
+                    path: path.into(),
+                    offset,
+                };
+                let outline = match source.build(current_buffer) {
+                    Ok(outline) => outline,
+                    Err(info) => return ModeTransition::new_mode_and_info(Normal::new(), info),
+                };
+                let range = outline.selected().map(|node| node.range.clone());
+                current_buffer.outline = Some(outline);
+                select_outline_field(current_buffer, range)
+            }
+            _ => usage(),
+        }
+    }
+
+    /// Shows (no args) or hides (`close`) an outline built from `source`.
+    fn toggle_outline(
+        buffr_collection: &mut BuffrCollection,
+        args: &str,
+        source: OutlineSource,
+        usage: &str,
+    ) -> ModeTransition {
+        let current_buffer = buffr_collection.current_mut();
+        match args.trim() {
+            "close" => {
+                current_buffer.outline = None;
+                ModeTransition::new_mode_and_dirty(Normal::new(), DirtyBytes::ChangeLength)
+            }
+            "" => match source.build(current_buffer) {
+                Ok(outline) => {
+                    current_buffer.outline = Some(outline);
+                    select_outline_field(current_buffer, None)
+                }
+                Err(info) => ModeTransition::new_mode_and_info(Normal::new(), info),
+            },
+            _ => ModeTransition::new_mode_and_info(Normal::new(), usage.into()),
+        }
+    }
+
+    pub fn headers(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        toggle_outline(buffr_collection, args, OutlineSource::Executable, "usage: :headers [close]")
+    }
+
+    pub fn partitions(buffr_collection: &mut BuffrCollection, args: &str) -> ModeTransition {
+        toggle_outline(
+            buffr_collection,
+            args,
+            OutlineSource::Partitions,
+            "usage: :partitions [close]",
+        )
+    }
+
+    /// Writes a new value into the outline field under the cursor.
+    pub fn set_outline_field(buffr_collection: &mut BuffrCollection, value: &str) -> ModeTransition {
+        let current_buffer = buffr_collection.current_mut();
+        let node = match current_buffer.outline.as_ref().and_then(|o| o.selected()) {
+            Some(node) => node,
+            None => {
+                return ModeTransition::new_mode_and_info(Normal::new(), "no field selected".into())
+            }
+        };
+        let range = node.range.clone();
+        let bytes = match &node.editor {
+            Some(editor) => editor.encode(value),
+            None => Err("this field can't be edited".into()),
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(info) => return ModeTransition::new_mode_and_info(OutlineFocus(), info),
+        };
+
+        let written = current_buffer
+            .select_file_range(range.clone(), FIELD_WINDOW_LEN, FIELD_WINDOW_ALIGN)
+            .and_then(|_| current_buffer.overwrite_at_file_offset(range.start, &bytes));
+        if let Err(info) = written {
+            return ModeTransition::new_mode_and_info(OutlineFocus(), info);
+        }
+
+        let mut outline = current_buffer.outline.take();
+        let rebuilt = outline
+            .as_mut()
+            .map_or(Ok(()), |outline| outline.rebuild(current_buffer));
+        current_buffer.outline = outline;
+        if let Err(info) = rebuilt {
+            return ModeTransition::new_mode_and_info(OutlineFocus(), info);
+        }
+        current_buffer.refresh_sections();
+        select_outline_field(current_buffer, Some(range))
+    }
+
+    fn select_outline_field(
+        current_buffer: &mut crate::CurrentBuffer,
+        range: Option<std::ops::Range<u64>>,
+    ) -> ModeTransition {
+        let selected = range.map_or(Ok(DirtyBytes::ChangeLength), |range| {
+            current_buffer.select_file_range(range, FIELD_WINDOW_LEN, FIELD_WINDOW_ALIGN)
+        });
+        match selected {
+            Ok(dirty) => ModeTransition::new_mode_and_dirty(OutlineFocus(), dirty),
+            Err(info) => ModeTransition::new_mode_and_info(OutlineFocus(), info),
+        }
+    }
+
+    /// Bytes shown of each register by `:registers`
+    const REGISTER_PREVIEW_LEN: usize = 8;
+    /// Bytes read from disk at a time by `:hash ... file`
+    const HASH_CHUNK_LEN: usize = 1 << 20;
+    /// Bytes read at a time by `:w <file> <start>..<end>`
+    const COPY_CHUNK_LEN: usize = 1 << 20;
+    /// Most bytes `:r` inserts
+    const MAX_READ_LEN: usize = 256 << 20;
+    /// Commands don't know the view's layout, so windows loaded to show a
+    /// field use a fixed size.
+    const FIELD_WINDOW_LEN: usize = 0x10000;
+    const FIELD_WINDOW_ALIGN: usize = 0x10;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::selection::SelRegion;
+
+        fn info(transition: ModeTransition) -> String {
+            match transition {
+                ModeTransition::ModeAndInfo(_, info) => info,
+                _ => String::new(),
+            }
+        }
+
+        #[test]
+        fn test_write_and_read_args() {
+            assert_eq!(parse_write_args("out.bin 0x10..0x20"), Ok(Some(("out.bin", 0x10..0x20))));
+            assert_eq!(parse_write_args(""), Ok(None));
+            assert_eq!(parse_write_args("out.bin"), Ok(None));
+            assert!(parse_write_args("0..10").is_err());
+            assert_eq!(parse_read_args("in.bin"), Ok(("in.bin", 0, None)));
+            assert_eq!(parse_read_args("in.bin 0x10 4"), Ok(("in.bin", 0x10, Some(4))));
+            assert!(parse_read_args("in.bin -1").is_err());
+            assert!(parse_read_args("").is_err());
+
+            let dir = std::env::temp_dir().join(format!("tofu-test-write-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let (own, other) = (dir.join("own.bin"), dir.join("other.bin"));
+            std::fs::write(&own, b"ABCDEFGH").unwrap();
+            let buffer = CurrentBuffer::from_data_and_path(b"ABCDEFGH".to_vec(), Some(&own));
+            let own_name = own.to_str().unwrap();
+            assert!(info(write_range(&buffer, own_name, 0..2)).starts_with("can't write"));
+            assert_eq!(std::fs::read(&own).unwrap(), b"ABCDEFGH");
+            info(write_range(&buffer, other.to_str().unwrap(), 2..5));
+            assert_eq!(std::fs::read(&other).unwrap(), b"CDE");
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn test_annotate_args() {
+            let buffer = CurrentBuffer::from_data_and_path(b"ABCD".to_vec(), None::<&str>);
+            let mut buffr_collection = BuffrCollection::with_current_buffer(buffer);
+            let annotation = |buffr_collection: &mut BuffrCollection, args: &str| {
+                annotate(buffr_collection, args);
+                let annotations = &mut buffr_collection.current_mut().annotations;
+                let annotation = annotations.remove_at(0).unwrap();
+                (annotation.color, annotation.text)
+            };
+            assert_eq!(annotation(&mut buffr_collection, "42 bytes"), (None, "42 bytes".into()));
+            assert_eq!(annotation(&mut buffr_collection, "red \"id\""), (None, "red \"id\"".into()));
+            assert_eq!(
+                annotation(&mut buffr_collection, "color=red \"id\""),
+                (Some("red".into()), "id".into())
+            );
+            assert_eq!(
+                annotation(&mut buffr_collection, "#ff8000 id"),
+                (Some("#ff8000".into()), "id".into())
+            );
+            assert!(info(annotate(&mut buffr_collection, "color=nope id")).contains("nope"));
+        }
+
+        #[test]
+        fn test_put_past_window() {
+            let path = std::env::temp_dir().join(format!("tofu-test-put-{}", std::process::id()));
+            std::fs::write(&path, b"ABCDEFGH").unwrap();
+            let window = CurrentBuffer::from_data_and_path(b"ABCD".to_vec(), Some(&path));
+            let mut buffr_collection = BuffrCollection::with_current_buffer(window);
+            buffr_collection.current_mut().map_selections(|_| vec![SelRegion::new(2, 2)]);
+            let refused = info(put_bytes(&mut buffr_collection, b"xyz".to_vec(), false));
+            assert_eq!(refused, "bytes to overwrite aren't loaded");
+            assert_eq!(buffr_collection.current().data.slice_to_cow(..), &b"ABCD"[..]);
+            put_bytes(&mut buffr_collection, b"xy".to_vec(), false);
+            assert_eq!(buffr_collection.current().data.slice_to_cow(..), &b"ABxy"[..]);
+
+            let whole = CurrentBuffer::from_data_and_path(b"ABCDEFGH".to_vec(), Some(&path));
+            let mut buffr_collection = BuffrCollection::with_current_buffer(whole);
+            buffr_collection.current_mut().map_selections(|_| vec![SelRegion::new(6, 6)]);
+            put_bytes(&mut buffr_collection, b"xyz".to_vec(), false);
+            assert_eq!(buffr_collection.current().data.slice_to_cow(..), &b"ABCDEFxyz"[..]);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
 }
 
 type CommandHandler = fn(&mut BuffrCollection, &str) -> ModeTransition;
@@ -160,6 +1165,11 @@ fn default_commands() -> HashMap<String, CommandHandler> {
         "w" => write,
         "write" => write,
         "wq" => write_quit,
+        "wsel" => write_selections,
+        "r" => read,
+        "read" => read,
+        "r!" => read_overwrite,
+        "read!" => read_overwrite,
         "wa" => write_all,
         "write-all" => write_all,
         "e" => edit,
@@ -168,6 +1178,43 @@ fn default_commands() -> HashMap<String, CommandHandler> {
         "delete-current_buffer" => delete_current_buffer,
         "db!" => force_delete_current_buffer,
         "delete-current_buffer!" => force_delete_current_buffer,
+        "help" => help,
+        "export" => export,
+        "put" => put,
+        "put!" => put_insert,
+        "fill" => fill,
+        "xor" => xor,
+        "and" => and,
+        "or" => or,
+        "not" => not,
+        "add" => add,
+        "sub" => sub,
+        "shl" => shift_left,
+        "shr" => shift_right,
+        "rol" => rotate_left,
+        "ror" => rotate_right,
+        "reverse" => reverse,
+        "hash" => hash,
+        "checksum" => checksum,
+        "registers" => registers,
+        "clipboard" => clipboard,
+        "undo" => undo,
+        "undolist" => undo_list,
+        "earlier" => earlier,
+        "later" => later,
+        "minimap" => minimap,
+        "mark" => mark,
+        "marks" => marks,
+        "delmarks" => delete_marks,
+        "annotate" => annotate,
+        "annotations" => annotations,
+        "unannotate" => unannotate,
+        "diff" => diff,
+        "diffsummary" => diff_summary,
+        "template" => template,
+        "tset" => set_outline_field,
+        "headers" => headers,
+        "partitions" => partitions,
     ]
 }
 
@@ -176,19 +1223,39 @@ lazy_static! {
     static ref DEFAULT_COMMANDS: HashMap<String, CommandHandler> = default_commands();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::RemoveLast => "delete before the cursor",
+            Action::RemoveThis => "delete under the cursor",
+            Action::CursorLeft => "move left",
+            Action::CursorRight => "move right",
+            Action::Finish => "run the command",
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 impl Command {
-    pub fn new() -> Command {
+    pub fn new(from: &'static str) -> Command {
         Command {
             cursor: 0,
             command: String::new(),
+            from,
         }
     }
 
     fn finish(&self, buffr_collection: &mut BuffrCollection) -> ModeTransition {
         let (name, rest) = self
             .command
-            .split_at(self.command.find(' ').unwrap_or_else(|| self.command.len()));
-        if let Some(handler) = DEFAULT_COMMANDS.get(name) {
+            .split_at(self.command.find(' ').unwrap_or(self.command.len()));
+        if name == "help" && rest.trim().is_empty() {
+            cmd::help(buffr_collection, self.from)
+        } else if let Some(handler) = DEFAULT_COMMANDS.get(name) {
             handler(buffr_collection, if rest.is_empty() { rest } else { &rest[1..] })
         } else {
             ModeTransition::new_mode_and_info(Normal::new(), format!("Unknown command {}", name))
@@ -226,7 +1293,7 @@ impl Mode for Command {
                 Action::Cancel => return Some(ModeTransition::new_mode(Normal::new())),
                 Action::Finish => return Some(self.finish(buffr_collection)),
             }
-            Some(ModeTransition::new_mode(Command { command, cursor }))
+            Some(ModeTransition::new_mode(Command { command, cursor, from: self.from }))
         } else if let Event::Key(KeyEvent {
             code: KeyCode::Char(ch),
             modifiers,
@@ -239,7 +1306,7 @@ impl Mode for Command {
             let mut cursor = self.cursor;
             command.insert(cursor, *ch);
             cursor += 1;
-            Some(ModeTransition::new_mode(Command { command, cursor }))
+            Some(ModeTransition::new_mode(Command { command, cursor, from: self.from }))
         } else {
             None
         }
@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use lazy_static::lazy_static;
+
+use crate::cmd_count;
+use crate::keymap::{Binding, Describe, KeyMap};
+use crate::modes::{
+    bracket, command, insert, jumpto, minimap,
+    mode::{DirtyBytes, Mode, ModeTransition},
+    normal::{self, Normal},
+    outline, replace, search, split,
+};
+use crate::BuffrCollection;
+
+type Bindings = fn() -> Vec<Binding>;
+
+/// Modes that have help, by the name `:help` takes.
+pub const TOPICS: &[(&str, Bindings)] = &[
+    ("normal", normal_bindings),
+    ("jump", jumpto::bindings),
+    ("split", split::bindings),
+    ("insert", insert::bindings),
+    ("replace", replace::bindings),
+    ("search", search::bindings),
+    ("command", command::bindings),
+    ("bracket", bracket::bindings),
+    ("minimap", minimap::bindings),
+    ("outline", outline::bindings),
+    ("help", bindings),
+];
+
+/// Counts are typed in normal mode, so they're listed with it.
+fn normal_bindings() -> Vec<Binding> {
+    let mut bindings = normal::bindings();
+    bindings.extend(cmd_count::bindings());
+    bindings
+}
+
+/// Shows the key bindings of a mode over the hex view.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Help {
+    pub topic: &'static str,
+    pub scroll: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Up,
+    Down,
+    First,
+    Last,
+    Close,
+}
+
+fn default_maps() -> KeyMap<Action> {
+    KeyMap {
+        maps: keys!(
+            (key KeyCode::Up => Action::Up),
+            ('k' => Action::Up),
+            (key KeyCode::Down => Action::Down),
+            ('j' => Action::Down),
+            ('g' => Action::First),
+            ('G' => Action::Last),
+            (key KeyCode::Esc => Action::Close),
+            ('q' => Action::Close)
+        ),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
+}
+
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Up => "scroll up",
+            Action::Down => "scroll down",
+            Action::First => "scroll to the top",
+            Action::Last => "scroll to the bottom",
+            Action::Close => "close (as does any other key)",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
+/// How many bindings fit in the overlay on a terminal `height` rows high:
+/// all rows but the title, the footer and the status line.
+pub fn visible_lines(height: usize) -> usize {
+    height.saturating_sub(3)
+}
+
+impl Help {
+    pub fn new(topic: &'static str) -> Help {
+        Help { topic, scroll: 0 }
+    }
+
+    /// The help for the mode called `name`, if there is one.
+    pub fn find(name: &str) -> Option<Help> {
+        TOPICS
+            .iter()
+            .find(|(topic, _)| *topic == name)
+            .map(|(topic, _)| Help::new(topic))
+    }
+
+    pub fn topic_names() -> String {
+        TOPICS
+            .iter()
+            .map(|(topic, _)| *topic)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn bindings(&self) -> Vec<Binding> {
+        TOPICS
+            .iter()
+            .find(|(topic, _)| *topic == self.topic)
+            .map(|(_, bindings)| bindings())
+            .unwrap_or_default()
+    }
+
+    fn last_scroll(&self) -> usize {
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        self.bindings()
+            .len()
+            .saturating_sub(visible_lines(height as usize))
+    }
+}
+
+impl Mode for Help {
+    fn name(&self) -> Cow<'static, str> {
+        "HELP".into()
+    }
+
+    fn transition(&self, evt: &Event, _: &mut BuffrCollection, _: usize) -> Option<ModeTransition> {
+        if !matches!(evt, Event::Key(_)) {
+            return None;
+        }
+        let scroll = match DEFAULT_MAPS.event_to_action(evt) {
+            Some(Action::Up) => self.scroll.saturating_sub(1),
+            Some(Action::Down) => std::cmp::min(self.scroll + 1, self.last_scroll()),
+            Some(Action::First) => 0,
+            Some(Action::Last) => self.last_scroll(),
+            Some(Action::Close) | None => {
+                return Some(ModeTransition::new_mode_and_dirty(
+                    Normal::new(),
+                    DirtyBytes::ChangeLength,
+                ))
+            }
+        };
+        Some(ModeTransition::new_mode(Help { scroll, ..*self }))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
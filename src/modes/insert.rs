@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
     mode::{Mode, ModeTransition},
     normal::Normal,
@@ -57,6 +57,26 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::InsertNull => "insert a null byte",
+            Action::SwitchInputMode => "switch between text and hex input",
+            Action::RemoveLast => "delete the byte before the cursor",
+            Action::RemoveThis => "delete the byte under the cursor",
+            Action::Exit => "back to normal mode",
+            Action::Move(Direction::Left) => "move left",
+            Action::Move(Direction::Down) => "move down a line",
+            Action::Move(Direction::Up) => "move up a line",
+            Action::Move(Direction::Right) => "move right",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 fn transition_ascii_insertion(
     key: char,
     current_buffer: &mut CurrentBuffer,
@@ -4,8 +4,9 @@ use std::collections::HashMap;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
+    minimap::MinimapJump,
     mode::{Mode, ModeTransition},
     normal::Normal,
 };
@@ -17,23 +18,52 @@ pub struct JumpTo {
     pub extend: bool,
 }
 
-fn default_maps() -> KeyMap<Direction> {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Boundary(Direction),
+    Minimap,
+    /// One state back or forward in the undo tree, by time
+    History(bool),
+}
+
+fn default_maps() -> KeyMap<Action> {
     KeyMap {
         maps: keys!(
-            (key KeyCode::Left => Direction::Left),
-            ('h' => Direction::Left),
-            (key KeyCode::Down => Direction::Down),
-            ('j' => Direction::Down),
-            (key KeyCode::Up => Direction::Up),
-            ('k' => Direction::Up),
-            (key KeyCode::Right => Direction::Right),
-            ('l' => Direction::Right)
+            (key KeyCode::Left => Action::Boundary(Direction::Left)),
+            ('h' => Action::Boundary(Direction::Left)),
+            (key KeyCode::Down => Action::Boundary(Direction::Down)),
+            ('j' => Action::Boundary(Direction::Down)),
+            (key KeyCode::Up => Action::Boundary(Direction::Up)),
+            ('k' => Action::Boundary(Direction::Up)),
+            (key KeyCode::Right => Action::Boundary(Direction::Right)),
+            ('l' => Action::Boundary(Direction::Right)),
+            ('m' => Action::Minimap),
+            ('-' => Action::History(false)),
+            ('+' => Action::History(true))
         ),
     }
 }
 
 lazy_static! {
-    static ref DEFAULT_MAPS: KeyMap<Direction> = default_maps();
+    static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
+}
+
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Boundary(Direction::Left) => "to line start",
+            Action::Boundary(Direction::Down) => "to file end",
+            Action::Boundary(Direction::Up) => "to file start",
+            Action::Boundary(Direction::Right) => "to line end",
+            Action::Minimap => "to a minimap cell",
+            Action::History(false) => "to the previous undo state",
+            Action::History(true) => "to the next undo state",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
 }
 
 impl Mode for JumpTo {
@@ -51,8 +81,23 @@ impl Mode for JumpTo {
         buffr_collection: &mut BuffrCollection,
         bytes_per_line: usize,
     ) -> Option<ModeTransition> {
+        if let (Some(minimap), false) = (&buffr_collection.shown_minimap, self.extend) {
+            if let Some(Action::Minimap) = DEFAULT_MAPS.event_to_action(evt) {
+                return Some(ModeTransition::new_mode(MinimapJump::at_cursor(
+                    buffr_collection.current(),
+                    minimap,
+                )));
+            }
+        }
         let current_buffer = buffr_collection.current_mut();
-        if let Some(direction) = DEFAULT_MAPS.event_to_action(evt) {
+        if let Some(Action::History(forward)) = DEFAULT_MAPS.event_to_action(evt) {
+            let dirty = current_buffer.step_history(if forward { 1 } else { -1 });
+            return Some(match dirty {
+                Some(dirty) => ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
+                None => ModeTransition::new_mode(Normal::new()),
+            });
+        }
+        if let Some(Action::Boundary(direction)) = DEFAULT_MAPS.event_to_action(evt) {
             let max_bytes = current_buffer.data.len();
             Some(ModeTransition::new_mode_and_dirty(
                 Normal::new(),
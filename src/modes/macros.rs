@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+
+use crate::modes::{
+    mode::{Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::BuffrCollection;
+
+/// Macros are named like named registers.
+pub fn is_valid_name(name: char) -> bool {
+    name.is_ascii_alphanumeric()
+}
+
+/// Waits for a register name after `Q` (record into it) or `q` (replay it
+/// `count` times).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MacroPrompt {
+    pub record: bool,
+    pub count: usize,
+}
+
+impl Mode for MacroPrompt {
+    fn name(&self) -> Cow<'static, str> {
+        if self.record {
+            "RECORD MACRO".into()
+        } else {
+            "REPLAY MACRO".into()
+        }
+    }
+
+    fn transition(
+        &self,
+        evt: &Event,
+        buffr_collection: &mut BuffrCollection,
+        _: usize,
+    ) -> Option<ModeTransition> {
+        let name = match evt {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(name),
+                ..
+            }) if is_valid_name(*name) => *name,
+            Event::Key(_) => return Some(ModeTransition::new_mode(Normal::new())),
+            _ => return None,
+        };
+        let macros = &mut buffr_collection.macros;
+        if self.record {
+            macros.start_recording(name);
+        } else if macros.get(name).is_some() {
+            macros.request_replay(name, self.count);
+        } else {
+            return Some(ModeTransition::new_mode_and_info(
+                Normal::new(),
+                format!("no macro in register {}", name),
+            ));
+        }
+        Some(ModeTransition::new_mode(Normal::new()))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
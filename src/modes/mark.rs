@@ -0,0 +1,75 @@
+use std::borrow::Cow;
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+
+use crate::marks::Marks;
+use crate::modes::{
+    mode::{jump_window_len, DirtyBytes, Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::BuffrCollection;
+
+/// Waits for a mark name after `m` (set) or `'` (jump).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MarkPrompt {
+    pub set: bool,
+}
+
+impl Mode for MarkPrompt {
+    fn name(&self) -> Cow<'static, str> {
+        if self.set {
+            "SET MARK".into()
+        } else {
+            "GOTO MARK".into()
+        }
+    }
+
+    fn transition(
+        &self,
+        evt: &Event,
+        buffr_collection: &mut BuffrCollection,
+        bytes_per_line: usize,
+    ) -> Option<ModeTransition> {
+        let name = match evt {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(name),
+                ..
+            }) if Marks::is_valid_name(*name) => *name,
+            Event::Key(_) => return Some(ModeTransition::new_mode(Normal::new())),
+            _ => return None,
+        };
+
+        let current_buffer = buffr_collection.current_mut();
+        if self.set {
+            current_buffer.set_mark(name, None);
+            return Some(ModeTransition::new_mode_and_dirty(
+                Normal::new(),
+                DirtyBytes::ChangeLength,
+            ));
+        }
+
+        let offset = match current_buffer.marks.get(name) {
+            Some(mark) => mark.offset,
+            None => {
+                return Some(ModeTransition::new_mode_and_info(
+                    Normal::new(),
+                    format!("mark '{}' is not set", name),
+                ))
+            }
+        };
+        Some(
+            match current_buffer.jump_to_file_offset(
+                offset,
+                jump_window_len(bytes_per_line),
+                bytes_per_line,
+            ) {
+                Ok(dirty) => ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
+                Err(info) => ModeTransition::new_mode_and_info(Normal::new(), info),
+            },
+        )
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
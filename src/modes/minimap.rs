@@ -0,0 +1,128 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use lazy_static::lazy_static;
+
+use crate::hex_view::minimap::Minimap;
+use crate::keymap::{Binding, Describe, KeyMap};
+use crate::modes::{
+    mode::{jump_window_len, Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::{BuffrCollection, CurrentBuffer};
+
+/// Picks a minimap cell to jump to; the view highlights `cell` in the sidebar.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MinimapJump {
+    pub cell: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Up,
+    Down,
+    First,
+    Last,
+    Jump,
+    Cancel,
+}
+
+fn default_maps() -> KeyMap<Action> {
+    KeyMap {
+        maps: keys!(
+            (key KeyCode::Up => Action::Up),
+            ('k' => Action::Up),
+            (key KeyCode::Down => Action::Down),
+            ('j' => Action::Down),
+            ('g' => Action::First),
+            ('G' => Action::Last),
+            (key KeyCode::Enter => Action::Jump),
+            (key KeyCode::Esc => Action::Cancel)
+        ),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
+}
+
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Up => "previous cell",
+            Action::Down => "next cell",
+            Action::First => "first cell",
+            Action::Last => "last cell",
+            Action::Jump => "jump to the cell",
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
+impl MinimapJump {
+    /// Starts on the cell of `minimap` that holds the main cursor.
+    pub fn at_cursor(current_buffer: &CurrentBuffer, minimap: &Minimap) -> MinimapJump {
+        let offset =
+            current_buffer.data_start_offset + current_buffer.selection.main_cursor_offset();
+        MinimapJump {
+            cell: minimap.cell_of_offset(offset as u64),
+        }
+    }
+}
+
+impl Mode for MinimapJump {
+    fn name(&self) -> Cow<'static, str> {
+        "MINIMAP".into()
+    }
+
+    fn transition(
+        &self,
+        evt: &Event,
+        buffr_collection: &mut BuffrCollection,
+        bytes_per_line: usize,
+    ) -> Option<ModeTransition> {
+        // The minimap can go away under the mode, e.g. when the file is written
+        let (last_cell, cell_len) = match &buffr_collection.shown_minimap {
+            Some(minimap) => (minimap.cell_count().saturating_sub(1), minimap.cell_len()),
+            None => return Some(ModeTransition::new_mode(Normal::new())),
+        };
+        let action = match DEFAULT_MAPS.event_to_action(evt) {
+            Some(action) => action,
+            None if matches!(evt, Event::Key(_)) => Action::Cancel,
+            None => return None,
+        };
+
+        let cell = match action {
+            Action::Up => self.cell.saturating_sub(1),
+            Action::Down => cmp::min(self.cell + 1, last_cell),
+            Action::First => 0,
+            Action::Last => last_cell,
+            Action::Cancel => return Some(ModeTransition::new_mode(Normal::new())),
+            Action::Jump => {
+                let current_buffer = buffr_collection.current_mut();
+                let offset = cmp::min(self.cell, last_cell) as u64 * cell_len;
+                return Some(
+                    match current_buffer.jump_to_file_offset(
+                        offset as usize,
+                        jump_window_len(bytes_per_line),
+                        bytes_per_line,
+                    ) {
+                        Ok(dirty) => ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
+                        Err(info) => ModeTransition::new_mode_and_info(Normal::new(), info),
+                    },
+                );
+            }
+        };
+        Some(ModeTransition::new_mode(MinimapJump { cell }))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -25,12 +25,19 @@ pub mod quitting {
     }
 }
 
+pub mod bracket;
 pub mod collapse;
 pub mod command;
+pub mod help;
 pub mod insert;
 pub mod jumpto;
+pub mod macros;
+pub mod mark;
+pub mod minimap;
 pub mod mode;
 pub mod normal;
+pub mod outline;
+pub mod register;
 pub mod replace;
 pub mod search;
 pub mod split;
@@ -1,4 +1,5 @@
 use crossterm::event::Event;
+use crossterm::terminal;
 use std::borrow::Cow;
 use xi_rope::Interval;
 
@@ -36,6 +37,14 @@ pub enum ModeTransition {
     DirtyBytes(DirtyBytes),
     ModeAndDirtyBytes(Box<dyn Mode>, DirtyBytes),
     ModeAndInfo(Box<dyn Mode>, String),
+    ModeDirtyBytesAndInfo(Box<dyn Mode>, DirtyBytes, String),
+}
+
+/// How many bytes to load when a jump leaves the loaded window: three screens,
+/// like the initial load.
+pub fn jump_window_len(bytes_per_line: usize) -> usize {
+    let (_, height) = terminal::size().unwrap_or((80, 24));
+    3 * std::cmp::max(1, height as usize - 1) * bytes_per_line
 }
 
 impl ModeTransition {
@@ -50,4 +59,8 @@ impl ModeTransition {
     pub fn new_mode_and_info(mode: impl Mode, info: String) -> ModeTransition {
         ModeTransition::ModeAndInfo(Box::new(mode), info)
     }
+
+    pub fn new_mode_dirty_and_info(mode: impl Mode, dirty: DirtyBytes, info: String) -> ModeTransition {
+        ModeTransition::ModeDirtyBytesAndInfo(Box::new(mode), dirty, info)
+    }
 }
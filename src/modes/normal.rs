@@ -4,20 +4,25 @@ use std::collections::HashMap;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
+use crate::byte_rope::Rope;
 use crate::operations as ops;
 use crate::selection::Direction;
 use crate::{
     cmd_count, modes,
-    modes::mode::{DirtyBytes, Mode, ModeTransition},
+    modes::mode::{jump_window_len, DirtyBytes, Mode, ModeTransition},
     BuffrCollection,
 };
 
 use super::insert::InsertionMode;
+use super::register::SEARCH_REGISTER;
+use crate::macros::LAST_CHANGE;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Normal {
     count_state: cmd_count::State,
+    /// Register chosen with `"` for the next yank, delete, change or paste
+    register: Option<char>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -45,6 +50,14 @@ enum Action {
     SelectAll,
     ReplaceMode { hex: bool },
     Measure,
+    MarkMode { set: bool },
+    BracketMode { forward: bool },
+    OutlineMode,
+    Help,
+    RegisterMode,
+    MacroRecord,
+    MacroReplay,
+    Repeat,
     Undo,
     Redo,
 }
@@ -76,6 +89,16 @@ fn default_maps() -> KeyMap<Action> {
             ('(' => Action::SelectPrev),
             (')' => Action::SelectNext),
             ('M' => Action::Measure),
+            ('m' => Action::MarkMode{set: true}),
+            ('\'' => Action::MarkMode{set: false}),
+            (']' => Action::BracketMode{forward: true}),
+            ('[' => Action::BracketMode{forward: false}),
+            (key KeyCode::Tab => Action::OutlineMode),
+            ('?' => Action::Help),
+            ('"' => Action::RegisterMode),
+            ('Q' => Action::MacroRecord),
+            ('q' => Action::MacroReplay),
+            ('.' => Action::Repeat),
             ('u' => Action::Undo),
             ('U' => Action::Redo),
 
@@ -105,9 +128,86 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Move(Direction::Left) => "move left",
+            Action::Move(Direction::Down) => "move down a line",
+            Action::Move(Direction::Up) => "move up a line",
+            Action::Move(Direction::Right) => "move right",
+            Action::Extend(Direction::Left) => "extend left",
+            Action::Extend(Direction::Down) => "extend down a line",
+            Action::Extend(Direction::Up) => "extend up a line",
+            Action::Extend(Direction::Right) => "extend right",
+            Action::SplitMode => "split selections...",
+            Action::JumpToMode => "jump to... (<count>g: to offset)",
+            Action::ExtendToMode => "extend to... (<count>G: to offset)",
+            Action::CollapseMode { hex: false } => "keep parts matching a text pattern",
+            Action::CollapseMode { hex: true } => "keep parts matching a hex pattern",
+            Action::CommandMode => "enter a command",
+            Action::SwapCaret => "swap cursor and selection end",
+            Action::CollapseSelection => "collapse selections to cursors",
+            Action::Delete { .. } => "delete selections",
+            Action::Yank { .. } => "yank selections",
+            Action::Paste { after: true, .. } => "paste after selections",
+            Action::Paste { after: false, .. } => "paste before selections",
+            Action::Change { hex: false, .. } => "change selections (text)",
+            Action::Change { hex: true, .. } => "change selections (hex)",
+            Action::Insert { hex: false } => "insert text before selections",
+            Action::Insert { hex: true } => "insert hex before selections",
+            Action::Append { hex: false } => "append text after selections",
+            Action::Append { hex: true } => "append hex after selections",
+            Action::Overwrite { hex: false } => "overwrite with text",
+            Action::Overwrite { hex: true } => "overwrite with hex",
+            Action::RemoveMain => "remove the main selection",
+            Action::RetainMain => "keep only the main selection",
+            Action::SelectPrev => "previous selection becomes main",
+            Action::SelectNext => "next selection becomes main",
+            Action::SelectAll => "select the whole buffer",
+            Action::ReplaceMode { hex: false } => "replace bytes with a character",
+            Action::ReplaceMode { hex: true } => "replace bytes with a hex byte",
+            Action::Measure => "show the main selection's length",
+            Action::MarkMode { set: true } => "set mark <letter>",
+            Action::MarkMode { set: false } => "jump to mark <letter>",
+            Action::BracketMode { forward: true } => "next... (]c: difference)",
+            Action::BracketMode { forward: false } => "previous... ([c: difference)",
+            Action::OutlineMode => "focus the outline panel",
+            Action::Help => "show this help",
+            Action::RegisterMode => "use register <name> for the next yank/delete/change/paste",
+            Action::MacroRecord => "record a macro into register <name>, or stop recording",
+            Action::MacroReplay => "replay the macro in register <name> (<count>q: count times)",
+            Action::Repeat => "repeat the last change (<count>.: count times)",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
+/// Goes to `mode` after a delete or change, telling if copying to the
+/// clipboard failed.
+fn transition_after_copy(
+    mode: impl Mode,
+    dirty: Option<DirtyBytes>,
+    copied: Result<(), String>,
+) -> ModeTransition {
+    match (dirty, copied) {
+        (Some(dirty), Ok(())) => ModeTransition::new_mode_and_dirty(mode, dirty),
+        (Some(dirty), Err(e)) => ModeTransition::new_mode_dirty_and_info(mode, dirty, e),
+        (None, Ok(())) => ModeTransition::new_mode(mode),
+        (None, Err(e)) => ModeTransition::new_mode_and_info(mode, e),
+    }
+}
+
 impl Mode for Normal {
     fn name(&self) -> Cow<'static, str> {
-        format!("NORMAL{}", self.count_state).into()
+        match self.register {
+            Some(register) => format!("NORMAL \"{}{}", register, self.count_state).into(),
+            None => format!("NORMAL{}", self.count_state).into(),
+        }
     }
 
     fn transition(
@@ -120,25 +220,36 @@ impl Mode for Normal {
         if let cmd_count::Transition::Update(new_state) = self.count_state.transition(event) {
             Some(ModeTransition::new_mode(Normal {
                 count_state: new_state,
+                ..*self
             }))
         } else if let Some(action) = DEFAULT_MAPS.event_to_action(event) {
-            Some(match action {
+            let transition = match action {
                 Action::JumpToMode => match self.count_state {
                     cmd_count::State::None => {
                         ModeTransition::new_mode(modes::jumpto::JumpTo { extend: false })
                     }
-                    cmd_count::State::Some { count: offset, .. } => {
-                        ModeTransition::new_mode_and_dirty(
-                            Normal::new(),
-                            current_buffer.map_selections(|region| vec![region.jump_to(offset)]),
-                        )
-                    }
+                    // Counts are file offsets, as shown in the status line
+                    cmd_count::State::Some { count: offset, .. } => match current_buffer
+                        .jump_to_file_offset(offset, jump_window_len(bytes_per_line), bytes_per_line)
+                    {
+                        Ok(dirty) => ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
+                        Err(info) => ModeTransition::new_mode_and_info(Normal::new(), info),
+                    },
                 },
                 Action::ExtendToMode => match self.count_state {
                     cmd_count::State::None => {
                         ModeTransition::new_mode(modes::jumpto::JumpTo { extend: true })
                     }
                     cmd_count::State::Some { count: offset, .. } => {
+                        let loaded = current_buffer.data_start_offset..current_buffer.data_start_offset
+                            + current_buffer.data.len();
+                        if !loaded.contains(&offset) {
+                            return Some(ModeTransition::new_mode_and_info(
+                                Normal::new(),
+                                format!("can't extend to 0x{:x}: it isn't loaded", offset),
+                            ));
+                        }
+                        let offset = offset - current_buffer.data_start_offset;
                         ModeTransition::new_mode_and_dirty(
                             Normal::new(),
                             current_buffer.map_selections(|region| vec![region.extend_to(offset)]),
@@ -146,6 +257,58 @@ impl Mode for Normal {
                     }
                 },
                 Action::SplitMode => ModeTransition::new_mode(modes::split::Split::new()),
+                Action::Help => ModeTransition::new_mode(modes::help::Help::new("normal")),
+                Action::RegisterMode => {
+                    ModeTransition::new_mode(modes::register::RegisterPrompt { normal: *self })
+                }
+                Action::MacroRecord => {
+                    if buffr_collection.macros.recording().is_some() {
+                        buffr_collection.macros.stop_recording();
+                        ModeTransition::new_mode(Normal::new())
+                    } else {
+                        ModeTransition::new_mode(modes::macros::MacroPrompt {
+                            record: true,
+                            count: 1,
+                        })
+                    }
+                }
+                Action::MacroReplay => ModeTransition::new_mode(modes::macros::MacroPrompt {
+                    record: false,
+                    count: self.count_state.to_count(),
+                }),
+                Action::Repeat => {
+                    let macros = &mut buffr_collection.macros;
+                    if macros.get(LAST_CHANGE).is_none() {
+                        return Some(ModeTransition::new_mode_and_info(
+                            Normal::new(),
+                            "no change to repeat".into(),
+                        ));
+                    }
+                    macros.request_replay(LAST_CHANGE, self.count_state.to_count());
+                    ModeTransition::new_mode(Normal::new())
+                }
+                Action::Delete { .. } | Action::Yank { .. } | Action::Change { .. }
+                    if self.register == Some(SEARCH_REGISTER) =>
+                {
+                    ModeTransition::new_mode_and_info(
+                        Normal::new(),
+                        format!("register {} is read-only", SEARCH_REGISTER),
+                    )
+                }
+                Action::MarkMode { set } => ModeTransition::new_mode(modes::mark::MarkPrompt { set }),
+                Action::OutlineMode => {
+                    if current_buffer.outline.is_some() {
+                        ModeTransition::new_mode(modes::outline::OutlineFocus())
+                    } else {
+                        ModeTransition::new_mode_and_info(
+                            Normal::new(),
+                            "no outline: use :template apply <file>".into(),
+                        )
+                    }
+                }
+                Action::BracketMode { forward } => {
+                    ModeTransition::new_mode(modes::bracket::Bracket { forward })
+                }
                 Action::Insert { hex } => ModeTransition::new_mode_and_dirty(
                     modes::insert::Insert {
                         hex,
@@ -216,47 +379,50 @@ impl Mode for Normal {
                     current_buffer.map_selections(|region| vec![region.collapse()]),
                 ),
                 Action::Delete { register } => {
-                    current_buffer.yank_selections(register);
-                    if !current_buffer.data.is_empty() {
+                    // A failed clipboard copy still leaves the bytes in the register
+                    let copied = buffr_collection.yank_selections(self.register.unwrap_or(register));
+                    let current_buffer = buffr_collection.current_mut();
+                    let dirty = (!current_buffer.data.is_empty()).then(|| {
                         let delta = ops::deletion(&current_buffer.data, &current_buffer.selection);
-                        ModeTransition::DirtyBytes(current_buffer.apply_delta(delta))
-                    } else {
-                        ModeTransition::None
-                    }
+                        current_buffer.apply_delta(delta)
+                    });
+                    transition_after_copy(Normal::new(), dirty, copied)
                 }
                 Action::Change { hex, register } => {
-                    current_buffer.yank_selections(register);
-                    if !current_buffer.data.is_empty() {
+                    let copied = buffr_collection.yank_selections(self.register.unwrap_or(register));
+                    let current_buffer = buffr_collection.current_mut();
+                    let dirty = (!current_buffer.data.is_empty()).then(|| {
                         let delta = ops::deletion(&current_buffer.data, &current_buffer.selection);
-                        ModeTransition::new_mode_and_dirty(
-                            modes::insert::Insert {
-                                hex,
-                                mode: InsertionMode::Insert,
-                                hex_half: None,
-                            },
-                            current_buffer.apply_delta(delta),
-                        )
-                    } else {
-                        ModeTransition::new_mode(modes::insert::Insert {
-                            hex,
-                            mode: InsertionMode::Insert,
-                            hex_half: None,
-                        })
-                    }
+                        current_buffer.apply_delta(delta)
+                    });
+                    let insert = modes::insert::Insert {
+                        hex,
+                        mode: InsertionMode::Insert,
+                        hex_half: None,
+                    };
+                    transition_after_copy(insert, dirty, copied)
                 }
                 Action::Yank { register } => {
-                    current_buffer.yank_selections(register);
-                    ModeTransition::None
+                    match buffr_collection.yank_selections(self.register.unwrap_or(register)) {
+                        Ok(()) => ModeTransition::new_mode(Normal::new()),
+                        Err(e) => ModeTransition::new_mode_and_info(Normal::new(), e),
+                    }
                 }
                 Action::Paste { register, after } => {
+                    let contents =
+                        match buffr_collection.register_contents(self.register.unwrap_or(register)) {
+                            Ok(contents) => contents.unwrap_or_else(|| vec![Rope::default()]),
+                            Err(e) => return Some(ModeTransition::new_mode_and_info(Normal::new(), e)),
+                        };
+                    let current_buffer = buffr_collection.current_mut();
                     let delta = ops::paste(
                         &current_buffer.data,
                         &current_buffer.selection,
-                        current_buffer.registers.get(&register).unwrap_or(&vec![vec![]]),
+                        &contents,
                         after,
                         self.count_state.to_count(),
                     );
-                    ModeTransition::DirtyBytes(current_buffer.apply_delta(delta))
+                    ModeTransition::new_mode_and_dirty(Normal::new(), current_buffer.apply_delta(delta))
                 }
                 // selection indexing in the UI starts at 1
                 // hence we check for count > 0 and offset by -1
@@ -310,7 +476,7 @@ impl Mode for Normal {
                         current_buffer.selection.main().len()
                     ),
                 ),
-                Action::CommandMode => ModeTransition::new_mode(modes::command::Command::new()),
+                Action::CommandMode => ModeTransition::new_mode(modes::command::Command::new("normal")),
                 Action::Undo => current_buffer.perform_undo().map_or_else(
                     || {
                         ModeTransition::new_mode_and_info(
@@ -329,7 +495,16 @@ impl Mode for Normal {
                     },
                     |dirty| ModeTransition::new_mode_and_dirty(Normal::new(), dirty),
                 ),
+            };
+            // A register only applies to the action right after it
+            Some(match transition {
+                ModeTransition::DirtyBytes(dirty) if self.register.is_some() => {
+                    ModeTransition::new_mode_and_dirty(Normal::new(), dirty)
+                }
+                transition => transition,
             })
+        } else if self.register.is_some() && matches!(event, Event::Key(_)) {
+            Some(ModeTransition::new_mode(Normal::new()))
         } else {
             None
         }
@@ -343,6 +518,63 @@ impl Normal {
     pub fn new() -> Normal {
         Normal {
             count_state: cmd_count::State::None,
+            register: None,
+        }
+    }
+
+    pub fn with_register(self, register: char) -> Normal {
+        Normal {
+            register: Some(register),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CurrentBuffer;
+
+    fn press(keys: &str, buffr_collection: &mut BuffrCollection) -> Option<String> {
+        let mut mode: Box<dyn Mode> = Box::new(Normal::new());
+        let mut info = None;
+        for ch in keys.chars() {
+            let event = Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+            match mode.transition(&event, buffr_collection, 16) {
+                Some(ModeTransition::NewMode(next)) | Some(ModeTransition::ModeAndDirtyBytes(next, _)) => {
+                    mode = next
+                }
+                Some(ModeTransition::ModeAndInfo(next, text))
+                | Some(ModeTransition::ModeDirtyBytesAndInfo(next, _, text)) => {
+                    mode = next;
+                    info = Some(text);
+                }
+                _ => {}
+            }
         }
+        info
+    }
+
+    fn register(buffr_collection: &BuffrCollection, name: char) -> Option<Vec<Vec<u8>>> {
+        let selections = buffr_collection.registers.get(name)?;
+        Some(selections.iter().map(Vec::from).collect())
+    }
+
+    #[test]
+    fn test_registers() {
+        let buffer = CurrentBuffer::from_data_and_path(b"abcd".to_vec(), None::<&str>);
+        let mut buffr_collection = BuffrCollection::with_current_buffer(buffer);
+        press("\"ayl\"Ay", &mut buffr_collection);
+        assert_eq!(register(&buffr_collection, 'a'), Some(vec![b"ab".to_vec()]));
+
+        assert_eq!(press("\"/y", &mut buffr_collection), Some("register / is read-only".into()));
+        assert_eq!(register(&buffr_collection, '/'), None);
+
+        // A register is dropped by actions that don't use it
+        press("\"b;y", &mut buffr_collection);
+        assert_eq!(register(&buffr_collection, 'b'), None);
+        assert_eq!(register(&buffr_collection, '"'), Some(vec![b"b".to_vec()]));
+        press("\"czy", &mut buffr_collection);
+        assert_eq!(register(&buffr_collection, 'c'), None);
     }
 }
@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use lazy_static::lazy_static;
+
+use crate::keymap::{Binding, Describe, KeyMap};
+use crate::modes::{
+    command::Command,
+    mode::{jump_window_len, Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::BuffrCollection;
+
+/// Moves through the outline panel; the bytes of the field under the cursor
+/// are kept selected in the hex view.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OutlineFocus();
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Action {
+    Up,
+    Down,
+    First,
+    Last,
+    Expand,
+    Collapse,
+    Toggle,
+    Edit,
+    Leave,
+}
+
+fn default_maps() -> KeyMap<Action> {
+    KeyMap {
+        maps: keys!(
+            (key KeyCode::Up => Action::Up),
+            ('k' => Action::Up),
+            (key KeyCode::Down => Action::Down),
+            ('j' => Action::Down),
+            ('g' => Action::First),
+            ('G' => Action::Last),
+            (key KeyCode::Right => Action::Expand),
+            ('l' => Action::Expand),
+            (key KeyCode::Left => Action::Collapse),
+            ('h' => Action::Collapse),
+            (key KeyCode::Enter => Action::Toggle),
+            (' ' => Action::Toggle),
+            ('e' => Action::Edit),
+            (key KeyCode::Tab => Action::Leave),
+            (key KeyCode::Esc => Action::Leave),
+            ('q' => Action::Leave)
+        ),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
+}
+
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Up => "previous field",
+            Action::Down => "next field",
+            Action::First => "first field",
+            Action::Last => "last field",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Toggle => "expand or collapse",
+            Action::Edit => "edit the field's value",
+            Action::Leave => "back to the hex view",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
+impl Mode for OutlineFocus {
+    fn name(&self) -> Cow<'static, str> {
+        "OUTLINE".into()
+    }
+
+    fn transition(
+        &self,
+        evt: &Event,
+        buffr_collection: &mut BuffrCollection,
+        bytes_per_line: usize,
+    ) -> Option<ModeTransition> {
+        let action = DEFAULT_MAPS.event_to_action(evt)?;
+        let current_buffer = buffr_collection.current_mut();
+        let outline = match current_buffer.outline.as_mut() {
+            Some(outline) => outline,
+            None => return Some(ModeTransition::new_mode(Normal::new())),
+        };
+
+        match action {
+            Action::Up => outline.move_cursor(-1),
+            Action::Down => outline.move_cursor(1),
+            Action::First => outline.cursor = 0,
+            Action::Last => outline.cursor_to_last(),
+            Action::Expand => outline.expand(),
+            Action::Collapse => outline.collapse(),
+            Action::Toggle => outline.toggle(),
+            Action::Edit => {
+                return Some(match outline.selected() {
+                    Some(node) if node.editor.is_some() => ModeTransition::new_mode(Command {
+                        command: "tset ".into(),
+                        cursor: 5,
+                        from: "outline",
+                    }),
+                    _ => ModeTransition::new_mode_and_info(
+                        OutlineFocus(),
+                        "this field can't be edited".into(),
+                    ),
+                })
+            }
+            Action::Leave => return Some(ModeTransition::new_mode(Normal::new())),
+        }
+
+        let range = match outline.selected() {
+            Some(node) => node.range.clone(),
+            None => return Some(ModeTransition::new_mode(OutlineFocus())),
+        };
+        Some(
+            match current_buffer.select_file_range(
+                range,
+                jump_window_len(bytes_per_line),
+                bytes_per_line,
+            ) {
+                Ok(dirty) => ModeTransition::new_mode_and_dirty(OutlineFocus(), dirty),
+                Err(info) => ModeTransition::new_mode_and_info(OutlineFocus(), info),
+            },
+        )
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
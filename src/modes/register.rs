@@ -0,0 +1,52 @@
+use std::borrow::Cow;
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+
+use crate::modes::{
+    mode::{Mode, ModeTransition},
+    normal::Normal,
+};
+use crate::BuffrCollection;
+
+/// Holds the last search pattern; it can be pasted but not yanked into.
+pub const SEARCH_REGISTER: char = '/';
+
+/// Yanks into it are copied to the system clipboard, and pastes read it.
+pub const CLIPBOARD_REGISTER: char = '+';
+
+/// `"` is the default register, letters are named registers (uppercase ones
+/// append to their lowercase register), `/` is `SEARCH_REGISTER` and `+` is
+/// `CLIPBOARD_REGISTER`.
+pub fn is_valid_name(name: char) -> bool {
+    name.is_ascii_alphanumeric() || name == '"' || name == SEARCH_REGISTER || name == CLIPBOARD_REGISTER
+}
+
+/// Waits for a register name after `"`, then goes back to normal mode with the
+/// register chosen for the next yank, delete, change or paste.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RegisterPrompt {
+    pub normal: Normal,
+}
+
+impl Mode for RegisterPrompt {
+    fn name(&self) -> Cow<'static, str> {
+        "REGISTER".into()
+    }
+
+    fn transition(&self, evt: &Event, _: &mut BuffrCollection, _: usize) -> Option<ModeTransition> {
+        match evt {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(name),
+                ..
+            }) if is_valid_name(*name) => {
+                Some(ModeTransition::new_mode(self.normal.with_register(*name)))
+            }
+            Event::Key(_) => Some(ModeTransition::new_mode(Normal::new())),
+            _ => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
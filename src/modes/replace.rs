@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
     mode::{Mode, ModeTransition},
     normal::Normal,
@@ -35,6 +35,18 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Null => "replace with null bytes",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 impl Mode for Replace {
     fn name(&self) -> Cow<'static, str> {
         match (self.hex, self.hex_half) {
@@ -7,10 +7,11 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ops::Range;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
     mode::{Mode, ModeTransition},
     normal::Normal,
+    register::SEARCH_REGISTER,
 };
 use crate::{CurrentBuffer, BuffrCollection};
 
@@ -161,6 +162,26 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::InsertNull => "insert a null byte",
+            Action::InsertWilcard => "insert a wildcard",
+            Action::RemoveLast => "delete before the cursor",
+            Action::RemoveThis => "delete under the cursor",
+            Action::CursorLeft => "move left",
+            Action::CursorRight => "move right",
+            Action::SwitchInputMode => "switch between text and hex input",
+            Action::Finish => "accept the pattern",
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 impl Search {
     pub fn new(next: impl SearchAcceptor, hex: bool) -> Search {
         Search {
@@ -218,6 +239,13 @@ impl Mode for Search {
                 }
                 Action::Cancel => return Some(ModeTransition::new_mode(Normal::new())),
                 Action::Finish => {
+                    // Patterns with wildcards have no bytes to paste
+                    match pattern.as_basic_slice() {
+                        Some(bytes) => {
+                            buffr_collection.registers.set(SEARCH_REGISTER, vec![bytes.into()])
+                        }
+                        None => buffr_collection.registers.clear(SEARCH_REGISTER),
+                    }
                     return Some(self.next.borrow().as_ref().unwrap().apply_search(
                         pattern,
                         buffr_collection,
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 
-use crate::keymap::KeyMap;
+use crate::keymap::{Binding, Describe, KeyMap};
 use crate::modes::{
     mode::{Mode, ModeTransition},
     normal::Normal,
@@ -45,6 +45,25 @@ lazy_static! {
     static ref DEFAULT_MAPS: KeyMap<Action> = default_maps();
 }
 
+impl Describe for Action {
+    fn describe(&self) -> &'static str {
+        match self {
+            Action::Width(1) => "into bytes",
+            Action::Width(2) => "into words (2 bytes)",
+            Action::Width(4) => "into dwords (4 bytes)",
+            Action::Width(8) => "into qwords (8 bytes)",
+            Action::Width(_) => "into owords (16 bytes)",
+            Action::Null => "at null bytes",
+            Action::Search { hex: false } => "at matches of a text pattern",
+            Action::Search { hex: true } => "at matches of a hex pattern",
+        }
+    }
+}
+
+pub fn bindings() -> Vec<Binding> {
+    DEFAULT_MAPS.bindings()
+}
+
 impl SearchAcceptor for Split {
     fn apply_search(&self, pattern: Pattern, buffr_collection: &mut BuffrCollection, _: usize) -> ModeTransition {
         let current_buffer = buffr_collection.current_mut();
@@ -129,8 +148,7 @@ impl Mode for Split {
                 ),
                 Action::Null => self.apply_search(
                     Pattern {
-                        pieces: std::iter::repeat(PatternPiece::Literal(0u8))
-                            .take(count)
+                        pieces: std::iter::repeat_n(PatternPiece::Literal(0u8), count)
                             .collect(),
                     },
                     buffr_collection,
@@ -1,5 +1,6 @@
 use super::byte_rope::*;
 use super::selection::*;
+use xi_rope::tree::TreeBuilder;
 use xi_rope::{DeltaBuilder, Interval};
 
 pub fn deletion(base: &Rope, selection: &Selection) -> RopeDelta {
@@ -14,6 +15,28 @@ pub fn deletion(base: &Rope, selection: &Selection) -> RopeDelta {
     builder.build()
 }
 
+pub fn overwrite_range(base: &Rope, start: usize, bytes: &[u8]) -> RopeDelta {
+    let mut builder = DeltaBuilder::new(base.len());
+    builder.replace(
+        Interval::new(start, start + bytes.len()),
+        Rope::from(bytes.to_vec()).into_node(),
+    );
+    builder.build()
+}
+
+/// Overwrites `bytes` from every caret, growing the data where they run past
+/// the end. Carets must be at least `bytes.len()` apart.
+pub fn overwrite_at_carets(base: &Rope, selection: &Selection, bytes: &[u8]) -> RopeDelta {
+    let written = Rope::from(bytes.to_vec());
+    let mut builder = DeltaBuilder::new(base.len());
+    for region in selection.iter() {
+        let end = std::cmp::min(base.len(), region.caret + bytes.len());
+        builder.replace(Interval::new(region.caret, end), written.clone().into_node());
+    }
+
+    builder.build()
+}
+
 pub fn backspace(base: &Rope, selection: &Selection) -> RopeDelta {
     let mut builder = DeltaBuilder::new(base.len());
     for region in selection.iter() {
@@ -54,7 +77,7 @@ pub fn insert(base: &Rope, selection: &Selection, text: impl Into<Rope>) -> Rope
 pub fn paste(
     base: &Rope,
     selection: &Selection,
-    register_contents: &[Vec<u8>],
+    register_contents: &[Rope],
     after: bool,
     count: usize,
 ) -> RopeDelta {
@@ -70,17 +93,11 @@ pub fn paste(
             region.min()
         };
         let iv = Interval::new(insert_pos, insert_pos);
-        builder.replace(
-            iv,
-            Rope::from(
-                std::iter::repeat(pasted)
-                    .take(count)
-                    .flatten()
-                    .copied()
-                    .collect::<Vec<_>>(),
-            )
-            .into_node(),
-        );
+        let mut repeated = TreeBuilder::new();
+        for _ in 0..count {
+            repeated.push(pasted.0.clone());
+        }
+        builder.replace(iv, repeated.build());
     }
 
     builder.build()
@@ -126,3 +143,31 @@ pub fn replace(base: &Rope, selection: &Selection, ch: u8) -> RopeDelta {
 
     builder.build()
 }
+
+/// Overwrites every selection with bytes from `fill`, which is given the
+/// length of each selection in turn.
+pub fn fill(base: &Rope, selection: &Selection, mut fill: impl FnMut(usize) -> Vec<u8>) -> RopeDelta {
+    let mut builder = DeltaBuilder::new(base.len());
+    for region in selection.iter() {
+        let iv = Interval::new(region.min(), std::cmp::min(base.len(), region.max() + 1));
+        if !iv.is_empty() {
+            builder.replace(iv, Rope::from(fill(iv.end - iv.start)).into_node());
+        }
+    }
+
+    builder.build()
+}
+
+/// Replaces the bytes of every selection with `transform` of them.
+pub fn transform(base: &Rope, selection: &Selection, mut transform: impl FnMut(&[u8]) -> Vec<u8>) -> RopeDelta {
+    let mut builder = DeltaBuilder::new(base.len());
+    for region in selection.iter() {
+        let iv = Interval::new(region.min(), std::cmp::min(base.len(), region.max() + 1));
+        if !iv.is_empty() {
+            let transformed = transform(&base.slice_to_cow(iv));
+            builder.replace(iv, Rope::from(transformed).into_node());
+        }
+    }
+
+    builder.build()
+}
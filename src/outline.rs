@@ -0,0 +1,523 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::byte_source::ByteSource;
+use crate::executable::Executable;
+use crate::partitions::Partitions;
+use crate::template::Template;
+
+/// An integer field's layout in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntFormat {
+    pub size: usize,
+    pub signed: bool,
+    pub big_endian: bool,
+}
+
+impl IntFormat {
+    /// `u8`..`u64` or `i8`..`i64`, with an optional `le`/`be` suffix (little
+    /// endian by default).
+    pub fn parse(name: &str) -> Option<IntFormat> {
+        let signed = match name.chars().next()? {
+            'u' => false,
+            'i' => true,
+            _ => return None,
+        };
+        let (bits, big_endian) = if let Some(bits) = name[1..].strip_suffix("le") {
+            (bits, false)
+        } else if let Some(bits) = name[1..].strip_suffix("be") {
+            (bits, true)
+        } else {
+            (&name[1..], false)
+        };
+        let size = match bits {
+            "8" => 1,
+            "16" => 2,
+            "32" => 4,
+            "64" => 8,
+            _ => return None,
+        };
+        Some(IntFormat {
+            size,
+            signed,
+            big_endian,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        let sign = if self.signed { 'i' } else { 'u' };
+        if self.size == 1 {
+            format!("{}8", sign)
+        } else {
+            let endian = if self.big_endian { "be" } else { "le" };
+            format!("{}{}{}", sign, self.size * 8, endian)
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> i128 {
+        let mut raw: u64 = 0;
+        for i in 0..self.size {
+            let byte = if self.big_endian {
+                bytes[i]
+            } else {
+                bytes[self.size - 1 - i]
+            };
+            raw = (raw << 8) | byte as u64;
+        }
+        let bits = self.size * 8;
+        if self.signed && bits < 64 && (raw >> (bits - 1)) & 1 == 1 {
+            raw as i128 - (1i128 << bits)
+        } else if self.signed {
+            raw as i64 as i128
+        } else {
+            raw as i128
+        }
+    }
+
+    pub fn encode(&self, value: i128) -> Result<Vec<u8>, String> {
+        let bits = self.size as u32 * 8;
+        let (min, max) = if self.signed {
+            (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+        } else {
+            (0, (1i128 << bits) - 1)
+        };
+        if value < min || value > max {
+            return Err(format!("{} doesn't fit in {}", value, self.name()));
+        }
+        let raw = value as u64;
+        let mut bytes: Vec<u8> = (0..self.size).map(|i| (raw >> (8 * i)) as u8).collect();
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    pub fn format(&self, value: i128) -> String {
+        if self.signed {
+            format!("{}", value)
+        } else {
+            format!("{} (0x{:x})", value, value)
+        }
+    }
+}
+
+/// Parses decimal, `0x` hex, `0b` binary or `0o` octal, optionally negative.
+pub fn parse_int(text: &str) -> Result<i128, String> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let lower = digits.to_ascii_lowercase();
+    let (radix, digits) = match lower.get(..2) {
+        Some("0x") => (16, &lower[2..]),
+        Some("0b") => (2, &lower[2..]),
+        Some("0o") => (8, &lower[2..]),
+        _ => (10, &lower[..]),
+    };
+    let value = i128::from_str_radix(&digits.replace('_', ""), radix)
+        .map_err(|_| format!("not a number: {}", text))?;
+    Ok(if negative { -value } else { value })
+}
+
+/// How a node's bytes can be rewritten from a typed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Editor {
+    Int(IntFormat),
+    Enum(IntFormat, Vec<(String, i128)>),
+    /// Fixed-size text, padded with NULs
+    Text(usize),
+}
+
+impl Editor {
+    pub fn encode(&self, input: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Editor::Int(format) => format.encode(parse_int(input)?),
+            Editor::Enum(format, variants) => {
+                let value = match variants.iter().find(|(name, _)| name == input.trim()) {
+                    Some((_, value)) => *value,
+                    None => parse_int(input)?,
+                };
+                format.encode(value)
+            }
+            Editor::Text(len) => {
+                let mut bytes = input.as_bytes().to_vec();
+                if bytes.len() > *len {
+                    return Err(format!("text is longer than {} bytes", len));
+                }
+                bytes.resize(*len, 0);
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineNode {
+    pub label: String,
+    pub value: String,
+    /// File offsets of the bytes the node covers
+    pub range: Range<u64>,
+    pub editor: Option<Editor>,
+    pub children: Vec<OutlineNode>,
+    pub expanded: bool,
+}
+
+impl OutlineNode {
+    pub fn new(label: impl Into<String>, value: impl Into<String>, range: Range<u64>) -> Self {
+        OutlineNode {
+            label: label.into(),
+            value: value.into(),
+            range,
+            editor: None,
+            children: vec![],
+            expanded: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_editor(self, editor: Editor) -> Self {
+        OutlineNode {
+            editor: Some(editor),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_children(self, children: Vec<OutlineNode>) -> Self {
+        OutlineNode { children, ..self }
+    }
+}
+
+/// Names for a field's known values.
+pub type Names = Option<fn(u64) -> Option<&'static str>>;
+
+/// Fields of a header or table entry: label, offset, size and value names.
+pub type Layout = [(&'static str, usize, usize, Names)];
+
+/// Bytes of a header or table, read in one go and decoded in the file's byte order.
+pub struct Table {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    pub big_endian: bool,
+}
+
+impl Table {
+    pub fn read(
+        source: &dyn ByteSource,
+        offset: u64,
+        len: usize,
+        big_endian: bool,
+        what: &str,
+    ) -> Result<Table, String> {
+        let bytes = source
+            .read_exact_at(offset, len)
+            .map_err(|_| format!("{} at 0x{:x} is cut short", what, offset))?;
+        Ok(Table {
+            offset,
+            bytes,
+            big_endian,
+        })
+    }
+
+    pub fn int_format(&self, size: usize) -> IntFormat {
+        IntFormat {
+            size,
+            signed: false,
+            big_endian: self.big_endian,
+        }
+    }
+
+    /// The unsigned field at `at`, or 0 if the table is too short for it.
+    pub fn get(&self, at: usize, size: usize) -> u64 {
+        match self.bytes.get(at..at + size) {
+            Some(bytes) => self.int_format(size).decode(bytes) as u64,
+            None => 0,
+        }
+    }
+
+    pub fn get_field(&self, base: usize, layout: &Layout, label: &str) -> u64 {
+        let &(_, at, size, _) = layout
+            .iter()
+            .find(|field| field.0 == label)
+            .expect("field is in the layout");
+        self.get(base + at, size)
+    }
+
+    /// Editable nodes for the fields of `layout` that start at `base`.
+    pub fn fields(&self, base: usize, layout: &Layout) -> Vec<OutlineNode> {
+        layout
+            .iter()
+            .filter(|&&(_, at, size, _)| base + at + size <= self.bytes.len())
+            .map(|&(label, at, size, names)| {
+                let value = self.get(base + at, size);
+                let format = self.int_format(size);
+                let text = match names.and_then(|names| names(value)) {
+                    Some(name) => format!("{} (0x{:x})", name, value),
+                    None => format.format(value as i128),
+                };
+                let start = self.offset + (base + at) as u64;
+                OutlineNode::new(label, text, start..start + size as u64)
+                    .with_editor(Editor::Int(format))
+            })
+            .collect()
+    }
+
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.offset + self.bytes.len() as u64
+    }
+}
+
+/// What an outline was built from, so it can be rebuilt after edits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutlineSource {
+    Template { path: PathBuf, offset: u64 },
+    /// ELF or PE headers
+    Executable,
+    /// MBR and GPT partition tables
+    Partitions,
+}
+
+impl OutlineSource {
+    /// Builds the tree from the current contents of `bytes`.
+    pub fn build(&self, bytes: &dyn ByteSource) -> Result<Outline, String> {
+        match self {
+            OutlineSource::Template { path, offset } => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+                let template = Template::parse(&text)?;
+                let root = template.apply(bytes, *offset)?;
+                Ok(Outline::new(
+                    format!("{} @ 0x{:x}", template.root_name(), offset),
+                    self.clone(),
+                    vec![root],
+                ))
+            }
+            OutlineSource::Executable => {
+                let executable = Executable::parse(bytes)?;
+                Ok(Outline::new(executable.format, self.clone(), executable.roots))
+            }
+            OutlineSource::Partitions => {
+                let partitions = Partitions::parse(bytes)?;
+                Ok(Outline::new(partitions.title, self.clone(), partitions.roots))
+            }
+        }
+    }
+}
+
+/// A collapsible tree shown in a panel next to the hex view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outline {
+    pub title: String,
+    pub source: OutlineSource,
+    pub roots: Vec<OutlineNode>,
+    /// Index into the visible (expanded) lines
+    pub cursor: usize,
+    /// First visible line shown in the panel
+    pub scroll: usize,
+}
+
+impl Outline {
+    pub fn new(title: impl Into<String>, source: OutlineSource, mut roots: Vec<OutlineNode>) -> Self {
+        for root in roots.iter_mut() {
+            root.expanded = true;
+        }
+        Outline {
+            title: title.into(),
+            source,
+            roots,
+            cursor: 0,
+            scroll: 0,
+        }
+    }
+
+    /// Paths (child indices from the roots) of the lines currently shown.
+    fn visible_paths(&self) -> Vec<Vec<usize>> {
+        fn walk(nodes: &[OutlineNode], prefix: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+            for (i, node) in nodes.iter().enumerate() {
+                prefix.push(i);
+                out.push(prefix.clone());
+                if node.expanded {
+                    walk(&node.children, prefix, out);
+                }
+                prefix.pop();
+            }
+        }
+        let mut out = vec![];
+        walk(&self.roots, &mut vec![], &mut out);
+        out
+    }
+
+    fn node(&self, path: &[usize]) -> &OutlineNode {
+        let mut node = &self.roots[path[0]];
+        for &i in &path[1..] {
+            node = &node.children[i];
+        }
+        node
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut OutlineNode {
+        let mut node = &mut self.roots[path[0]];
+        for &i in &path[1..] {
+            node = &mut node.children[i];
+        }
+        node
+    }
+
+    /// Visible lines as (depth, node).
+    pub fn visible(&self) -> Vec<(usize, &OutlineNode)> {
+        self.visible_paths()
+            .into_iter()
+            .map(|path| (path.len() - 1, self.node(&path)))
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&OutlineNode> {
+        self.visible_paths()
+            .get(self.cursor)
+            .map(|path| self.node(path))
+    }
+
+    pub fn selected_mut(&mut self) -> Option<&mut OutlineNode> {
+        let path = self.visible_paths().get(self.cursor)?.clone();
+        Some(self.node_mut(&path))
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        let last = self.visible_paths().len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize + delta).clamp(0, last) as usize;
+    }
+
+    pub fn cursor_to_last(&mut self) {
+        self.cursor = self.visible_paths().len().saturating_sub(1);
+    }
+
+    /// Expands the selected node, or moves into it if it's already expanded.
+    pub fn expand(&mut self) {
+        if let Some(node) = self.selected_mut() {
+            if node.children.is_empty() {
+                return;
+            }
+            if node.expanded {
+                self.cursor += 1;
+            } else {
+                node.expanded = true;
+            }
+        }
+    }
+
+    /// Collapses the selected node, or moves to its parent if it's collapsed.
+    pub fn collapse(&mut self) {
+        let paths = self.visible_paths();
+        let path = match paths.get(self.cursor) {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        let node = self.node_mut(&path);
+        if node.expanded && !node.children.is_empty() {
+            node.expanded = false;
+        } else if path.len() > 1 {
+            let parent = &path[..path.len() - 1];
+            self.cursor = paths.iter().position(|p| p == parent).unwrap_or(0);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(node) = self.selected_mut() {
+            node.expanded = !node.expanded && !node.children.is_empty();
+        }
+    }
+
+    /// Replaces the tree with a rebuilt one, keeping expanded nodes expanded.
+    pub fn replace_roots(&mut self, mut roots: Vec<OutlineNode>) {
+        fn copy_expansion(old: &[OutlineNode], new: &mut [OutlineNode]) {
+            for (old, new) in old.iter().zip(new.iter_mut()) {
+                if old.label == new.label {
+                    new.expanded = old.expanded;
+                    copy_expansion(&old.children, &mut new.children);
+                }
+            }
+        }
+        copy_expansion(&self.roots, &mut roots);
+        self.roots = roots;
+        self.move_cursor(0);
+    }
+
+    /// Rebuilds the tree from its source, e.g. after its bytes were edited.
+    pub fn rebuild(&mut self, bytes: &dyn ByteSource) -> Result<(), String> {
+        let rebuilt = self.source.build(bytes)?;
+        self.title = rebuilt.title;
+        self.replace_roots(rebuilt.roots);
+        Ok(())
+    }
+
+    /// Adjusts `scroll` so the cursor is within `height` lines.
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if height > 0 && self.cursor >= self.scroll + height {
+            self.scroll = self.cursor + 1 - height;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Stores the low `size` bytes of `value` at `at`, little endian, for
+    /// building headers in parser tests.
+    pub fn put(bytes: &mut [u8], at: usize, value: u64, size: usize) {
+        bytes[at..at + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    #[test]
+    fn test_int_format() {
+        let u16be = IntFormat {
+            size: 2,
+            signed: false,
+            big_endian: true,
+        };
+        assert_eq!(u16be.encode(0x1234).unwrap(), vec![0x12, 0x34]);
+        assert_eq!(u16be.decode(&[0x12, 0x34]), 0x1234);
+        assert!(u16be.encode(0x10000).is_err());
+
+        let i32le = IntFormat {
+            size: 4,
+            signed: true,
+            big_endian: false,
+        };
+        assert_eq!(i32le.encode(-2).unwrap(), vec![0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(i32le.decode(&[0xfe, 0xff, 0xff, 0xff]), -2);
+        assert_eq!(parse_int("-0x10").unwrap(), -16);
+    }
+
+    #[test]
+    fn test_outline_navigation() {
+        let tree = vec![OutlineNode::new("root", "", 0..8).with_children(vec![
+            OutlineNode::new("a", "1", 0..4),
+            OutlineNode::new("b", "", 4..8).with_children(vec![OutlineNode::new("c", "2", 4..8)]),
+        ])];
+        let mut outline = Outline::new(
+            "test",
+            OutlineSource::Template {
+                path: "x.tpl".into(),
+                offset: 0,
+            },
+            tree,
+        );
+        assert_eq!(outline.visible().len(), 3);
+
+        outline.move_cursor(2);
+        outline.expand();
+        assert_eq!(outline.visible().len(), 4);
+        outline.expand();
+        assert_eq!(outline.selected().unwrap().label, "c");
+        outline.collapse();
+        assert_eq!(outline.selected().unwrap().label, "b");
+
+        let rebuilt = outline.roots.clone();
+        outline.replace_roots(rebuilt);
+        assert_eq!(outline.visible().len(), 4);
+    }
+}
@@ -0,0 +1,450 @@
+use crate::byte_source::ByteSource;
+use crate::checksum::crc32;
+use crate::outline::{Layout, OutlineNode, Table};
+
+const MBR_LEN: usize = 512;
+const MBR_ENTRIES_AT: usize = 446;
+const MBR_ENTRY_LEN: usize = 16;
+const MBR_SECTOR_LEN: u64 = 512;
+/// Extended boot records followed at most, so a looping chain ends
+const MAX_LOGICAL_PARTITIONS: usize = 256;
+
+const GPT_SIGNATURE: &[u8] = b"EFI PART";
+/// Largest GPT entry array read
+const MAX_GPT_ENTRIES_LEN: usize = 1 << 20;
+const GPT_MIN_HEADER_LEN: usize = 92;
+const GPT_MIN_ENTRY_LEN: usize = 128;
+
+const MBR_ENTRY: &Layout = &[
+    ("status", 0, 1, None),
+    ("type", 4, 1, Some(mbr_type_name)),
+    ("first LBA", 8, 4, None),
+    ("sectors", 12, 4, None),
+];
+
+const GPT_HEADER: &Layout = &[
+    ("revision", 8, 4, None),
+    ("header size", 12, 4, None),
+    ("header CRC32", 16, 4, None),
+    ("current LBA", 24, 8, None),
+    ("backup LBA", 32, 8, None),
+    ("first usable LBA", 40, 8, None),
+    ("last usable LBA", 48, 8, None),
+    ("entries LBA", 72, 8, None),
+    ("number of entries", 80, 4, None),
+    ("entry size", 84, 4, None),
+    ("entries CRC32", 88, 4, None),
+];
+
+const GPT_ENTRY: &Layout = &[
+    ("first LBA", 32, 8, None),
+    ("last LBA", 40, 8, None),
+    ("attributes", 48, 8, None),
+];
+
+fn mbr_type_name(value: u64) -> Option<&'static str> {
+    Some(match value {
+        0x01 => "FAT12",
+        0x04 => "FAT16 <32M",
+        0x05 => "Extended",
+        0x06 => "FAT16",
+        0x07 => "NTFS/exFAT",
+        0x0b => "FAT32",
+        0x0c => "FAT32 LBA",
+        0x0e => "FAT16 LBA",
+        0x0f => "Extended LBA",
+        0x82 => "Linux swap",
+        0x83 => "Linux",
+        0x85 => "Linux extended",
+        0x8e => "Linux LVM",
+        0xee => "GPT protective",
+        0xef => "EFI System",
+        _ => return None,
+    })
+}
+
+fn is_extended(mbr_type: u64) -> bool {
+    matches!(mbr_type, 0x05 | 0x0f | 0x85)
+}
+
+fn gpt_type_name(guid: &str) -> Option<&'static str> {
+    Some(match guid {
+        "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => "EFI System",
+        "21686148-6449-6E6F-744E-656564454649" => "BIOS boot",
+        "0FC63DAF-8483-4772-8E79-3D69D8477DE4" => "Linux filesystem",
+        "4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709" => "Linux root (x86-64)",
+        "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F" => "Linux swap",
+        "E6D6D379-F507-44C2-A23C-238F2A3DF928" => "Linux LVM",
+        "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7" => "Microsoft basic data",
+        "E3C9E316-0B5C-4DB8-817D-F92DF00215AE" => "Microsoft reserved",
+        _ => return None,
+    })
+}
+
+/// GUIDs are stored with their first three groups little endian.
+fn format_guid(bytes: &[u8]) -> String {
+    let hex = |bytes: &[u8]| -> String { bytes.iter().map(|b| format!("{:02X}", b)).collect() };
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex(&bytes[8..10]),
+        hex(&bytes[10..16])
+    )
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// A partition's node, covering its bytes, or marked invalid if they run
+/// past the largest offset.
+fn partition_node(label: String, first_lba: u64, sectors: u64, sector_len: u64) -> OutlineNode {
+    let start = first_lba.saturating_mul(sector_len);
+    let end_lba = first_lba.checked_add(sectors);
+    match end_lba.and_then(|end_lba| end_lba.checked_mul(sector_len)) {
+        Some(end) if first_lba.checked_mul(sector_len).is_some() => OutlineNode::new(
+            label,
+            format!(
+                "LBA {}..{} ({})",
+                first_lba,
+                end_lba.unwrap_or(0).saturating_sub(1),
+                human_size(end - start)
+            ),
+            start..end,
+        ),
+        _ => OutlineNode::new(
+            label,
+            format!("invalid: {} sectors from LBA {}", sectors, first_lba),
+            start..start,
+        ),
+    }
+}
+
+fn crc_status(stored: u64, computed: u32) -> Result<String, String> {
+    if stored == computed as u64 {
+        Ok("CRC32 ok".into())
+    } else {
+        Err(format!(
+            "CRC32 mismatch: stored 0x{:08x}, computed 0x{:08x}",
+            stored, computed
+        ))
+    }
+}
+
+/// The MBR and GPT partition tables of a disk image or device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partitions {
+    /// e.g. "MBR, GPT", flagging CRC mismatches
+    pub title: String,
+    pub roots: Vec<OutlineNode>,
+}
+
+impl Partitions {
+    pub fn parse(source: &dyn ByteSource) -> Result<Partitions, String> {
+        let mut roots = vec![];
+        let mut tables = vec![];
+        let mbr = Table::read(source, 0, MBR_LEN, false, "MBR")?;
+        if mbr.bytes[510..] == [0x55, 0xaa] {
+            roots.push(parse_mbr(source, &mbr));
+            tables.push("MBR".to_string());
+        }
+        if let Some((title, gpt_roots)) = parse_gpt(source)? {
+            roots.extend(gpt_roots);
+            tables.push(title);
+        }
+        if roots.is_empty() {
+            return Err("no MBR or GPT partition table".into());
+        }
+        Ok(Partitions {
+            title: tables.join(", "),
+            roots,
+        })
+    }
+}
+
+fn mbr_partition(table: &Table, base: usize, number: usize, lba_offset: u64) -> OutlineNode {
+    let value = |label| table.get_field(base, MBR_ENTRY, label);
+    let kind = value("type");
+    let name = mbr_type_name(kind).map_or_else(|| format!("type 0x{:02x}", kind), str::to_string);
+    partition_node(
+        format!("[{}] {}", number, name),
+        lba_offset + value("first LBA"),
+        value("sectors"),
+        MBR_SECTOR_LEN,
+    )
+    .with_children(table.fields(base, MBR_ENTRY))
+}
+
+fn parse_mbr(source: &dyn ByteSource, mbr: &Table) -> OutlineNode {
+    let mut partitions = vec![];
+    for i in 0..4 {
+        let base = MBR_ENTRIES_AT + i * MBR_ENTRY_LEN;
+        let kind = mbr.get_field(base, MBR_ENTRY, "type");
+        if kind == 0 {
+            continue;
+        }
+        let mut node = mbr_partition(mbr, base, i + 1, 0);
+        if is_extended(kind) {
+            let start = mbr.get_field(base, MBR_ENTRY, "first LBA");
+            node.children.extend(logical_partitions(source, start));
+        }
+        partitions.push(node);
+    }
+    OutlineNode::new("MBR", format!("{} partitions", partitions.len()), 0..MBR_LEN as u64)
+        .with_children(partitions)
+}
+
+/// Follows the chain of extended boot records. Each one describes a logical
+/// partition relative to itself and links the next relative to the extended
+/// partition's start.
+fn logical_partitions(source: &dyn ByteSource, extended_start: u64) -> Vec<OutlineNode> {
+    let mut nodes = vec![];
+    let mut ebr_lba = extended_start;
+    for _ in 0..MAX_LOGICAL_PARTITIONS {
+        let offset = ebr_lba * MBR_SECTOR_LEN;
+        let ebr = match Table::read(source, offset, MBR_LEN, false, "EBR") {
+            Ok(ebr) if ebr.bytes[510..] == [0x55, 0xaa] => ebr,
+            _ => {
+                nodes.push(OutlineNode::new(
+                    "broken chain",
+                    format!("no EBR at LBA {}", ebr_lba),
+                    offset..offset,
+                ));
+                break;
+            }
+        };
+        if ebr.get_field(MBR_ENTRIES_AT, MBR_ENTRY, "type") != 0 {
+            // Logical partitions are numbered from 5, after the primary ones
+            nodes.push(mbr_partition(&ebr, MBR_ENTRIES_AT, nodes.len() + 5, ebr_lba));
+        }
+        let link = MBR_ENTRIES_AT + MBR_ENTRY_LEN;
+        if ebr.get_field(link, MBR_ENTRY, "type") == 0 {
+            break;
+        }
+        ebr_lba = extended_start + ebr.get_field(link, MBR_ENTRY, "first LBA");
+    }
+    nodes
+}
+
+/// The GPT header and partitions, if there's a GPT. The header is looked for
+/// at LBA 1 for both 512 and 4096 byte sectors.
+fn parse_gpt(source: &dyn ByteSource) -> Result<Option<(String, Vec<OutlineNode>)>, String> {
+    let sector_len = match [512u64, 4096]
+        .iter()
+        .find(|&&len| source.read_at(len, 8).is_ok_and(|sig| sig == GPT_SIGNATURE))
+    {
+        Some(&len) => len,
+        None => return Ok(None),
+    };
+    let header = Table::read(source, sector_len, sector_len as usize, false, "GPT header")?;
+    let value = |label| header.get_field(0, GPT_HEADER, label);
+
+    let header_len = value("header size") as usize;
+    if !(GPT_MIN_HEADER_LEN..=sector_len as usize).contains(&header_len) {
+        return Err(format!("GPT header size {} is invalid", header_len));
+    }
+    let mut crc_bytes = header.bytes[..header_len].to_vec();
+    crc_bytes[16..20].fill(0);
+    let header_crc = crc_status(value("header CRC32"), crc32(&crc_bytes));
+
+    let count = value("number of entries") as usize;
+    let entry_len = value("entry size") as usize;
+    if entry_len < GPT_MIN_ENTRY_LEN || count.saturating_mul(entry_len) > MAX_GPT_ENTRIES_LEN {
+        return Err(format!(
+            "GPT entry array of {} entries of {} bytes is invalid",
+            count, entry_len
+        ));
+    }
+    let entries = Table::read(
+        source,
+        value("entries LBA").saturating_mul(sector_len),
+        count * entry_len,
+        false,
+        "GPT entry array",
+    )?;
+    let entries_crc = crc_status(value("entries CRC32"), crc32(&entries.bytes));
+
+    let guid_node = |table: &Table, label: &str, at: usize, named: bool| {
+        let guid = format_guid(&table.bytes[at..at + 16]);
+        let text = match gpt_type_name(&guid).filter(|_| named) {
+            Some(name) => format!("{} ({})", name, guid),
+            None => guid,
+        };
+        let start = table.offset + at as u64;
+        OutlineNode::new(label, text, start..start + 16)
+    };
+
+    let mut partitions = vec![];
+    for i in 0..count {
+        let base = i * entry_len;
+        if entries.bytes[base..base + 16].iter().all(|&b| b == 0) {
+            continue;
+        }
+        let first = entries.get_field(base, GPT_ENTRY, "first LBA");
+        let last = entries.get_field(base, GPT_ENTRY, "last LBA");
+        let name_units: Vec<u16> = entries.bytes[base + 56..base + 128]
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        let name = String::from_utf16_lossy(&name_units);
+        let type_guid = guid_node(&entries, "type GUID", base, true);
+        let label = if name.is_empty() {
+            gpt_type_name(&format_guid(&entries.bytes[base..base + 16]))
+                .unwrap_or("unnamed")
+                .to_string()
+        } else {
+            name.clone()
+        };
+
+        let mut fields = vec![type_guid, guid_node(&entries, "unique GUID", base + 16, false)];
+        fields.extend(entries.fields(base, GPT_ENTRY));
+        let name_start = entries.offset + base as u64 + 56;
+        fields.push(OutlineNode::new("name", name, name_start..name_start + 72));
+        partitions.push(
+            partition_node(
+                format!("[{}] {}", i + 1, label),
+                first,
+                last.checked_add(1).map_or(u64::MAX, |end| end.saturating_sub(first)),
+                sector_len,
+            )
+            .with_children(fields),
+        );
+    }
+
+    let mut header_fields = vec![OutlineNode::new("signature", "EFI PART", sector_len..sector_len + 8)];
+    header_fields.extend(header.fields(0, GPT_HEADER));
+    header_fields.push(guid_node(&header, "disk GUID", 56, false));
+    let header_node = OutlineNode::new(
+        "GPT header",
+        header_crc.clone().unwrap_or_else(|e| e),
+        sector_len..sector_len + header_len as u64,
+    )
+    .with_children(header_fields);
+    let partitions_node = OutlineNode::new(
+        "GPT partitions",
+        format!(
+            "{} used of {}, {}",
+            partitions.len(),
+            count,
+            entries_crc.clone().unwrap_or_else(|e| e)
+        ),
+        entries.range(),
+    )
+    .with_children(partitions);
+
+    let mut title = "GPT".to_string();
+    if sector_len != 512 {
+        title += &format!(" ({}-byte sectors)", sector_len);
+    }
+    if header_crc.is_err() || entries_crc.is_err() {
+        title += " CRC MISMATCH";
+    }
+    Ok(Some((title, vec![header_node, partitions_node])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::tests::put;
+
+    #[test]
+    fn test_gpt() {
+        // Protective MBR, GPT header at LBA 1, four entries at LBA 2
+        let mut disk = vec![0u8; 4096];
+        disk[510] = 0x55;
+        disk[511] = 0xaa;
+        disk[MBR_ENTRIES_AT + 4] = 0xee;
+        put(&mut disk, MBR_ENTRIES_AT + 8, 1, 4);
+        put(&mut disk, MBR_ENTRIES_AT + 12, 7, 4);
+
+        disk[512..520].copy_from_slice(GPT_SIGNATURE);
+        put(&mut disk, 512 + 12, 92, 4);
+        put(&mut disk, 512 + 72, 2, 8);
+        put(&mut disk, 512 + 80, 4, 4);
+        put(&mut disk, 512 + 84, 128, 4);
+        let entry = 1024;
+        disk[entry..entry + 16].copy_from_slice(&[
+            0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47,
+            0x7d, 0xe4,
+        ]);
+        put(&mut disk, entry + 32, 4, 8);
+        put(&mut disk, entry + 40, 7, 8);
+        disk[entry + 56] = b'r';
+        disk[entry + 58] = b'o';
+
+        let entries_crc = crc32(&disk[1024..1024 + 4 * 128]);
+        put(&mut disk, 512 + 88, entries_crc as u64, 4);
+        let header_crc = crc32(&disk[512..512 + 92]);
+        put(&mut disk, 512 + 16, header_crc as u64, 4);
+
+        let partitions = Partitions::parse(&disk).unwrap();
+        assert_eq!(partitions.title, "MBR, GPT");
+        assert_eq!(partitions.roots[0].children[0].label, "[1] GPT protective");
+        let gpt = &partitions.roots[2];
+        assert_eq!(gpt.value, "1 used of 4, CRC32 ok");
+        assert_eq!(gpt.children[0].label, "[1] ro");
+        assert_eq!(gpt.children[0].range, 2048..4096);
+        assert_eq!(
+            gpt.children[0].children[0].value,
+            "Linux filesystem (0FC63DAF-8483-4772-8E79-3D69D8477DE4)"
+        );
+
+        disk[entry + 40] = 8;
+        let partitions = Partitions::parse(&disk).unwrap();
+        assert_eq!(partitions.title, "MBR, GPT CRC MISMATCH");
+        assert_eq!(partitions.roots[1].value, "CRC32 ok");
+
+        put(&mut disk, entry + 40, u64::MAX, 8);
+        let partitions = Partitions::parse(&disk).unwrap();
+        assert!(partitions.roots[2].children[0].value.starts_with("invalid"));
+    }
+
+    #[test]
+    fn test_mbr_logical_partitions() {
+        let mut disk = vec![0u8; 8 * 512];
+        for lba in [0, 2, 5] {
+            disk[lba * 512 + 510] = 0x55;
+            disk[lba * 512 + 511] = 0xaa;
+        }
+        // Extended partition at LBA 2, holding logical partitions at LBA 3 and 6
+        disk[MBR_ENTRIES_AT + 4] = 0x0f;
+        put(&mut disk, MBR_ENTRIES_AT + 8, 2, 4);
+        put(&mut disk, MBR_ENTRIES_AT + 12, 6, 4);
+        let ebr = 2 * 512 + MBR_ENTRIES_AT;
+        disk[ebr + 4] = 0x83;
+        put(&mut disk, ebr + 8, 1, 4);
+        put(&mut disk, ebr + 12, 2, 4);
+        disk[ebr + 16 + 4] = 0x05;
+        put(&mut disk, ebr + 16 + 8, 3, 4);
+        let ebr = 5 * 512 + MBR_ENTRIES_AT;
+        disk[ebr + 4] = 0x82;
+        put(&mut disk, ebr + 8, 1, 4);
+        put(&mut disk, ebr + 12, 2, 4);
+
+        let partitions = Partitions::parse(&disk).unwrap();
+        assert_eq!(partitions.title, "MBR");
+        let extended = &partitions.roots[0].children[0];
+        let logical: Vec<_> = extended.children[MBR_ENTRY.len()..]
+            .iter()
+            .map(|node| (node.label.as_str(), node.range.clone()))
+            .collect();
+        assert_eq!(
+            logical,
+            [("[5] Linux", 1536..2560), ("[6] Linux swap", 3072..4096)]
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Yank registers, shared by every buffer of a session.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use crate::byte_rope::Rope;
+use crate::encoding::parse_hex;
+use crate::state;
+
+/// Selections at least this long are kept in a file rather than in memory.
+pub const SPILL_LEN: usize = 16 << 20;
+
+#[derive(Debug)]
+enum Contents {
+    Bytes(Vec<u8>),
+    /// Kept in the file `name` of the spill directory
+    Spilled { name: String, len: usize },
+}
+
+/// Where the registers themselves are kept.
+#[derive(Debug)]
+enum Store {
+    Memory(HashMap<char, Vec<Contents>>),
+    /// One file per register in the spill directory, named by the register's
+    /// code point in hex, with one selection per line as `<hex>` or
+    /// `@<file>`. Every instance reads and writes them, so they share the
+    /// registers.
+    Files,
+}
+
+/// One list of selections per register name. Uppercase names mean the
+/// lowercase register.
+///
+/// With `TOFU_PERSIST_REGISTERS` set, registers are kept in the state
+/// directory and survive restarting tofu; otherwise large ones spill to a
+/// temporary directory removed on exit.
+#[derive(Debug)]
+pub struct Registers {
+    store: Store,
+    spill_dir: PathBuf,
+    /// Selections at least this long are spilled
+    spill_len: usize,
+    /// Numbers the spill files this instance writes
+    next_spill: usize,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers::new()
+    }
+}
+
+impl Registers {
+    pub fn new() -> Registers {
+        let persistent = env::var_os("TOFU_PERSIST_REGISTERS").is_some_and(|x| !x.is_empty());
+        match state::state_dir() {
+            Some(dir) if persistent => Registers::load(dir.join("registers.d"), SPILL_LEN),
+            _ => Registers {
+                store: Store::Memory(HashMap::new()),
+                spill_dir: env::temp_dir().join(format!("tofu-registers-{}", std::process::id())),
+                spill_len: SPILL_LEN,
+                next_spill: 0,
+            },
+        }
+    }
+
+    /// Registers kept in files in `dir`, see `Store::Files`.
+    fn load(dir: PathBuf, spill_len: usize) -> Registers {
+        Registers {
+            store: Store::Files,
+            spill_dir: dir,
+            spill_len,
+            next_spill: 0,
+        }
+    }
+
+    fn register_path(&self, name: char) -> PathBuf {
+        self.spill_dir.join(format!("{:x}", name as u32))
+    }
+
+    /// The selections of lowercase register `name`.
+    fn contents(&self, name: char) -> Option<Vec<Contents>> {
+        let registers = match &self.store {
+            Store::Memory(registers) => {
+                return registers.get(&name).map(|contents| {
+                    contents
+                        .iter()
+                        .map(|contents| match contents {
+                            Contents::Bytes(bytes) => Contents::Bytes(bytes.clone()),
+                            Contents::Spilled { name, len } => {
+                                Contents::Spilled { name: name.clone(), len: *len }
+                            }
+                        })
+                        .collect()
+                })
+            }
+            Store::Files => fs::read_to_string(self.register_path(name)).ok()?,
+        };
+        let mut contents = Vec::new();
+        for line in registers.lines() {
+            contents.push(match line.strip_prefix('@') {
+                Some(file) => Contents::Spilled {
+                    name: file.to_string(),
+                    len: fs::metadata(self.spill_dir.join(file)).map_or(0, |m| m.len() as usize),
+                },
+                None => Contents::Bytes(parse_hex(line).unwrap_or_default()),
+            });
+        }
+        Some(contents)
+    }
+
+    /// Replaces lowercase register `name`, or removes it, and then the files
+    /// of the old contents that the new ones don't use.
+    fn store(&mut self, name: char, contents: Option<Vec<Contents>>) -> io::Result<()> {
+        let old = self.contents(name).unwrap_or_default();
+        let kept: HashSet<&str> = contents.iter().flatten().filter_map(spill_name).collect();
+        let unused: Vec<PathBuf> = old
+            .iter()
+            .filter_map(spill_name)
+            .filter(|file| !kept.contains(file))
+            .map(|file| self.spill_dir.join(file))
+            .collect();
+
+        match &mut self.store {
+            Store::Memory(registers) => match contents {
+                Some(contents) => {
+                    registers.insert(name, contents);
+                }
+                None => {
+                    registers.remove(&name);
+                }
+            },
+            Store::Files => {
+                let path = self.register_path(name);
+                match contents {
+                    Some(contents) => {
+                        let mut text = String::new();
+                        for contents in &contents {
+                            match contents {
+                                Contents::Bytes(bytes) => {
+                                    text.extend(bytes.iter().map(|byte| format!("{:02x}", byte)))
+                                }
+                                Contents::Spilled { name, .. } => text += &format!("@{}", name),
+                            }
+                            text.push('\n');
+                        }
+                        // Other instances may be reading it
+                        let temp = format!(".{:x}-{}", name as u32, std::process::id());
+                        let temp = self.spill_dir.join(temp);
+                        fs::create_dir_all(&self.spill_dir)?;
+                        fs::write(&temp, text)?;
+                        fs::rename(temp, path)?;
+                    }
+                    None => match fs::remove_file(path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        for file in unused {
+            let _ = fs::remove_file(file);
+        }
+        Ok(())
+    }
+
+    /// Stores `selections` in register `name`, or appends them to the
+    /// lowercase register when `name` is uppercase.
+    pub fn set(&mut self, name: char, selections: Vec<Rope>) {
+        let append = name.is_ascii_uppercase();
+        let name = name.to_ascii_lowercase();
+        let mut contents = if append {
+            self.contents(name).unwrap_or_default()
+        } else {
+            vec![]
+        };
+        for (i, rope) in selections.into_iter().enumerate() {
+            match contents.get_mut(i) {
+                Some(old) => *old = self.append(name, old, &rope),
+                None => {
+                    let new = self.spill(name, &[], &rope);
+                    contents.push(new);
+                }
+            }
+        }
+        let _ = self.store(name, Some(contents));
+    }
+
+    /// Empties register `name`, or its lowercase register.
+    pub fn clear(&mut self, name: char) {
+        let _ = self.store(name.to_ascii_lowercase(), None);
+    }
+
+    /// `old` followed by `rope`. Spilled selections grow in place.
+    fn append(&mut self, name: char, old: &Contents, rope: &Rope) -> Contents {
+        match old {
+            Contents::Bytes(bytes) => self.spill(name, bytes, rope),
+            Contents::Spilled { name: file, len } => {
+                let appended = OpenOptions::new()
+                    .append(true)
+                    .open(self.spill_dir.join(file))
+                    .and_then(|file| write_chunks(BufWriter::new(file), &[], rope));
+                match appended {
+                    Ok(()) => Contents::Spilled {
+                        name: file.clone(),
+                        len: *len + rope.len(),
+                    },
+                    Err(_) => Contents::Spilled { name: file.clone(), len: *len },
+                }
+            }
+        }
+    }
+
+    /// `start` followed by `rope`, in a new file if it's long enough, or in
+    /// memory if that fails.
+    fn spill(&mut self, name: char, start: &[u8], rope: &Rope) -> Contents {
+        let len = start.len() + rope.len();
+        if len < self.spill_len {
+            let mut bytes = start.to_vec();
+            bytes.extend(Vec::from(rope));
+            return Contents::Bytes(bytes);
+        }
+        if let Ok((file, created)) = self.create_spill_file(name) {
+            if write_chunks(BufWriter::new(created), start, rope).is_ok() {
+                return Contents::Spilled { name: file, len };
+            }
+            let _ = fs::remove_file(self.spill_dir.join(file));
+        }
+        let mut bytes = start.to_vec();
+        bytes.extend(Vec::from(rope));
+        Contents::Bytes(bytes)
+    }
+
+    /// A new, empty spill file, named so other instances sharing the
+    /// directory never write or remove it by accident.
+    fn create_spill_file(&mut self, name: char) -> io::Result<(String, File)> {
+        fs::create_dir_all(&self.spill_dir)?;
+        loop {
+            let file = format!("{:x}-{}-{}", name as u32, std::process::id(), self.next_spill);
+            self.next_spill += 1;
+            match OpenOptions::new().write(true).create_new(true).open(self.spill_dir.join(&file)) {
+                Ok(created) => return Ok((file, created)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Contents of register `name`; spilled selections that can no longer be
+    /// read come back empty.
+    pub fn get(&self, name: char) -> Option<Vec<Rope>> {
+        let contents = self.contents(name.to_ascii_lowercase())?;
+        Some(
+            contents
+                .into_iter()
+                .map(|contents| match contents {
+                    Contents::Bytes(bytes) => Rope::from(bytes),
+                    Contents::Spilled { name, .. } => File::open(self.spill_dir.join(name))
+                        .and_then(Rope::read_from)
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Up to `len` bytes from the start of a selection, and its length.
+    fn preview(&self, contents: &Contents, len: usize) -> (Vec<u8>, usize) {
+        match contents {
+            Contents::Bytes(bytes) => (bytes.iter().take(len).copied().collect(), bytes.len()),
+            Contents::Spilled { name, len: total } => {
+                let mut start = Vec::new();
+                if let Ok(file) = File::open(self.spill_dir.join(name)) {
+                    let _ = file.take(len as u64).read_to_end(&mut start);
+                }
+                (start, *total)
+            }
+        }
+    }
+
+    fn names(&self) -> Vec<char> {
+        let mut names: Vec<char> = match &self.store {
+            Store::Memory(registers) => registers.keys().copied().collect(),
+            Store::Files => fs::read_dir(&self.spill_dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| {
+                    let file = entry.ok()?.file_name().into_string().ok()?;
+                    char::from_u32(u32::from_str_radix(&file, 16).ok()?)
+                })
+                .collect(),
+        };
+        names.sort_unstable();
+        names
+    }
+
+    /// A hex preview of the first `preview_len` bytes of every register, with
+    /// how many more selections it holds.
+    pub fn describe(&self, preview_len: usize) -> String {
+        let names = self.names();
+        if names.is_empty() {
+            return "no registers".to_string();
+        }
+        names
+            .iter()
+            .map(|&name| {
+                let contents = self.contents(name).unwrap_or_default();
+                let (start, len) = contents
+                    .first()
+                    .map(|first| self.preview(first, preview_len))
+                    .unwrap_or_default();
+                let mut preview = start
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if len > preview_len {
+                    preview += "..";
+                }
+                match contents.len() {
+                    0 | 1 => format!("\"{} {}", name, preview),
+                    n => format!("\"{} {} (+{})", name, preview, n - 1),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+fn spill_name(contents: &Contents) -> Option<&str> {
+    match contents {
+        Contents::Spilled { name, .. } => Some(name),
+        Contents::Bytes(_) => None,
+    }
+}
+
+fn write_chunks(mut writer: BufWriter<File>, start: &[u8], rope: &Rope) -> io::Result<()> {
+    writer.write_all(start)?;
+    for chunk in rope.iter_chunks(..) {
+        writer.write_all(chunk)?;
+    }
+    writer.flush()
+}
+
+impl Drop for Registers {
+    fn drop(&mut self) {
+        if let Store::Memory(_) = self.store {
+            let _ = fs::remove_dir_all(&self.spill_dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(registers: &Registers, name: char) -> Option<Vec<Vec<u8>>> {
+        registers
+            .get(name)
+            .map(|selections| selections.iter().map(Vec::from).collect())
+    }
+
+    #[test]
+    fn test_registers() {
+        let dir = env::temp_dir().join(format!("tofu-test-registers-{}", std::process::id()));
+        let mut registers = Registers::load(dir.clone(), 4);
+        registers.set('a', vec![b"ab".to_vec().into(), Rope::default()]);
+        registers.set('A', vec![b"cd".to_vec().into(), b"d".to_vec().into(), b"e".to_vec().into()]);
+        registers.set('A', vec![b"f".to_vec().into()]);
+        registers.set('"', vec![vec![7; 6].into()]);
+        assert_eq!(get(&registers, 'A'), Some(vec![b"abcdf".to_vec(), b"d".to_vec(), b"e".to_vec()]));
+        assert_eq!(registers.describe(2), "\"\" 07 07..  \"a 61 62.. (+2)");
+
+        // Another instance sees the same registers and keeps its own files
+        let mut other = Registers::load(dir.clone(), 4);
+        assert_eq!(get(&other, 'a'), get(&registers, 'a'));
+        other.set('b', vec![vec![8; 5].into()]);
+        registers.set('a', vec![vec![9; 4].into()]);
+        registers.set('"', vec![]);
+        assert_eq!(get(&other, '"'), Some(vec![]));
+        assert_eq!(get(&other, 'a'), Some(vec![vec![9; 4]]));
+        assert_eq!(get(&registers, 'b'), Some(vec![vec![8; 5]]));
+        assert_eq!(get(&registers, 'c'), None);
+        // Replaced spill files are gone: three registers and two spilled selections
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 5);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -180,6 +180,32 @@ impl Selection {
         self.regions.iter()
     }
 
+    /// Adds `region` as the main selection, merged with any selections it
+    /// overlaps.
+    pub fn add(&mut self, region: SelRegion) {
+        let (mut min, mut max) = (region.min(), region.max());
+        self.regions.retain(|other| {
+            let overlaps = other.max() >= min && other.min() <= max;
+            if overlaps {
+                min = cmp::min(min, other.min());
+                max = cmp::max(max, other.max());
+            }
+            !overlaps
+        });
+        for other in self.regions.iter_mut() {
+            other.main = false;
+        }
+        let mut merged = SelRegion::new(max, min).with_direction(region.caret < region.tail);
+        merged.main = true;
+        self.main_selection = self.regions.partition_point(|other| other.max() < min);
+        self.regions.insert(self.main_selection, merged);
+    }
+
+    pub fn replace_main(&mut self, region: SelRegion) {
+        self.regions.remove(self.main_selection);
+        self.add(region);
+    }
+
     pub fn select_next(&mut self, count: usize) {
         self.regions[self.main_selection].main = false;
         self.main_selection = (self.main_selection + count) % self.regions.len();
@@ -195,6 +221,56 @@ impl Selection {
     }
 }
 
+/// Regions as `tail..caret`, comma separated, the main one starting with `*`.
+impl std::fmt::Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, region) in self.regions.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let main = if i == self.main_selection { "*" } else { "" };
+            write!(f, "{}{}{}", separator, main, region)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Selection {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Selection, String> {
+        let error = || format!("not a selection: {}", text);
+        let mut selection = Selection {
+            regions: vec![],
+            main_selection: 0,
+        };
+        for region in text.split(',') {
+            let (main, region) = match region.strip_prefix('*') {
+                Some(region) => (true, region),
+                None => (false, region),
+            };
+            let (tail, caret) = region.split_once("..").ok_or_else(error)?;
+            let mut region = SelRegion::new(
+                caret.parse().map_err(|_| error())?,
+                tail.parse().map_err(|_| error())?,
+            );
+            if let Some(last) = selection.regions.last() {
+                if region.min() <= last.max() {
+                    return Err(error());
+                }
+            }
+            if main {
+                region.main = true;
+                selection.main_selection = selection.regions.len();
+            }
+            selection.regions.push(region);
+        }
+        let main_count = selection.regions.iter().filter(|region| region.main).count();
+        if main_count != 1 {
+            return Err(error());
+        }
+        Ok(selection)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SelRegion {
     // Start of selection, inclusive
@@ -457,3 +533,25 @@ impl From<SelRegion> for Interval {
         (sel_region.min()..=sel_region.max()).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_region() {
+        let mut selection = Selection::new();
+        selection.add(SelRegion::new(20, 20));
+        selection.add(SelRegion::new(10, 10));
+        let regions: Vec<_> = selection.iter().map(|r| (r.tail, r.caret)).collect();
+        assert_eq!(regions, [(0, 0), (10, 10), (20, 20)]);
+        assert_eq!(selection.main_cursor_offset(), 10);
+
+        // Dragging the main cursor over the next one merges them
+        selection.replace_main(SelRegion::new(25, 10));
+        let regions: Vec<_> = selection.iter().map(|r| (r.tail, r.caret)).collect();
+        assert_eq!(regions, [(0, 0), (10, 25)]);
+        assert_eq!(selection.main_selection, 1);
+        assert!(selection.main().is_main());
+    }
+}
@@ -0,0 +1,87 @@
+//! Per-file editor state that lives outside the edited file, such as marks.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// `$TOFU_STATE_DIR`, else `$XDG_STATE_HOME/tofu`, else `~/.local/state/tofu`.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("TOFU_STATE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_STATE_HOME") {
+        return Some(PathBuf::from(dir).join("tofu"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state/tofu"))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a_extend(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_extend(FNV_OFFSET, bytes)
+}
+
+/// A hash of the size and modification time of the file at `path` and of the
+/// bytes in `window`, to tell whether it changed without reading all of it.
+pub fn fingerprint(path: &Path, window: Range<u64>) -> io::Result<u64> {
+    let mut file = fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut hash = fnv1a_extend(FNV_OFFSET, &metadata.len().to_le_bytes());
+    hash = fnv1a_extend(hash, &modified.as_nanos().to_le_bytes());
+
+    file.seek(SeekFrom::Start(window.start))?;
+    let mut window = file.take(window.end.saturating_sub(window.start));
+    let mut chunk = vec![0; 1 << 20];
+    loop {
+        match window.read(&mut chunk)? {
+            0 => return Ok(hash),
+            n => hash = fnv1a_extend(hash, &chunk[..n]),
+        }
+    }
+}
+
+/// State file of the given kind (e.g. "marks") for the file at `path`.
+/// Files are keyed by canonical path, so `path` must exist.
+pub fn state_file(path: &Path, kind: &str) -> Option<PathBuf> {
+    let canonical = path.canonicalize().ok()?;
+    let key = fnv1a(canonical.to_string_lossy().as_bytes());
+    Some(state_dir()?.join(format!("{:016x}.{}", key, kind)))
+}
+
+pub fn read_state(path: &Path, kind: &str) -> Option<String> {
+    fs::read_to_string(state_file(path, kind)?).ok()
+}
+
+/// Stores `contents` as the state of the given kind; empty contents remove it.
+pub fn write_state(path: &Path, kind: &str, contents: &str) -> io::Result<()> {
+    let file = state_file(path, kind)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+    if contents.is_empty() {
+        return match fs::remove_file(file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file, contents)
+}
+
+/// Points the state directory of the test process at a temporary directory,
+/// so tests that save state don't touch the user's.
+#[cfg(test)]
+pub fn use_test_state_dir() {
+    let dir = env::temp_dir().join(format!("tofu-test-state-{}", std::process::id()));
+    env::set_var("TOFU_STATE_DIR", dir);
+}
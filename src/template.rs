@@ -0,0 +1,508 @@
+//! Structure templates: typed layouts that are overlaid on the bytes as an
+//! outline tree.
+//!
+//! ```text
+//! // Comments run to the end of the line
+//! enum Kind : u8 { Text = 1, Image = 2 }
+//!
+//! struct Entry {
+//!     u32le offset;
+//!     u32le size;
+//! }
+//!
+//! struct Header {
+//!     char magic[4];      // text
+//!     u16be version;      // u8..u64 and i8..i64, le (default) or be
+//!     Kind kind;
+//!     u8 count;
+//!     Entry entries[count]; // length taken from an earlier field
+//!     u8 reserved[8];
+//! }
+//! ```
+//! The last struct is the one applied at the given offset.
+
+use std::collections::HashMap;
+
+use crate::byte_source::ByteSource;
+use crate::outline::{parse_int, Editor, IntFormat, OutlineNode};
+
+const MAX_DEPTH: usize = 64;
+/// Longest array whose elements vary in size, which has to be walked element
+/// by element. Other arrays are only read where their nodes are listed.
+const MAX_ARRAY_LEN: u64 = 1 << 16;
+/// Array elements beyond this are summarized instead of listed, and char
+/// arrays show this many bytes
+const MAX_ARRAY_NODES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Type {
+    Int(IntFormat),
+    Char,
+    Enum(String),
+    Struct(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Count {
+    Fixed(u64),
+    Field(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldDef {
+    name: String,
+    ty: Type,
+    count: Option<Count>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnumDef {
+    format: IntFormat,
+    variants: Vec<(String, i128)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    structs: HashMap<String, Vec<FieldDef>>,
+    enums: HashMap<String, EnumDef>,
+    root: String,
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or("");
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            if c.is_ascii_alphanumeric() || c == '_' {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push(line[start..end].to_string());
+            } else if "{}[];:=,-".contains(c) {
+                tokens.push(c.to_string());
+            } else {
+                return Err(format!("unexpected character '{}'", c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("unexpected end of template")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(format!("expected '{}', found '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        let token = self.next()?;
+        if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            Ok(token)
+        } else {
+            Err(format!("expected a name, found '{}'", token))
+        }
+    }
+
+    fn skip_semicolon(&mut self) {
+        if self.peek() == Some(";") {
+            self.pos += 1;
+        }
+    }
+
+    fn enum_def(&mut self) -> Result<(String, EnumDef), String> {
+        let name = self.identifier()?;
+        self.expect(":")?;
+        let base = self.next()?;
+        let format = IntFormat::parse(&base).ok_or(format!("enum {} needs an integer type", name))?;
+        self.expect("{")?;
+        let mut variants = vec![];
+        while self.peek() != Some("}") {
+            let variant = self.identifier()?;
+            self.expect("=")?;
+            let mut number = self.next()?;
+            if number == "-" {
+                number = format!("-{}", self.next()?);
+            }
+            variants.push((variant, parse_int(&number)?));
+            if self.peek() == Some(",") {
+                self.pos += 1;
+            }
+        }
+        self.expect("}")?;
+        self.skip_semicolon();
+        Ok((name, EnumDef { format, variants }))
+    }
+
+    fn struct_def(&mut self) -> Result<(String, Vec<FieldDef>), String> {
+        let name = self.identifier()?;
+        self.expect("{")?;
+        let mut fields = vec![];
+        while self.peek() != Some("}") {
+            let type_name = self.identifier()?;
+            let ty = match IntFormat::parse(&type_name) {
+                Some(format) => Type::Int(format),
+                None if type_name == "char" => Type::Char,
+                // Resolved once all definitions are known
+                None => Type::Struct(type_name),
+            };
+            let field_name = self.identifier()?;
+            let count = if self.peek() == Some("[") {
+                self.pos += 1;
+                let count = self.next()?;
+                self.expect("]")?;
+                Some(match parse_int(&count) {
+                    Ok(n) if n >= 0 => Count::Fixed(n as u64),
+                    _ => Count::Field(count),
+                })
+            } else {
+                None
+            };
+            self.expect(";")?;
+            fields.push(FieldDef {
+                name: field_name,
+                ty,
+                count,
+            });
+        }
+        self.expect("}")?;
+        self.skip_semicolon();
+        Ok((name, fields))
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        let mut structs = HashMap::new();
+        let mut enums = HashMap::new();
+        let mut root = None;
+        while let Some(keyword) = parser.peek() {
+            match keyword {
+                "enum" => {
+                    parser.pos += 1;
+                    let (name, def) = parser.enum_def()?;
+                    enums.insert(name, def);
+                }
+                "struct" => {
+                    parser.pos += 1;
+                    let (name, fields) = parser.struct_def()?;
+                    root = Some(name.clone());
+                    structs.insert(name, fields);
+                }
+                other => return Err(format!("expected 'struct' or 'enum', found '{}'", other)),
+            }
+        }
+
+        for fields in structs.values_mut() {
+            for field in fields.iter_mut() {
+                if let Type::Struct(name) = &field.ty {
+                    if enums.contains_key(name) {
+                        field.ty = Type::Enum(name.clone());
+                    }
+                }
+            }
+        }
+        for fields in structs.values() {
+            for field in fields {
+                if let Type::Struct(name) = &field.ty {
+                    if !structs.contains_key(name) {
+                        return Err(format!("unknown type {}", name));
+                    }
+                }
+            }
+        }
+
+        Ok(Template {
+            structs,
+            enums,
+            root: root.ok_or("template declares no struct")?,
+        })
+    }
+
+    pub fn root_name(&self) -> &str {
+        &self.root
+    }
+
+    /// Lays the root struct over `source` at `offset`.
+    pub fn apply(&self, source: &dyn ByteSource, offset: u64) -> Result<OutlineNode, String> {
+        Evaluator {
+            template: self,
+            source,
+        }
+        .eval_struct(&self.root, self.root.clone(), offset, 0)
+    }
+}
+
+struct Evaluator<'a> {
+    template: &'a Template,
+    source: &'a dyn ByteSource,
+}
+
+impl Evaluator<'_> {
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, String> {
+        self.source
+            .read_exact_at(offset, len)
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// Size of every value of `ty`, unless it depends on the data.
+    fn fixed_size(&self, ty: &Type, depth: usize) -> Option<u64> {
+        match ty {
+            _ if depth > MAX_DEPTH => None,
+            Type::Int(format) => Some(format.size as u64),
+            Type::Char => Some(1),
+            Type::Enum(name) => Some(self.template.enums[name].format.size as u64),
+            Type::Struct(name) => self.template.structs[name].iter().try_fold(0u64, |size, field| {
+                let count = match &field.count {
+                    None => 1,
+                    Some(Count::Fixed(count)) => *count,
+                    Some(Count::Field(_)) => return None,
+                };
+                size.checked_add(self.fixed_size(&field.ty, depth + 1)?.checked_mul(count)?)
+            }),
+        }
+    }
+
+    fn eval_struct(
+        &self,
+        type_name: &str,
+        label: String,
+        offset: u64,
+        depth: usize,
+    ) -> Result<OutlineNode, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("structs nested deeper than {} levels", MAX_DEPTH));
+        }
+        let fields = &self.template.structs[type_name];
+        let mut values: HashMap<&str, i128> = HashMap::new();
+        let mut children = vec![];
+        let mut pos = offset;
+        for field in fields {
+            let node = match &field.count {
+                None => self.eval_single(&field.ty, field.name.clone(), pos, depth)?,
+                Some(count) => {
+                    let len = match count {
+                        Count::Fixed(len) => *len,
+                        Count::Field(name) => match values.get(name.as_str()) {
+                            Some(&len) if len >= 0 => len as u64,
+                            Some(len) => return Err(format!("{}: negative length {}", name, len)),
+                            None => {
+                                return Err(format!(
+                                    "{}.{}: length field {} must come before it",
+                                    type_name, field.name, name
+                                ))
+                            }
+                        },
+                    };
+                    self.eval_array(field, pos, len, depth)?
+                }
+            };
+            // Integers can be used as lengths of later arrays
+            if field.count.is_none() {
+                if let Some(Editor::Int(format) | Editor::Enum(format, _)) = &node.editor {
+                    values.insert(&field.name, format.decode(&self.read(pos, format.size)?));
+                }
+            }
+            pos = node.range.end;
+            children.push(node);
+        }
+        Ok(OutlineNode::new(label, type_name, offset..pos).with_children(children))
+    }
+
+    fn eval_single(
+        &self,
+        ty: &Type,
+        label: String,
+        offset: u64,
+        depth: usize,
+    ) -> Result<OutlineNode, String> {
+        match ty {
+            Type::Int(format) => {
+                let value = format.decode(&self.read(offset, format.size)?);
+                Ok(
+                    OutlineNode::new(label, format.format(value), offset..offset + format.size as u64)
+                        .with_editor(Editor::Int(*format)),
+                )
+            }
+            Type::Char => {
+                let byte = self.read(offset, 1)?[0];
+                Ok(OutlineNode::new(label, quote(&[byte]), offset..offset + 1)
+                    .with_editor(Editor::Text(1)))
+            }
+            Type::Enum(name) => {
+                let def = &self.template.enums[name];
+                let value = def.format.decode(&self.read(offset, def.format.size)?);
+                let variant = def
+                    .variants
+                    .iter()
+                    .find(|(_, v)| *v == value)
+                    .map_or("?", |(name, _)| name.as_str());
+                Ok(OutlineNode::new(
+                    label,
+                    format!("{} ({})", variant, value),
+                    offset..offset + def.format.size as u64,
+                )
+                .with_editor(Editor::Enum(def.format, def.variants.clone())))
+            }
+            Type::Struct(name) => self.eval_struct(name, label, offset, depth + 1),
+        }
+    }
+
+    fn eval_array(
+        &self,
+        field: &FieldDef,
+        offset: u64,
+        len: u64,
+        depth: usize,
+    ) -> Result<OutlineNode, String> {
+        let too_long = || format!("{}: array of {} elements is too long", field.name, len);
+        let end = match self.fixed_size(&field.ty, depth) {
+            Some(size) => Some(
+                len.checked_mul(size)
+                    .and_then(|size| offset.checked_add(size))
+                    .ok_or_else(too_long)?,
+            ),
+            None if len > MAX_ARRAY_LEN => return Err(too_long()),
+            None => None,
+        };
+        let label = format!("{}[{}]", field.name, len);
+        let listed = std::cmp::min(len, MAX_ARRAY_NODES as u64);
+        if field.ty == Type::Char {
+            let end = offset + len;
+            let mut value = quote(&self.read(offset, listed as usize)?);
+            if len > listed {
+                self.read(end - 1, 1)?;
+                value += "..";
+            }
+            return Ok(OutlineNode::new(label, value, offset..end)
+                .with_editor(Editor::Text(len as usize)));
+        }
+
+        let mut children = vec![];
+        let mut pos = offset;
+        for i in 0..listed {
+            let element = self.eval_single(&field.ty, format!("[{}]", i), pos, depth)?;
+            pos = element.range.end;
+            children.push(element);
+        }
+        if len > listed {
+            let rest_start = pos;
+            match end {
+                // Fixed-size elements: no need to visit the rest
+                Some(end) => {
+                    pos = end;
+                    if pos > rest_start {
+                        self.read(pos - 1, 1)?;
+                    }
+                }
+                None => {
+                    for i in listed..len {
+                        pos = self.eval_single(&field.ty, format!("[{}]", i), pos, depth)?.range.end;
+                    }
+                }
+            }
+            children.push(OutlineNode::new(
+                format!("[{}..{}]", listed, len),
+                "not listed",
+                rest_start..pos,
+            ));
+        }
+        let type_name = match &field.ty {
+            Type::Int(format) => format.name(),
+            Type::Enum(name) | Type::Struct(name) => name.clone(),
+            Type::Char => unreachable!(),
+        };
+        Ok(OutlineNode::new(label, type_name, offset..pos).with_children(children))
+    }
+}
+
+fn quote(bytes: &[u8]) -> String {
+    let text: String = bytes
+        .iter()
+        .flat_map(|&b| std::ascii::escape_default(b))
+        .map(char::from)
+        .collect();
+    format!("\"{}\"", text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "
+        enum Kind : u8 { Text = 1, Image = 2 }
+        struct Entry { u16be offset; u8 size; }
+        struct Header {
+            char magic[4];
+            Kind kind;
+            u8 count;
+            Entry entries[count]; // counted
+            i16le delta;
+        }
+    ";
+
+    #[test]
+    fn test_apply_template() {
+        let template = Template::parse(TEMPLATE).unwrap();
+        assert_eq!(template.root_name(), "Header");
+
+        let data = vec![
+            0xff, b'T', b'O', b'F', b'U', 2, 2, 0x01, 0x00, 7, 0x02, 0x00, 9, 0xfe, 0xff,
+        ];
+        let root = template.apply(&data, 1).unwrap();
+        assert_eq!(root.range, 1..15);
+        let values: Vec<&str> = root.children.iter().map(|n| n.value.as_str()).collect();
+        assert_eq!(values, ["\"TOFU\"", "Image (2)", "2 (0x2)", "Entry", "-2"]);
+
+        let entries = &root.children[3];
+        assert_eq!(entries.label, "entries[2]");
+        assert_eq!(entries.children[1].children[0].value, "512 (0x200)");
+        assert_eq!(entries.children[1].children[0].range, 10..12);
+
+        assert!(template.apply(&data, 8).is_err());
+
+        // Only listed elements of long fixed-size arrays are read
+        let long = Template::parse("struct E { u16be a; u8 b; } struct L { E e[1000]; char c[300]; }");
+        let root = long.unwrap().apply(&vec![0; 3300], 0).unwrap();
+        assert_eq!(root.range, 0..3300);
+        assert_eq!(root.children[0].children.len(), MAX_ARRAY_NODES + 1);
+        assert!(root.children[1].value.ends_with("\".."));
+        let huge = Template::parse("struct H { u32le values[0x4000000000000000]; }").unwrap();
+        assert!(huge.apply(&vec![0; 16], 0).is_err());
+        assert!(Template::parse("struct A { Missing m; }").is_err());
+    }
+}
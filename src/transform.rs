@@ -0,0 +1,152 @@
+use crate::encoding::parse_hex;
+use crate::outline::{parse_int, IntFormat};
+
+const BYTE: IntFormat = IntFormat {
+    size: 1,
+    signed: false,
+    big_endian: false,
+};
+
+/// An in-place change of selected bytes. Keys start over at every selection;
+/// arithmetic works on whole values of the chosen width, leaving a shorter
+/// tail unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    Xor(Vec<u8>),
+    And(Vec<u8>),
+    Or(Vec<u8>),
+    Not,
+    /// Wraps around; subtraction adds the negated amount
+    Add(IntFormat, i128),
+    ShiftLeft(IntFormat, u32),
+    ShiftRight(IntFormat, u32),
+    RotateLeft(IntFormat, u32),
+    RotateRight(IntFormat, u32),
+    /// Reverses the order of the bytes
+    Reverse,
+}
+
+impl Transform {
+    /// Parses the arguments of the command `name`.
+    pub fn parse(name: &str, args: &str) -> Result<Transform, String> {
+        let args: Vec<&str> = args.split_whitespace().collect();
+        let key = || match parse_hex(&args.join(" "))? {
+            key if key.is_empty() => Err(format!("usage: :{} <hex key>", name)),
+            key => Ok(key),
+        };
+        // `[type] <n>`, bytes by default
+        let typed = || {
+            let (ty, n) = match args[..] {
+                [n] => (BYTE, n),
+                [ty, n] => (
+                    IntFormat::parse(ty).ok_or_else(|| format!("not an int type: {}", ty))?,
+                    n,
+                ),
+                _ => return Err(format!("usage: :{} [int type] <n>", name)),
+            };
+            Ok((ty, parse_int(n)?))
+        };
+        let bits = || {
+            let (format, bits) = typed()?;
+            match u32::try_from(bits) {
+                Ok(bits) if bits < format.size as u32 * 8 => Ok((format, bits)),
+                _ => Err(format!("can't {} a u{} by {}", name, format.size * 8, bits)),
+            }
+        };
+        let no_args = |transform| match args[..] {
+            [] => Ok(transform),
+            _ => Err(format!(":{} takes no arguments", name)),
+        };
+        Ok(match name {
+            "xor" => Transform::Xor(key()?),
+            "and" => Transform::And(key()?),
+            "or" => Transform::Or(key()?),
+            "not" => no_args(Transform::Not)?,
+            "add" => typed().map(|(format, n)| Transform::Add(format, n))?,
+            "sub" => typed().map(|(format, n)| Transform::Add(format, n.wrapping_neg()))?,
+            "shl" => bits().map(|(format, n)| Transform::ShiftLeft(format, n))?,
+            "shr" => bits().map(|(format, n)| Transform::ShiftRight(format, n))?,
+            "rol" => bits().map(|(format, n)| Transform::RotateLeft(format, n))?,
+            "ror" => bits().map(|(format, n)| Transform::RotateRight(format, n))?,
+            "reverse" => no_args(Transform::Reverse)?,
+            _ => return Err(format!("unknown transform {}", name)),
+        })
+    }
+
+    pub fn apply(&self, bytes: &[u8]) -> Vec<u8> {
+        let with_key = |key: &[u8], op: fn(u8, u8) -> u8| {
+            bytes
+                .iter()
+                .zip(key.iter().cycle())
+                .map(|(&byte, &k)| op(byte, k))
+                .collect()
+        };
+        match self {
+            Transform::Xor(key) => with_key(key, |byte, k| byte ^ k),
+            Transform::And(key) => with_key(key, |byte, k| byte & k),
+            Transform::Or(key) => with_key(key, |byte, k| byte | k),
+            Transform::Not => bytes.iter().map(|byte| !byte).collect(),
+            Transform::Add(format, n) => map_values(bytes, *format, |value| {
+                value.wrapping_add(*n as u128)
+            }),
+            Transform::ShiftLeft(format, n) => map_values(bytes, *format, |value| value << n),
+            Transform::ShiftRight(format, n) => map_values(bytes, *format, |value| value >> n),
+            Transform::RotateLeft(format, n) => map_values(bytes, *format, |value| {
+                let bits = format.size as u32 * 8;
+                (value << n) | (value >> ((bits - n) % bits))
+            }),
+            Transform::RotateRight(format, n) => map_values(bytes, *format, |value| {
+                let bits = format.size as u32 * 8;
+                (value >> n) | (value << ((bits - n) % bits))
+            }),
+            Transform::Reverse => bytes.iter().rev().copied().collect(),
+        }
+    }
+}
+
+/// Applies `f` to every whole value of `format` in `bytes`, keeping the low
+/// bits of the result.
+fn map_values(bytes: &[u8], format: IntFormat, f: impl Fn(u128) -> u128) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    for chunk in out.chunks_exact_mut(format.size) {
+        if format.big_endian {
+            chunk.reverse();
+        }
+        let value = chunk
+            .iter()
+            .rev()
+            .fold(0u128, |value, &byte| value << 8 | byte as u128);
+        chunk.copy_from_slice(&f(value).to_le_bytes()[..format.size]);
+        if format.big_endian {
+            chunk.reverse();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(name: &str, args: &str, bytes: &[u8]) -> Vec<u8> {
+        Transform::parse(name, args).unwrap().apply(bytes)
+    }
+
+    #[test]
+    fn test_transforms() {
+        assert_eq!(apply("xor", "ff 01", &[0x0f, 0x0f, 0x0f]), [0xf0, 0x0e, 0xf0]);
+        assert_eq!(apply("xor", "0xff, 0x01", &[0x0f, 0x0f]), [0xf0, 0x0e]);
+        assert_eq!(apply("not", "", &[0x0f]), [0xf0]);
+        assert_eq!(apply("add", "u16le 0x10", &[0xf8, 0xff, 0x01]), [0x08, 0x00, 0x01]);
+        assert_eq!(apply("sub", "u16be 1", &[0x01, 0x00]), [0x00, 0xff]);
+        assert_eq!(apply("sub", "3", &[1, 2]), [0xfe, 0xff]);
+        assert_eq!(apply("shl", "u16be 4", &[0x12, 0x34]), [0x23, 0x40]);
+        assert_eq!(apply("shr", "1", &[0x81]), [0x40]);
+        assert_eq!(apply("rol", "u32le 8", &[1, 2, 3, 4]), [4, 1, 2, 3]);
+        assert_eq!(apply("ror", "1", &[0x01]), [0x80]);
+        assert_eq!(apply("rol", "0", &[0x81]), [0x81]);
+        assert_eq!(apply("reverse", "", &[1, 2, 3]), [3, 2, 1]);
+        assert!(Transform::parse("shl", "8").is_err());
+        assert!(Transform::parse("xor", "").is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use std::cmp;
+use std::ops::Range;
+
+use xi_rope::Transformer;
+
+use crate::byte_rope::{RopeDelta, RopeInfo};
+
+/// An edit of the loaded window, which starts at file offset `window_start`
+/// and was `window_len` bytes long before the edit, seen in file offsets.
+pub struct WindowEdit<'a> {
+    transformer: Transformer<'a, RopeInfo>,
+    window_start: usize,
+    window_len: usize,
+    new_window_len: usize,
+}
+
+impl<'a> WindowEdit<'a> {
+    pub fn new(delta: &'a RopeDelta, window_start: usize, window_len: usize) -> Self {
+        WindowEdit {
+            transformer: Transformer::new(delta),
+            window_start,
+            window_len,
+            new_window_len: delta.new_document_len(),
+        }
+    }
+
+    /// Where the byte at `offset` ends up. Bytes before the window stay,
+    /// bytes after it shift by the change in length.
+    pub fn transform(&mut self, offset: usize) -> usize {
+        if offset < self.window_start {
+            return offset;
+        }
+        let relative = offset - self.window_start;
+        self.window_start
+            + if relative < self.window_len {
+                cmp::min(
+                    self.transformer.transform(relative, true),
+                    self.new_window_len.saturating_sub(1),
+                )
+            } else {
+                relative + self.new_window_len - self.window_len
+            }
+    }
+
+    /// Where the bytes of `range` end up, without the bytes inserted at its
+    /// ends. Empty if they were all deleted.
+    pub fn transform_range(&mut self, range: Range<usize>) -> Range<usize> {
+        let start = self.transform_boundary(range.start, true);
+        let end = self.transform_boundary(range.end, false);
+        start..cmp::max(start, end)
+    }
+
+    fn transform_boundary(&mut self, offset: usize, after: bool) -> usize {
+        if offset < self.window_start {
+            return offset;
+        }
+        let relative = offset - self.window_start;
+        self.window_start
+            + if relative <= self.window_len {
+                self.transformer.transform(relative, after)
+            } else {
+                relative + self.new_window_len - self.window_len
+            }
+    }
+}